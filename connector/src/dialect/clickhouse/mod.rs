@@ -152,6 +152,7 @@ impl ClickhouseConnection {
         r#type: row.r#type,
         schema: None,
         size: row.total_bytes,
+        comment: None,
       });
     }
     Ok(tables)
@@ -175,6 +176,7 @@ impl ClickhouseConnection {
         database,
         table,
         columns: columns.clone(),
+        comments: Default::default(),
       });
     }
     Ok(metadata)
@@ -213,6 +215,8 @@ impl ClickhouseConnection {
       .map(|m| Title {
         name: m.name,
         r#type: m.r#type,
+        is_json: false,
+        semantic_type: None,
       })
       .collect::<Vec<_>>();
 
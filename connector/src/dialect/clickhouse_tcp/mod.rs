@@ -140,6 +140,7 @@ impl ClickhouseConnection {
         r#type,
         schema: None,
         size,
+        comment: None,
       });
     }
     Ok(tables)
@@ -170,7 +171,7 @@ impl ClickhouseConnection {
     let b = batchs[0].clone();
     let schema = b.schema();
     let batch = arrow::compute::concat_batches(&schema, &batchs)?;
-    write_csv(file, &batch)?;
+    write_csv(file, &batch, "")?;
     Ok(())
   }
 
@@ -192,6 +193,8 @@ impl ClickhouseConnection {
           .map(|c| Title {
             name: c.name().to_string(),
             r#type: c.sql_type().to_string().into(),
+            is_json: false,
+            semantic_type: None,
           })
           .collect();
       }
@@ -220,6 +223,8 @@ impl ClickhouseConnection {
       .map(|c| Title {
         name: c.name().to_string(),
         r#type: c.sql_type().to_string().into(),
+        is_json: false,
+        semantic_type: None,
       })
       .collect();
     let batch = type_arrow::block_to_arrow(&block)?;
@@ -259,6 +264,8 @@ impl ClickhouseConnection {
           .map(|c| Title {
             name: c.name().to_string(),
             r#type: c.sql_type().to_string().into(),
+            is_json: false,
+            semantic_type: None,
           })
           .collect();
       }
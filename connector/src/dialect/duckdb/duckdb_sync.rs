@@ -71,6 +71,7 @@ impl DuckDbSyncConnection {
         database,
         table,
         columns,
+        comments: Default::default(),
       })
       .collect();
     Ok(metadata)
@@ -107,6 +108,7 @@ impl DuckDbSyncConnection {
           table_type: row.get(3)?,
           r#type: row.get(4)?,
           size: None,
+          comment: None,
         })
       })?
       .flatten()
@@ -139,6 +141,8 @@ impl DuckDbSyncConnection {
       .map(|(i, name)| Title {
         name: name.clone(),
         r#type: stmt.column_type(i).to_string(),
+        is_json: false,
+        semantic_type: None,
       })
       .collect();
 
@@ -170,6 +174,8 @@ pub fn query(conn: &duckdb::Connection, sql: &str) -> anyhow::Result<RawArrowDat
     .map(|(i, name)| Title {
       name: name.clone(),
       r#type: stmt.column_type(i).to_string(),
+      is_json: false,
+      semantic_type: None,
     })
     .collect();
 
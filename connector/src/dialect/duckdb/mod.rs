@@ -102,7 +102,7 @@ impl Connection for DuckDbConnection {
 
   async fn export(&self, sql: &str, file: &str) -> anyhow::Result<()> {
     let batch = self.connect()?.query_arrow(sql)?;
-    write_csv(file, &batch)?;
+    write_csv(file, &batch, "")?;
     Ok(())
   }
 }
@@ -91,7 +91,7 @@ impl Connection for FolderConnection {
   async fn export(&self, sql: &str, file: &str) -> anyhow::Result<()> {
     let conn = self.connect()?;
     let batch = duckdb_sync::query(&conn, sql)?.batch;
-    write_csv(file, &batch)?;
+    write_csv(file, &batch, "")?;
     Ok(())
   }
 
@@ -195,6 +195,7 @@ impl FolderConnection {
           database: String::new(),
           table: format!("./{}", path),
           columns: vec![],
+          comments: Default::default(),
         });
     }
     Ok(data)
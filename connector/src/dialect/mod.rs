@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use std::collections::HashMap;
 
-use crate::utils::{Metadata, RawArrowData};
+use crate::utils::{ExecResult, Metadata, RawArrowData};
 use crate::dialect::ast::first_stmt;
 use crate::utils::TreeNode;
 
@@ -18,10 +18,29 @@ pub mod sqlite;
 #[async_trait]
 pub trait Connection: Sync + Send {
   async fn get_db(&self) -> anyhow::Result<TreeNode>;
+
+  /// Check that the server is reachable and the credentials are accepted,
+  /// without loading anything.
+  async fn test_connection(&self) -> anyhow::Result<()> {
+    self.get_db().await.map(|_| ())
+  }
+
   async fn query(&self, _sql: &str, _limit: usize, _offset: usize) -> anyhow::Result<RawArrowData> {
     unimplemented!()
   }
 
+  /// Like `query`, with `?` placeholders in `sql` bound to `params` by the
+  /// server instead of being spliced into the text.
+  async fn query_with_params(
+    &self,
+    _sql: &str,
+    _params: Vec<serde_json::Value>,
+    _limit: usize,
+    _offset: usize,
+  ) -> anyhow::Result<RawArrowData> {
+    unimplemented!()
+  }
+
   async fn query_count(&self, _sql: &str) -> anyhow::Result<usize> {
     unimplemented!()
   }
@@ -100,6 +119,16 @@ pub trait Connection: Sync + Send {
     unimplemented!()
   }
 
+  /// One row per index column of `table`: index name, column name, position
+  /// in the index, uniqueness and index type.
+  async fn show_indexes(
+    &self,
+    _schema: Option<&str>,
+    _table: &str,
+  ) -> anyhow::Result<RawArrowData> {
+    unimplemented!()
+  }
+
   async fn all_columns(&self) -> anyhow::Result<Vec<Metadata>> {
     Ok(vec![])
   }
@@ -147,7 +176,8 @@ pub trait Connection: Sync + Send {
   async fn find(&self, value: &str, path: &str) -> anyhow::Result<RawArrowData> {
     unimplemented!()
   }
-  async fn execute(&self, sql: &str) -> anyhow::Result<usize> {
+  /// Run a statement such as `INSERT` that returns no rows.
+  async fn execute(&self, sql: &str) -> anyhow::Result<ExecResult> {
     unimplemented!()
   }
 }
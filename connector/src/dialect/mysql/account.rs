@@ -0,0 +1,100 @@
+use mysql::prelude::*;
+
+use super::MySqlConnection;
+
+/// Split an account name, either `user@host` as returned by
+/// `CURRENT_USER()` or the quoted `'user'@'host'` form used in grantees.
+pub(crate) fn split_user_host(account: &str) -> Option<(String, String)> {
+  let account = account.trim();
+  if let Some(rest) = account.strip_prefix('\'') {
+    let (user, host) = rest.split_once("'@'")?;
+    let host = host.strip_suffix('\'')?;
+    return Some((user.to_string(), host.to_string()));
+  }
+  let (user, host) = account.rsplit_once('@')?;
+  Some((user.to_string(), host.to_string()))
+}
+
+/// `user` and `host` quoted the way `information_schema` lists grantees,
+/// with `'` doubled, e.g. `'o''brien'@'%'`.
+pub(crate) fn grantee(user: &str, host: &str) -> String {
+  format!(
+    "'{}'@'{}'",
+    user.replace('\'', "''"),
+    host.replace('\'', "''")
+  )
+}
+
+impl MySqlConnection {
+  fn _current_user(&self) -> anyhow::Result<String> {
+    let mut conn = self.get_conn()?;
+    let user: Option<String> = conn.query_first("SELECT CURRENT_USER()")?;
+    user.ok_or_else(|| anyhow::anyhow!("server returned no current user"))
+  }
+
+  /// The account the server authenticated us as, `user@host`.
+  pub async fn current_user(&self) -> anyhow::Result<String> {
+    self._current_user()
+  }
+
+  /// The authenticated account split into user and host pattern.
+  pub async fn current_user_host(&self) -> anyhow::Result<(String, String)> {
+    let account = self._current_user()?;
+    split_user_host(&account).ok_or_else(|| anyhow::anyhow!("unexpected account name: {account}"))
+  }
+
+  /// Whether the current account holds the global SUPER privilege, needed
+  /// to e.g. kill other users' threads or skip binary logging.
+  pub async fn super_privilege(&self) -> anyhow::Result<bool> {
+    let (user, host) = self.current_user_host().await?;
+    let mut conn = self.get_conn()?;
+    let count: Option<u64> = conn.exec_first(
+      r"
+      select count(*) from information_schema.USER_PRIVILEGES
+      where GRANTEE = ? and PRIVILEGE_TYPE = 'SUPER'
+      ",
+      (grantee(&user, &host),),
+    )?;
+    Ok(count.unwrap_or_default() > 0)
+  }
+
+  /// `SHOW GRANTS` for the current account, one statement per entry.
+  pub async fn show_grants(&self) -> anyhow::Result<Vec<String>> {
+    let mut conn = self.get_conn()?;
+    Ok(conn.query("SHOW GRANTS")?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dialect::mysql::local_connection;
+
+  #[test]
+  fn test_split_user_host() {
+    let pair = |u: &str, h: &str| Some((u.to_string(), h.to_string()));
+    assert_eq!(split_user_host("'root'@'%'"), pair("root", "%"));
+    assert_eq!(split_user_host("'app'@'10.0.0.1'"), pair("app", "10.0.0.1"));
+    assert_eq!(split_user_host("root@localhost"), pair("root", "localhost"));
+    assert_eq!(split_user_host("root"), None);
+  }
+
+  #[test]
+  fn test_grantee() {
+    assert_eq!(grantee("root", "%"), "'root'@'%'");
+    assert_eq!(grantee("o'brien", "10.0.0.1"), "'o''brien'@'10.0.0.1'");
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_current_user() {
+    let conn = local_connection();
+    let user = conn.current_user().await.unwrap();
+    assert!(user.starts_with(&format!("{}@", conn.username)));
+    let (user_name, _) = conn.current_user_host().await.unwrap();
+    assert_eq!(user_name, conn.username);
+    conn.super_privilege().await.unwrap();
+    let grants = conn.show_grants().await.unwrap();
+    assert!(grants.iter().any(|g| g.starts_with("GRANT")));
+  }
+}
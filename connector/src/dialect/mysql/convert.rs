@@ -0,0 +1,1339 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock};
+
+use arrow::array::*;
+use arrow::compute::cast;
+use arrow::datatypes::{DECIMAL128_MAX_PRECISION, DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use mysql::{Value, from_value, from_value_opt};
+
+use crate::utils::SemanticType;
+
+/// Builds one Arrow array from a column of MySQL values; `NULL` and values
+/// that do not convert become null slots.
+pub(crate) trait ArrowColumnBuilder: Send + Sync {
+  fn build(&self, values: &[Value]) -> ArrayRef;
+}
+
+struct Int64Column;
+struct BooleanColumn;
+struct Int32Column;
+struct UInt64Column;
+struct Float64Column;
+struct Utf8Column;
+struct BinaryColumn;
+struct SetColumn;
+struct Date32Column;
+struct TimestampColumn;
+struct DurationColumn;
+
+impl ArrowColumnBuilder for Int64Column {
+  fn build(&self, values: &[Value]) -> ArrayRef {
+    Arc::new(Int64Array::from(convert_to_i64_arr(values)))
+  }
+}
+
+impl ArrowColumnBuilder for BooleanColumn {
+  fn build(&self, values: &[Value]) -> ArrayRef {
+    Arc::new(BooleanArray::from(convert_to_bool_arr(values)))
+  }
+}
+
+impl ArrowColumnBuilder for Int32Column {
+  fn build(&self, values: &[Value]) -> ArrayRef {
+    Arc::new(Int32Array::from(convert_to_i32_arr(values)))
+  }
+}
+
+impl ArrowColumnBuilder for UInt64Column {
+  fn build(&self, values: &[Value]) -> ArrayRef {
+    Arc::new(UInt64Array::from(convert_to_u64_arr(values)))
+  }
+}
+
+impl ArrowColumnBuilder for Float64Column {
+  fn build(&self, values: &[Value]) -> ArrayRef {
+    Arc::new(Float64Array::from(convert_to_f64_arr(values)))
+  }
+}
+
+impl ArrowColumnBuilder for Utf8Column {
+  fn build(&self, values: &[Value]) -> ArrayRef {
+    Arc::new(StringArray::from(convert_to_str_arr(values)))
+  }
+}
+
+impl ArrowColumnBuilder for BinaryColumn {
+  fn build(&self, values: &[Value]) -> ArrayRef {
+    Arc::new(BinaryArray::from(convert_to_binary_arr(values)))
+  }
+}
+
+impl ArrowColumnBuilder for SetColumn {
+  fn build(&self, values: &[Value]) -> ArrayRef {
+    Arc::new(convert_to_set_arr(values))
+  }
+}
+
+impl ArrowColumnBuilder for Date32Column {
+  fn build(&self, values: &[Value]) -> ArrayRef {
+    Arc::new(Date32Array::from(convert_to_date32_arr(values)))
+  }
+}
+
+impl ArrowColumnBuilder for TimestampColumn {
+  fn build(&self, values: &[Value]) -> ArrayRef {
+    Arc::new(TimestampMicrosecondArray::from(convert_to_timestamp_arr(
+      values,
+    )))
+  }
+}
+
+impl ArrowColumnBuilder for DurationColumn {
+  fn build(&self, values: &[Value]) -> ArrayRef {
+    Arc::new(DurationMicrosecondArray::from(convert_to_duration_arr(
+      values,
+    )))
+  }
+}
+
+/// Fixed-point column; its precision and scale are part of the Arrow type,
+/// so it is built by `build_column` rather than looked up in `BUILDERS`.
+struct DecimalColumn {
+  precision: u8,
+  scale: i8,
+}
+
+impl ArrowColumnBuilder for DecimalColumn {
+  fn build(&self, values: &[Value]) -> ArrayRef {
+    let arr = Decimal128Array::from(convert_to_decimal128_arr(values, self.scale))
+      .with_precision_and_scale(self.precision, self.scale)
+      .expect("precision and scale come from a Decimal128 type");
+    Arc::new(arr)
+  }
+}
+
+/// Arrow type of DECIMAL columns, or `None` beyond what Decimal128 holds.
+pub(crate) fn decimal_type(precision: u32, scale: u32) -> Option<DataType> {
+  let precision = u8::try_from(precision)
+    .ok()
+    .filter(|p| *p > 0 && *p <= DECIMAL128_MAX_PRECISION)?;
+  let scale = i8::try_from(scale).ok().filter(|s| *s as u8 <= precision)?;
+  Some(DataType::Decimal128(precision, scale))
+}
+
+/// Arrow type of DATETIME and TIMESTAMP columns: microseconds without a
+/// time zone, as the server returns them.
+pub(crate) fn timestamp_type() -> DataType {
+  DataType::Timestamp(TimeUnit::Microsecond, None)
+}
+
+/// Arrow type of TIME columns: signed microseconds. TIME is a duration of
+/// up to ±838 hours, not a time of day, so `Time64` cannot hold it.
+pub(crate) fn time_type() -> DataType {
+  DataType::Duration(TimeUnit::Microsecond)
+}
+
+/// Arrow type of `SET` columns: a list of member strings.
+pub(crate) fn set_list_type() -> DataType {
+  DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true)))
+}
+
+type Registry = HashMap<DataType, Box<dyn ArrowColumnBuilder>>;
+
+/// Builder per Arrow type chosen in `arrow_fields`; adding a type is one
+/// `ArrowColumnBuilder` impl plus an entry here.
+static BUILDERS: LazyLock<Registry> = LazyLock::new(|| {
+  let mut builders: Registry = HashMap::new();
+  builders.insert(DataType::Int64, Box::new(Int64Column));
+  builders.insert(DataType::Int32, Box::new(Int32Column));
+  builders.insert(DataType::Boolean, Box::new(BooleanColumn));
+  builders.insert(DataType::UInt64, Box::new(UInt64Column));
+  builders.insert(DataType::Float64, Box::new(Float64Column));
+  builders.insert(DataType::Utf8, Box::new(Utf8Column));
+  builders.insert(DataType::Binary, Box::new(BinaryColumn));
+  builders.insert(set_list_type(), Box::new(SetColumn));
+  builders.insert(DataType::Date32, Box::new(Date32Column));
+  builders.insert(timestamp_type(), Box::new(TimestampColumn));
+  builders.insert(time_type(), Box::new(DurationColumn));
+  builders
+});
+
+/// The builder for `data_type`, or `None` if `arrow_fields` never picks it.
+pub(crate) fn column_builder(data_type: &DataType) -> Option<&'static dyn ArrowColumnBuilder> {
+  BUILDERS.get(data_type).map(Box::as_ref)
+}
+
+/// Build the array for a column of `data_type`; a type without a builder
+/// is an error rather than a column of the wrong type.
+fn build_column(data_type: &DataType, values: &[Value]) -> anyhow::Result<ArrayRef> {
+  match *data_type {
+    DataType::Decimal128(precision, scale) => Ok(DecimalColumn { precision, scale }.build(values)),
+    _ => column_builder(data_type)
+      .map(|builder| builder.build(values))
+      .ok_or_else(|| anyhow::anyhow!("no Arrow column builder for {data_type}")),
+  }
+}
+
+/// Non-null values inspected when guessing whether a column holds JSON or
+/// another kind of text.
+const JSON_SAMPLE_SIZE: usize = 20;
+
+/// Whether the first non-null values all parse as JSON objects or arrays;
+/// bare scalars such as `42` are valid JSON but not worth pretty-printing.
+pub(crate) fn looks_like_json(values: &[Value]) -> bool {
+  let mut samples = values
+    .iter()
+    .filter(|v| **v != Value::NULL)
+    .take(JSON_SAMPLE_SIZE)
+    .peekable();
+  samples.peek().is_some()
+    && samples.all(|v| match v {
+      Value::Bytes(bytes) => matches!(
+        serde_json::from_slice::<serde_json::Value>(bytes),
+        Ok(serde_json::Value::Object(_) | serde_json::Value::Array(_))
+      ),
+      _ => false,
+    })
+}
+
+fn is_uuid(s: &str) -> bool {
+  s.len() == 36
+    && s.char_indices().all(|(i, c)| match i {
+      8 | 13 | 18 | 23 => c == '-',
+      _ => c.is_ascii_hexdigit(),
+    })
+}
+
+fn is_email(s: &str) -> bool {
+  let Some((local, domain)) = s.split_once('@') else {
+    return false;
+  };
+  !local.is_empty()
+    && !s.chars().any(char::is_whitespace)
+    && !domain.contains('@')
+    && domain.contains('.')
+    && domain.split('.').all(|label| !label.is_empty())
+}
+
+fn is_url(s: &str) -> bool {
+  ["http://", "https://", "ftp://"]
+    .iter()
+    .find_map(|scheme| {
+      s.get(..scheme.len())
+        .filter(|p| p.eq_ignore_ascii_case(scheme))
+    })
+    .is_some_and(|scheme| {
+      let rest = &s[scheme.len()..];
+      !rest.is_empty() && !rest.starts_with('/') && !s.chars().any(char::is_whitespace)
+    })
+}
+
+fn is_ip(s: &str) -> bool {
+  s.parse::<std::net::IpAddr>().is_ok()
+}
+
+/// Contains a `yyyy-mm-dd` date.
+fn contains_date(s: &str) -> bool {
+  s.as_bytes().windows(10).any(|w| {
+    w.iter().enumerate().all(|(i, b)| {
+      if i == 4 || i == 7 {
+        *b == b'-'
+      } else {
+        b.is_ascii_digit()
+      }
+    })
+  })
+}
+
+/// 7 to 15 digits (E.164) written with a leading `+` or grouped by spaces,
+/// dashes or parentheses, so that plain numbers, decimals and dates stored
+/// as text are not taken for phone numbers.
+fn is_phone(s: &str) -> bool {
+  let digits = s.chars().filter(char::is_ascii_digit).count();
+  let formatted = s.starts_with('+') || s.contains([' ', '-', '(']);
+  (7..=15).contains(&digits)
+    && formatted
+    && !contains_date(s)
+    && s
+      .trim_start_matches('+')
+      .chars()
+      .all(|c| c.is_ascii_digit() || matches!(c, ' ' | '-' | '(' | ')'))
+}
+
+type Matcher = fn(&str) -> bool;
+
+/// Checked in order; the first kind every sampled value matches wins.
+const SEMANTIC_MATCHERS: [(SemanticType, Matcher); 5] = [
+  (SemanticType::Uuid, is_uuid),
+  (SemanticType::Email, is_email),
+  (SemanticType::Url, is_url),
+  (SemanticType::Ip, is_ip),
+  (SemanticType::Phone, is_phone),
+];
+
+/// The kind of value all of the first non-null text values are, if any.
+pub(crate) fn infer_semantic_type(values: &[Value]) -> Option<SemanticType> {
+  let samples: Vec<&str> = values
+    .iter()
+    .filter(|v| **v != Value::NULL)
+    .take(JSON_SAMPLE_SIZE)
+    .map(|v| match v {
+      Value::Bytes(bytes) => std::str::from_utf8(bytes).ok(),
+      _ => None,
+    })
+    .collect::<Option<_>>()?;
+  if samples.is_empty() {
+    return None;
+  }
+  SEMANTIC_MATCHERS
+    .iter()
+    .find(|(_, matches)| samples.iter().all(|s| matches(s.trim())))
+    .map(|(kind, _)| *kind)
+}
+
+/// Approximate bytes a value takes once converted: its length for strings
+/// and bytes, 8 for numbers and temporals.
+pub(crate) fn value_size(val: &Value) -> usize {
+  match val {
+    Value::NULL => 0,
+    Value::Bytes(bytes) => bytes.len(),
+    _ => 8,
+  }
+}
+
+/// Maps a raw value to the form the column's builder reads.
+pub(crate) type ValueRewrite = fn(&Value) -> Value;
+
+pub(crate) fn rewrite_value(rewrite: Option<ValueRewrite>, val: Value) -> Value {
+  match rewrite {
+    Some(rewrite) => rewrite(&val),
+    None => val,
+  }
+}
+
+/// DATE/DATETIME/TIMESTAMP from the text (`Bytes`) or binary (`Date`)
+/// protocol; zero dates are `None`.
+fn naive_datetime(val: &Value) -> Option<NaiveDateTime> {
+  match val {
+    Value::Date(y, m, d, h, mi, s, us) => NaiveDate::from_ymd_opt(*y as i32, *m as u32, *d as u32)?
+      .and_hms_micro_opt(*h as u32, *mi as u32, *s as u32, *us),
+    Value::Bytes(bytes) => {
+      let text = std::str::from_utf8(bytes).ok()?;
+      NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f")
+        .ok()
+        .or_else(|| {
+          NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)
+        })
+    }
+    _ => None,
+  }
+}
+
+/// TIME from the text (`Bytes`, e.g. `-838:59:59.5`) or binary (`Time`)
+/// protocol as signed microseconds. TIME is a duration, so it may be
+/// negative or longer than a day.
+fn time_micros(val: &Value) -> Option<i64> {
+  match val {
+    Value::Time(negative, days, h, m, s, us) => {
+      let hours = *days as i64 * 24 + *h as i64;
+      let micros = ((hours * 60 + *m as i64) * 60 + *s as i64) * 1_000_000 + *us as i64;
+      Some(if *negative { -micros } else { micros })
+    }
+    Value::Bytes(bytes) => {
+      let text = std::str::from_utf8(bytes).ok()?;
+      let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+      };
+      let (hms, frac) = text.split_once('.').unwrap_or((text, ""));
+      let mut parts = hms.split(':').map(|part| part.parse::<i64>().ok());
+      let (Some(Some(h)), Some(Some(m)), Some(Some(s)), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+      else {
+        return None;
+      };
+      if h < 0 || !(0..60).contains(&m) || !(0..60).contains(&s) {
+        return None;
+      }
+      let us = match frac {
+        "" => 0,
+        _ if frac.len() <= 6 && frac.bytes().all(|b| b.is_ascii_digit()) => {
+          format!("{frac:0<6}").parse::<i64>().ok()?
+        }
+        _ => return None,
+      };
+      let micros = h
+        .checked_mul(3_600_000_000)?
+        .checked_add(m * 60_000_000 + s * 1_000_000 + us)?;
+      Some(if negative { -micros } else { micros })
+    }
+    _ => None,
+  }
+}
+
+/// Days since 1970-01-01 as `Int`, `NULL` if the value is not a date.
+pub(crate) fn epoch_days(val: &Value) -> Value {
+  let epoch = DateTime::UNIX_EPOCH.date_naive();
+  naive_datetime(val)
+    .map(|dt| Value::Int((dt.date() - epoch).num_days()))
+    .unwrap_or(Value::NULL)
+}
+
+/// Microseconds since 1970-01-01 00:00:00 as `Int`; TIMESTAMP values are
+/// taken as the session time zone reports them.
+pub(crate) fn epoch_micros(val: &Value) -> Value {
+  naive_datetime(val)
+    .map(|dt| Value::Int(dt.and_utc().timestamp_micros()))
+    .unwrap_or(Value::NULL)
+}
+
+type Point = (f64, f64);
+
+/// Geometry types by their WKB code, 1 to 7, as GeoJSON names them; WKT
+/// uses the same names in upper case.
+const GEOMETRY_TYPES: [&str; 7] = [
+  "Point",
+  "LineString",
+  "Polygon",
+  "MultiPoint",
+  "MultiLineString",
+  "MultiPolygon",
+  "GeometryCollection",
+];
+
+/// A decoded WKB geometry; coordinates are kept in stored (x, y) order.
+enum Geometry {
+  Point(Point),
+  LineString(Vec<Point>),
+  Polygon(Vec<Vec<Point>>),
+  /// `MULTI*` (codes 4 to 6) or `GEOMETRYCOLLECTION` (7).
+  Collection(u32, Vec<Geometry>),
+}
+
+fn read_u32(bytes: &mut &[u8], little: bool) -> Option<u32> {
+  let (head, rest) = bytes.split_first_chunk::<4>()?;
+  *bytes = rest;
+  Some(match little {
+    true => u32::from_le_bytes(*head),
+    false => u32::from_be_bytes(*head),
+  })
+}
+
+fn read_f64(bytes: &mut &[u8], little: bool) -> Option<f64> {
+  let (head, rest) = bytes.split_first_chunk::<8>()?;
+  *bytes = rest;
+  Some(match little {
+    true => f64::from_le_bytes(*head),
+    false => f64::from_be_bytes(*head),
+  })
+}
+
+fn read_points(bytes: &mut &[u8], little: bool) -> Option<Vec<Point>> {
+  (0..read_u32(bytes, little)?)
+    .map(|_| Some((read_f64(bytes, little)?, read_f64(bytes, little)?)))
+    .collect()
+}
+
+/// One WKB geometry from the front of `bytes`; `None` if it is truncated or
+/// of an unknown type.
+fn read_wkb(bytes: &mut &[u8]) -> Option<Geometry> {
+  let (&order, rest) = bytes.split_first()?;
+  *bytes = rest;
+  let little = order == 1;
+  let geometry = match read_u32(bytes, little)? {
+    1 => Geometry::Point((read_f64(bytes, little)?, read_f64(bytes, little)?)),
+    2 => Geometry::LineString(read_points(bytes, little)?),
+    3 => Geometry::Polygon(
+      (0..read_u32(bytes, little)?)
+        .map(|_| read_points(bytes, little))
+        .collect::<Option<_>>()?,
+    ),
+    kind @ 4..=7 => Geometry::Collection(
+      kind,
+      (0..read_u32(bytes, little)?)
+        .map(|_| read_wkb(bytes))
+        .collect::<Option<_>>()?,
+    ),
+    _ => return None,
+  };
+  Some(geometry)
+}
+
+fn wkt_points(points: &[Point]) -> String {
+  let points: Vec<String> = points.iter().map(|(x, y)| format!("{x} {y}")).collect();
+  format!("({})", points.join(","))
+}
+
+fn geojson_points(points: &[Point]) -> serde_json::Value {
+  points
+    .iter()
+    .map(|(x, y)| serde_json::json!([x, y]))
+    .collect()
+}
+
+impl Geometry {
+  fn type_name(&self) -> &'static str {
+    match self {
+      Geometry::Point(_) => GEOMETRY_TYPES[0],
+      Geometry::LineString(_) => GEOMETRY_TYPES[1],
+      Geometry::Polygon(_) => GEOMETRY_TYPES[2],
+      Geometry::Collection(kind, _) => GEOMETRY_TYPES[*kind as usize - 1],
+    }
+  }
+
+  /// The parenthesized part of the WKT, without the type name.
+  fn wkt_body(&self) -> String {
+    let join = |parts: Vec<String>| format!("({})", parts.join(","));
+    match self {
+      Geometry::Point(point) => wkt_points(&[*point]),
+      Geometry::LineString(points) => wkt_points(points),
+      Geometry::Polygon(rings) => join(rings.iter().map(|r| wkt_points(r)).collect()),
+      Geometry::Collection(7, items) => join(items.iter().map(Geometry::wkt).collect()),
+      Geometry::Collection(_, items) => join(items.iter().map(Geometry::wkt_body).collect()),
+    }
+  }
+
+  fn wkt(&self) -> String {
+    let name = self.type_name().to_ascii_uppercase();
+    match self.wkt_body().as_str() {
+      "()" => format!("{name} EMPTY"),
+      body => format!("{name}{body}"),
+    }
+  }
+
+  fn geojson(&self) -> serde_json::Value {
+    let coordinates = match self {
+      Geometry::Point((x, y)) => serde_json::json!([x, y]),
+      Geometry::LineString(points) => geojson_points(points),
+      Geometry::Polygon(rings) => rings.iter().map(|r| geojson_points(r)).collect(),
+      Geometry::Collection(7, items) => {
+        let geometries: Vec<_> = items.iter().map(Geometry::geojson).collect();
+        return serde_json::json!({"type": self.type_name(), "geometries": geometries});
+      }
+      Geometry::Collection(_, items) => items
+        .iter()
+        .map(|item| item.geojson()["coordinates"].take())
+        .collect(),
+    };
+    serde_json::json!({"type": self.type_name(), "coordinates": coordinates})
+  }
+}
+
+/// A MySQL geometry value: a 4-byte SRID followed by WKB.
+fn geometry(val: &Value) -> Option<Geometry> {
+  match val {
+    Value::Bytes(bytes) => read_wkb(&mut bytes.get(4..)?),
+    _ => None,
+  }
+}
+
+/// Geometry as well-known text, with coordinates in storage axis order;
+/// `NULL` if it is not a valid geometry.
+pub(crate) fn geometry_wkt(val: &Value) -> Value {
+  geometry(val)
+    .map(|g| Value::Bytes(g.wkt().into_bytes()))
+    .unwrap_or(Value::NULL)
+}
+
+/// Geometry as a GeoJSON object, with coordinates in storage axis order;
+/// `NULL` if it is not a valid geometry.
+pub(crate) fn geometry_geojson(val: &Value) -> Value {
+  geometry(val)
+    .map(|g| Value::Bytes(g.geojson().to_string().into_bytes()))
+    .unwrap_or(Value::NULL)
+}
+
+pub(crate) fn build_batch(
+  schema: SchemaRef,
+  tables: Vec<Vec<Value>>,
+) -> anyhow::Result<RecordBatch> {
+  let arrs: Vec<ArrayRef> = schema
+    .fields()
+    .iter()
+    .zip(tables)
+    .map(|(field, col)| build_column(field.data_type(), &col))
+    .collect::<anyhow::Result<_>>()?;
+  new_batch(schema, arrs)
+}
+
+/// A batch of `arrs`, with fields declared NOT NULL made nullable where a
+/// value did not convert, e.g. a zero date.
+fn new_batch(schema: SchemaRef, arrs: Vec<ArrayRef>) -> anyhow::Result<RecordBatch> {
+  let has_nulls =
+    |(field, arr): (&Arc<Field>, &ArrayRef)| !field.is_nullable() && arr.null_count() > 0;
+  if !schema.fields().iter().zip(&arrs).any(has_nulls) {
+    return Ok(RecordBatch::try_new(schema, arrs)?);
+  }
+  let fields: Vec<Field> = schema
+    .fields()
+    .iter()
+    .zip(&arrs)
+    .map(|(field, arr)| {
+      let nullable = field.is_nullable() || arr.null_count() > 0;
+      field.as_ref().clone().with_nullable(nullable)
+    })
+    .collect();
+  let schema = Schema::new(fields).with_metadata(schema.metadata().clone());
+  Ok(RecordBatch::try_new(Arc::new(schema), arrs)?)
+}
+
+/// Like `build_batch`, but a non-null value its column's builder cannot
+/// convert exactly is an error rather than a null slot. `row_offset` is
+/// added to the reported row for batches after the first.
+pub(crate) fn build_batch_strict(
+  schema: SchemaRef,
+  tables: Vec<Vec<Value>>,
+  row_offset: usize,
+) -> anyhow::Result<RecordBatch> {
+  let mut arrs: Vec<ArrayRef> = Vec::with_capacity(tables.len());
+  for (field, col) in schema.fields().iter().zip(&tables) {
+    let arr = build_column(field.data_type(), col)?;
+    if let Some(row) = (0..col.len()).find(|&i| arr.is_null(i) && col[i] != Value::NULL) {
+      anyhow::bail!(
+        "column `{}` row {}: {:?} is not a valid {}",
+        field.name(),
+        row + row_offset,
+        col[row],
+        field.data_type()
+      );
+    }
+    arrs.push(arr);
+  }
+  new_batch(schema, arrs)
+}
+
+/// Distinct over non-null values of a string column; `None` if all null.
+fn cardinality_ratio(arr: &StringArray) -> Option<f64> {
+  let non_null = arr.len() - arr.null_count();
+  if non_null == 0 {
+    return None;
+  }
+  let distinct: HashSet<&str> = arr.iter().flatten().collect();
+  Some(distinct.len() as f64 / non_null as f64)
+}
+
+/// Re-encode Utf8 columns whose cardinality ratio is below `threshold` as
+/// `Dictionary<Int32, Utf8>`; other columns are kept as they are.
+pub(crate) fn dictionary_encode(batch: RecordBatch, threshold: f64) -> anyhow::Result<RecordBatch> {
+  let dict_type = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+  let schema = batch.schema();
+  let mut fields = Vec::with_capacity(schema.fields().len());
+  let mut arrs = Vec::with_capacity(schema.fields().len());
+  for (field, arr) in schema.fields().iter().zip(batch.columns()) {
+    let low = arr
+      .as_any()
+      .downcast_ref::<StringArray>()
+      .and_then(cardinality_ratio)
+      .is_some_and(|ratio| ratio < threshold);
+    match low {
+      true => {
+        fields.push(field.as_ref().clone().with_data_type(dict_type.clone()));
+        arrs.push(cast(arr, &dict_type)?);
+      }
+      false => {
+        fields.push(field.as_ref().clone());
+        arrs.push(arr.clone());
+      }
+    }
+  }
+  let schema = Schema::new(fields).with_metadata(schema.metadata().clone());
+  Ok(RecordBatch::try_new(Arc::new(schema), arrs)?)
+}
+
+/// `batch` with its columns cast to the fields of `schema`, so a later
+/// batch of a stream matches the first exactly, e.g. in dictionary-encoded
+/// columns. Fails on nulls in a field that does not allow them.
+pub(crate) fn cast_batch(batch: RecordBatch, schema: &SchemaRef) -> anyhow::Result<RecordBatch> {
+  if batch.schema().fields() == schema.fields() {
+    return Ok(batch);
+  }
+  let arrs = batch
+    .columns()
+    .iter()
+    .zip(schema.fields())
+    .map(|(arr, field)| cast(arr, field.data_type()))
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(RecordBatch::try_new(schema.clone(), arrs)?)
+}
+
+/// `batch` with its list columns, i.e. SET columns, joined into strings
+/// with `sep` for writers such as CSV that take no nested types. An empty
+/// set becomes an empty string and a null stays null.
+pub(crate) fn join_lists(batch: RecordBatch, sep: &str) -> anyhow::Result<RecordBatch> {
+  let schema = batch.schema();
+  if !schema
+    .fields()
+    .iter()
+    .any(|f| matches!(f.data_type(), DataType::List(_)))
+  {
+    return Ok(batch);
+  }
+  let mut fields = Vec::with_capacity(schema.fields().len());
+  let mut arrs: Vec<ArrayRef> = Vec::with_capacity(schema.fields().len());
+  for (field, arr) in schema.fields().iter().zip(batch.columns()) {
+    let Some(list) = arr.as_list_opt::<i32>() else {
+      fields.push(field.as_ref().clone());
+      arrs.push(arr.clone());
+      continue;
+    };
+    let joined: StringArray = list
+      .iter()
+      .map(|members| {
+        let members = members?;
+        let members: Vec<&str> = members.as_string_opt::<i32>()?.iter().flatten().collect();
+        Some(members.join(sep))
+      })
+      .collect();
+    fields.push(field.as_ref().clone().with_data_type(DataType::Utf8));
+    arrs.push(Arc::new(joined));
+  }
+  let schema = Schema::new(fields).with_metadata(schema.metadata().clone());
+  Ok(RecordBatch::try_new(Arc::new(schema), arrs)?)
+}
+
+fn convert_to_date32_arr(values: &[Value]) -> Vec<Option<i32>> {
+  let epoch = DateTime::UNIX_EPOCH.date_naive();
+  values
+    .iter()
+    .map(|val| Some((naive_datetime(val)?.date() - epoch).num_days() as i32))
+    .collect()
+}
+
+fn convert_to_timestamp_arr(values: &[Value]) -> Vec<Option<i64>> {
+  values
+    .iter()
+    .map(|val| Some(naive_datetime(val)?.and_utc().timestamp_micros()))
+    .collect()
+}
+
+fn convert_to_duration_arr(values: &[Value]) -> Vec<Option<i64>> {
+  values.iter().map(time_micros).collect()
+}
+
+/// Decimal text such as `-12.5` as an integer of `scale` fractional digits;
+/// extra digits are truncated. `None` if it does not fit an `i128`.
+fn parse_decimal(text: &str, scale: i8) -> Option<i128> {
+  let text = text.trim();
+  let (negative, digits) = match text.strip_prefix('-') {
+    Some(rest) => (true, rest),
+    None => (false, text.strip_prefix('+').unwrap_or(text)),
+  };
+  let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+  if int.is_empty() && frac.is_empty() {
+    return None;
+  }
+  let scale = scale.max(0) as usize;
+  let mut value: i128 = 0;
+  let frac = frac.chars().chain(std::iter::repeat('0')).take(scale);
+  for c in int.chars().chain(frac) {
+    let digit = c.to_digit(10)? as i128;
+    value = value.checked_mul(10)?.checked_add(digit)?;
+  }
+  Some(if negative { -value } else { value })
+}
+
+fn convert_to_decimal128(unknown_val: &Value, scale: i8) -> Option<i128> {
+  match unknown_val {
+    Value::Bytes(bytes) => parse_decimal(std::str::from_utf8(bytes).ok()?, scale),
+    Value::Int(v) => (*v as i128).checked_mul(10i128.checked_pow(scale.max(0) as u32)?),
+    Value::UInt(v) => (*v as i128).checked_mul(10i128.checked_pow(scale.max(0) as u32)?),
+    _ => None,
+  }
+}
+
+fn convert_to_decimal128_arr(values: &[Value], scale: i8) -> Vec<Option<i128>> {
+  values
+    .iter()
+    .map(|val| convert_to_decimal128(val, scale))
+    .collect()
+}
+
+fn convert_to_str(unknown_val: &Value) -> Option<String> {
+  match unknown_val {
+    val @ Value::Bytes(..) => {
+      let val = from_value::<Vec<u8>>(val.clone());
+      String::from_utf8(val).ok()
+    }
+    _ => None,
+  }
+}
+
+fn convert_to_str_arr(values: &[Value]) -> Vec<Option<String>> {
+  values.iter().map(convert_to_str).collect()
+}
+
+fn convert_to_binary_arr(values: &[Value]) -> Vec<Option<&[u8]>> {
+  values
+    .iter()
+    .map(|val| match val {
+      Value::Bytes(bytes) => Some(bytes.as_slice()),
+      _ => None,
+    })
+    .collect()
+}
+
+/// Split comma-joined SET values into a list array; `''` is the empty set.
+fn convert_to_set_arr(values: &[Value]) -> ListArray {
+  let mut builder = ListBuilder::new(StringBuilder::new());
+  for val in values {
+    match convert_to_str(val) {
+      Some(s) => {
+        for member in s.split(',').filter(|m| !m.is_empty()) {
+          builder.values().append_value(member);
+        }
+        builder.append(true);
+      }
+      None => builder.append_null(),
+    }
+  }
+  builder.finish()
+}
+
+// `from_value_opt` rather than `from_value`, so that bytes which fail to
+// parse become a null slot instead of a panic
+
+fn convert_to_i64(unknown_val: &Value) -> Option<i64> {
+  match unknown_val {
+    val @ (Value::Int(..) | Value::UInt(..) | Value::Bytes(..)) => {
+      from_value_opt::<i64>(val.clone()).ok()
+    }
+    _ => None,
+  }
+}
+
+fn convert_to_i64_arr(values: &[Value]) -> Vec<Option<i64>> {
+  values.iter().map(convert_to_i64).collect()
+}
+
+/// Nonzero integers are true.
+fn convert_to_bool_arr(values: &[Value]) -> Vec<Option<bool>> {
+  values
+    .iter()
+    .map(|val| convert_to_i64(val).map(|i| i != 0))
+    .collect()
+}
+
+fn convert_to_i32(unknown_val: &Value) -> Option<i32> {
+  match unknown_val {
+    val @ Value::Int(..) => from_value_opt::<i32>(val.clone()).ok(),
+    _ => None,
+  }
+}
+
+fn convert_to_i32_arr(values: &[Value]) -> Vec<Option<i32>> {
+  values.iter().map(convert_to_i32).collect()
+}
+
+fn convert_to_u64(unknown_val: &Value) -> Option<u64> {
+  match unknown_val {
+    val @ (Value::UInt(..) | Value::Int(..) | Value::Bytes(..)) => {
+      from_value_opt::<u64>(val.clone()).ok()
+    }
+    _ => None,
+  }
+}
+
+fn convert_to_u64_arr(values: &[Value]) -> Vec<Option<u64>> {
+  values.iter().map(convert_to_u64).collect()
+}
+
+/// Integers are accepted too: YEAR comes as `Int` over the binary protocol.
+fn convert_to_f64(unknown_val: &Value) -> Option<f64> {
+  match unknown_val {
+    val @ (Value::Float(..) | Value::Double(..) | Value::Bytes(..)) => {
+      from_value_opt::<f64>(val.clone()).ok()
+    }
+    Value::Int(v) => Some(*v as f64),
+    Value::UInt(v) => Some(*v as f64),
+    _ => None,
+  }
+}
+
+fn convert_to_f64_arr(values: &[Value]) -> Vec<Option<f64>> {
+  values.iter().map(convert_to_f64).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_build_batch_strict() {
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]));
+    let tables = || {
+      vec![vec![
+        Value::Int(1),
+        Value::NULL,
+        Value::Bytes(b"x1".to_vec()),
+      ]]
+    };
+
+    let batch = build_batch(schema.clone(), tables()).unwrap();
+    assert_eq!(batch.column(0).null_count(), 2);
+
+    let err = build_batch_strict(schema.clone(), tables(), 100).unwrap_err();
+    let msg = err.to_string();
+    assert!(msg.contains("`id` row 102"), "{msg}");
+
+    let ok = vec![vec![
+      Value::Int(1),
+      Value::NULL,
+      Value::Bytes(b"7".to_vec()),
+    ]];
+    let batch = build_batch_strict(schema, ok, 0).unwrap();
+    assert_eq!(batch.column(0).null_count(), 1);
+  }
+
+  #[test]
+  fn test_dictionary_encode() {
+    let schema = Arc::new(Schema::new(vec![
+      Field::new("status", DataType::Utf8, true),
+      Field::new("id", DataType::Utf8, true),
+    ]));
+    let status = ["ok", "ok", "failed", "ok", "ok", "failed", "ok", "ok"];
+    let tables = vec![
+      status.iter().map(|s| Value::from(*s)).collect(),
+      (0..8).map(|i| Value::from(i.to_string())).collect(),
+    ];
+    let batch = build_batch(schema, tables).unwrap();
+    let batch = dictionary_encode(batch, 0.5).unwrap();
+
+    let dict = batch
+      .column(0)
+      .as_any()
+      .downcast_ref::<DictionaryArray<arrow::datatypes::Int32Type>>()
+      .unwrap();
+    assert_eq!(dict.values().len(), 2);
+    assert_eq!(dict.len(), 8);
+    assert!(matches!(
+      batch.schema().field(0).data_type(),
+      DataType::Dictionary(..)
+    ));
+    // every id is distinct, so the column stays plain
+    assert_eq!(batch.column(1).data_type(), &DataType::Utf8);
+  }
+
+  #[test]
+  fn test_cast_batch() {
+    let dict_type = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+    let first = Arc::new(Schema::new(vec![Field::new("s", dict_type.clone(), true)]));
+    let schema = Arc::new(Schema::new(vec![Field::new("s", DataType::Utf8, true)]));
+    let batch = build_batch(schema, vec![vec![Value::from("a"), Value::NULL]]).unwrap();
+    let cast = cast_batch(batch.clone(), &first).unwrap();
+    assert_eq!(cast.schema(), first);
+    assert!(cast.column(0).is_null(1));
+
+    let not_null = Arc::new(Schema::new(vec![Field::new("s", dict_type, false)]));
+    assert!(cast_batch(batch, &not_null).is_err());
+  }
+
+  #[test]
+  fn test_semantic_matchers() {
+    assert!(is_uuid("3f2504e0-4f89-11d3-9a0c-0305e82c3301"));
+    assert!(!is_uuid("3f2504e0-4f89-11d3-9a0c-0305e82c330"));
+    assert!(is_email("ada@example.org"));
+    assert!(!is_email("ada@localhost"));
+    assert!(!is_email("a b@example.org"));
+    assert!(!is_email("not an email"));
+    assert!(is_url("https://duckling.dev/docs"));
+    assert!(!is_url("https://"));
+    assert!(is_ip("10.0.0.1") && is_ip("::1"));
+    assert!(!is_ip("10.0.0.256"));
+    assert!(is_phone("+1 (555) 010-9999") && is_phone("030-1234567"));
+    assert!(!is_phone("5550109999"));
+    assert!(!is_phone("2024-01-15") && !is_phone("2024-01-15 10"));
+    assert!(!is_phone("1234567.89") && !is_phone("-1234567.5"));
+    assert!(!is_phone("555.010.9999"));
+  }
+
+  #[test]
+  fn test_value_size() {
+    assert_eq!(value_size(&Value::NULL), 0);
+    assert_eq!(value_size(&Value::Bytes(b"duckling".to_vec())), 8);
+    assert_eq!(value_size(&Value::Int(-1)), 8);
+    assert_eq!(value_size(&Value::Date(2024, 1, 2, 3, 4, 5, 0)), 8);
+  }
+
+  #[test]
+  fn test_converters_null_validity() {
+    let values = [
+      Value::NULL,
+      Value::Bytes(b"not a number".to_vec()),
+      Value::Bytes(b"42".to_vec()),
+    ];
+    let valid = |v: Vec<bool>| assert_eq!(v, [false, false, true]);
+    valid(
+      convert_to_i64_arr(&values)
+        .iter()
+        .map(Option::is_some)
+        .collect(),
+    );
+    valid(
+      convert_to_u64_arr(&values)
+        .iter()
+        .map(Option::is_some)
+        .collect(),
+    );
+    valid(
+      convert_to_f64_arr(&values)
+        .iter()
+        .map(Option::is_some)
+        .collect(),
+    );
+    assert_eq!(
+      convert_to_i32_arr(&[Value::NULL, Value::Int(7)]),
+      [None, Some(7)]
+    );
+    assert_eq!(convert_to_u64(&Value::Int(-1)), None);
+    assert_eq!(convert_to_bool_arr(&values), [None, None, Some(true)]);
+    assert!(convert_to_str_arr(&values)[0].is_none());
+    assert!(convert_to_binary_arr(&values)[0].is_none());
+
+    // every Arrow type `build_batch` produces marks NULL, and for
+    // non-text types unparsable bytes, as an invalid slot
+    let bytes = |s: &str| Value::Bytes(s.as_bytes().to_vec());
+    let cases = [
+      (DataType::Int64, bytes("1"), true),
+      // only rewritten DATE values reach Int32 columns
+      (DataType::Int32, Value::Int(1), true),
+      (DataType::UInt64, bytes("1"), true),
+      (DataType::Boolean, bytes("1"), true),
+      (DataType::Float64, bytes("1.5"), true),
+      (decimal_type(10, 2).unwrap(), bytes("1.50"), true),
+      (DataType::Date32, bytes("2024-01-01"), true),
+      (timestamp_type(), bytes("2024-01-01 00:00:00"), true),
+      (time_type(), bytes("01:00:00"), true),
+      (DataType::Utf8, bytes("a"), false),
+      (DataType::Binary, bytes("a"), false),
+      (set_list_type(), bytes("a"), false),
+    ];
+    let fields: Vec<Field> = cases
+      .iter()
+      .enumerate()
+      .map(|(i, (t, _, _))| Field::new(format!("c{i}"), t.clone(), true))
+      .collect();
+    let tables = cases
+      .iter()
+      .map(|(_, valid, _)| vec![Value::NULL, valid.clone(), bytes("not a value")])
+      .collect();
+    let batch = build_batch(Arc::new(Schema::new(fields)), tables).unwrap();
+    for ((data_type, _, parsed), column) in cases.iter().zip(batch.columns()) {
+      assert_eq!(column.data_type(), data_type);
+      assert!(column.is_null(0), "{data_type:?}");
+      assert!(column.is_valid(1), "{data_type:?}");
+      assert_eq!(column.is_null(2), *parsed, "{data_type:?}");
+    }
+
+    // dictionary encoding keeps the nulls of the text column
+    let schema = Arc::new(Schema::new(vec![Field::new("s", DataType::Utf8, true)]));
+    let tables = vec![vec![Value::NULL, Value::from("a"), Value::from("a")]];
+    let batch = dictionary_encode(build_batch(schema, tables).unwrap(), 1.0).unwrap();
+    let column = batch.column(0);
+    assert!(matches!(column.data_type(), DataType::Dictionary(..)));
+    assert!(column.is_null(0) && column.is_valid(1) && column.is_valid(2));
+  }
+
+  #[test]
+  fn test_looks_like_json() {
+    let bytes = |s: &str| Value::Bytes(s.as_bytes().to_vec());
+    assert!(looks_like_json(&[
+      bytes(r#"{"a": [1]}"#),
+      Value::NULL,
+      bytes("[]")
+    ]));
+    assert!(!looks_like_json(&[bytes(r#"{"a": 1}"#), bytes("{oops")]));
+    assert!(!looks_like_json(&[bytes("42"), bytes("\"x\"")]));
+    assert!(!looks_like_json(&[Value::NULL]));
+  }
+
+  #[test]
+  fn test_epoch_values() {
+    assert_eq!(
+      epoch_days(&Value::Bytes(b"1970-01-02".to_vec())),
+      Value::Int(1)
+    );
+    assert_eq!(
+      epoch_days(&Value::Date(2024, 1, 1, 0, 0, 0, 0)),
+      Value::Int(19723)
+    );
+    assert_eq!(
+      epoch_days(&Value::Bytes(b"0000-00-00".to_vec())),
+      Value::NULL
+    );
+    assert_eq!(epoch_days(&Value::NULL), Value::NULL);
+    assert_eq!(
+      epoch_micros(&Value::Bytes(b"1970-01-01 00:01:00".to_vec())),
+      Value::Int(60_000_000)
+    );
+  }
+
+  #[test]
+  fn test_temporal_converters() {
+    let values = [
+      Value::Date(2024, 1, 1, 12, 30, 0, 500_000),
+      Value::Bytes(b"1970-01-02 00:00:01".to_vec()),
+      Value::Bytes(b"0000-00-00 00:00:00".to_vec()),
+      Value::Date(0, 0, 0, 0, 0, 0, 0),
+      Value::NULL,
+    ];
+    assert_eq!(
+      convert_to_date32_arr(&values),
+      [Some(19723), Some(1), None, None, None]
+    );
+    assert_eq!(
+      convert_to_timestamp_arr(&values),
+      [
+        Some(1_704_112_200_500_000),
+        Some(86_401_000_000),
+        None,
+        None,
+        None
+      ]
+    );
+  }
+
+  #[test]
+  fn test_time_duration() {
+    let values = [
+      Value::Bytes(b"12:30:05.25".to_vec()),
+      Value::Time(false, 0, 23, 59, 59, 999_999),
+      Value::Bytes(b"-01:00:00".to_vec()),
+      Value::Bytes(b"838:59:59".to_vec()),
+      Value::Time(false, 1, 0, 0, 0, 0),
+      Value::Bytes(b"12:61:00".to_vec()),
+      Value::NULL,
+    ];
+    assert_eq!(
+      convert_to_duration_arr(&values),
+      [
+        Some(45_005_250_000),
+        Some(86_399_999_999),
+        Some(-3_600_000_000),
+        Some(3_020_399_000_000),
+        Some(86_400_000_000),
+        None,
+        None
+      ]
+    );
+    assert_eq!(
+      time_micros(&Value::Time(true, 0, 0, 0, 1, 500_000)),
+      Some(-1_500_000)
+    );
+    assert_eq!(
+      time_micros(&Value::Bytes(b"100:00:00".to_vec())),
+      Some(360_000_000_000)
+    );
+    assert_eq!(
+      time_micros(&Value::Bytes(b"99999999999999:00:00".to_vec())),
+      None
+    );
+  }
+
+  #[test]
+  fn test_geometry_text() {
+    // SRID 0, then little-endian WKB
+    let wkb = |body: &[u8]| {
+      let mut bytes = vec![0, 0, 0, 0];
+      bytes.extend_from_slice(body);
+      Value::Bytes(bytes)
+    };
+    let point = |x: f64, y: f64| {
+      let mut bytes = vec![1, 1, 0, 0, 0];
+      bytes.extend(x.to_le_bytes());
+      bytes.extend(y.to_le_bytes());
+      bytes
+    };
+    let mut line = vec![1, 2, 0, 0, 0, 2, 0, 0, 0];
+    for c in [0.0f64, 0.0, 1.0, 1.5] {
+      line.extend(c.to_le_bytes());
+    }
+    let mut collection = vec![1, 7, 0, 0, 0, 2, 0, 0, 0];
+    collection.extend(point(1.0, 2.0));
+    collection.extend(&line);
+    let mut multipoint = vec![1, 4, 0, 0, 0, 2, 0, 0, 0];
+    multipoint.extend(point(1.0, 2.0));
+    multipoint.extend(point(-3.0, 4.25));
+
+    let text = |v: Value| match v {
+      Value::Bytes(bytes) => String::from_utf8(bytes).unwrap(),
+      v => panic!("{v:?}"),
+    };
+    assert_eq!(text(geometry_wkt(&wkb(&point(1.0, 2.0)))), "POINT(1 2)");
+    assert_eq!(
+      text(geometry_wkt(&wkb(&collection))),
+      "GEOMETRYCOLLECTION(POINT(1 2),LINESTRING(0 0,1 1.5))"
+    );
+    assert_eq!(
+      text(geometry_wkt(&wkb(&multipoint))),
+      "MULTIPOINT((1 2),(-3 4.25))"
+    );
+    assert_eq!(
+      text(geometry_wkt(&wkb(&[1, 7, 0, 0, 0, 0, 0, 0, 0]))),
+      "GEOMETRYCOLLECTION EMPTY"
+    );
+
+    let json = |v: Value| serde_json::from_str::<serde_json::Value>(&text(v)).unwrap();
+    assert_eq!(
+      json(geometry_geojson(&wkb(&multipoint))),
+      serde_json::json!({"type": "MultiPoint", "coordinates": [[1.0, 2.0], [-3.0, 4.25]]})
+    );
+    assert_eq!(
+      json(geometry_geojson(&wkb(&collection))),
+      serde_json::json!({"type": "GeometryCollection", "geometries": [
+        {"type": "Point", "coordinates": [1.0, 2.0]},
+        {"type": "LineString", "coordinates": [[0.0, 0.0], [1.0, 1.5]]},
+      ]})
+    );
+
+    // truncated, unknown type, not bytes
+    assert_eq!(geometry_wkt(&wkb(&point(1.0, 2.0)[..12])), Value::NULL);
+    assert_eq!(geometry_wkt(&wkb(&[1, 9, 0, 0, 0])), Value::NULL);
+    assert_eq!(geometry_geojson(&Value::NULL), Value::NULL);
+  }
+
+  #[test]
+  fn test_decimal128() {
+    assert_eq!(parse_decimal("123.45", 2), Some(12345));
+    assert_eq!(parse_decimal("-0.5", 3), Some(-500));
+    assert_eq!(parse_decimal("7", 2), Some(700));
+    assert_eq!(parse_decimal("1.239", 2), Some(123));
+    assert_eq!(parse_decimal("12a", 0), None);
+    assert_eq!(parse_decimal("", 0), None);
+
+    // 38 nines, the most Decimal128 holds
+    let max = "9".repeat(38);
+    assert_eq!(parse_decimal(&max, 0), Some(10i128.pow(38) - 1));
+
+    assert_eq!(decimal_type(10, 2), Some(DataType::Decimal128(10, 2)));
+    assert_eq!(decimal_type(65, 30), None);
+
+    let values = [
+      Value::Bytes(b"19999999999999999.99".to_vec()),
+      Value::NULL,
+      Value::Int(3),
+    ];
+    let arr = build_column(&DataType::Decimal128(19, 2), &values).unwrap();
+    let arr = arr.as_any().downcast_ref::<Decimal128Array>().unwrap();
+    assert_eq!(arr.value_as_string(0), "19999999999999999.99");
+    assert!(arr.is_null(1));
+    assert_eq!(arr.value_as_string(2), "3.00");
+  }
+
+  #[test]
+  fn test_builders_per_mysql_type() {
+    use mysql::Column;
+    use mysql::consts::ColumnFlags;
+    use mysql::consts::ColumnType::*;
+
+    let bytes = |s: &str| Value::Bytes(s.as_bytes().to_vec());
+    let mut set = ListBuilder::new(StringBuilder::new());
+    set.values().append_value("a");
+    set.values().append_value("b");
+    set.append(true);
+    set.append(true);
+    set.append_null();
+    let cases: Vec<(Column, Vec<Value>, DataType, ArrayRef)> = vec![
+      (
+        Column::new(MYSQL_TYPE_LONGLONG),
+        vec![Value::Int(-3), bytes("42"), bytes("x"), Value::NULL],
+        DataType::Int64,
+        Arc::new(Int64Array::from(vec![Some(-3), Some(42), None, None])),
+      ),
+      (
+        Column::new(MYSQL_TYPE_LONG).with_flags(ColumnFlags::UNSIGNED_FLAG),
+        vec![
+          Value::UInt(7),
+          bytes("18446744073709551615"),
+          Value::Int(-1),
+        ],
+        DataType::UInt64,
+        Arc::new(UInt64Array::from(vec![Some(7), Some(u64::MAX), None])),
+      ),
+      (
+        Column::new(MYSQL_TYPE_TINY).with_column_length(1),
+        vec![Value::Int(1), bytes("0"), Value::NULL],
+        DataType::Boolean,
+        Arc::new(BooleanArray::from(vec![Some(true), Some(false), None])),
+      ),
+      (
+        Column::new(MYSQL_TYPE_DOUBLE),
+        vec![Value::Double(1.5), bytes("2.25"), Value::NULL],
+        DataType::Float64,
+        Arc::new(Float64Array::from(vec![Some(1.5), Some(2.25), None])),
+      ),
+      (
+        Column::new(MYSQL_TYPE_YEAR),
+        vec![bytes("2024"), Value::Int(1999)],
+        DataType::Float64,
+        Arc::new(Float64Array::from(vec![2024.0, 1999.0])),
+      ),
+      (
+        Column::new(MYSQL_TYPE_NEWDECIMAL)
+          .with_column_length(7)
+          .with_decimals(2),
+        vec![bytes("-123.45"), Value::Int(3)],
+        DataType::Decimal128(5, 2),
+        Arc::new(
+          Decimal128Array::from(vec![-12345, 300])
+            .with_precision_and_scale(5, 2)
+            .unwrap(),
+        ),
+      ),
+      (
+        Column::new(MYSQL_TYPE_VAR_STRING),
+        vec![bytes("duck"), Value::NULL],
+        DataType::Utf8,
+        Arc::new(StringArray::from(vec![Some("duck"), None])),
+      ),
+      (
+        Column::new(MYSQL_TYPE_BLOB).with_character_set(63),
+        vec![Value::Bytes(vec![0, 255]), Value::NULL],
+        DataType::Binary,
+        Arc::new(BinaryArray::from(vec![Some(&[0u8, 255][..]), None])),
+      ),
+      (
+        Column::new(MYSQL_TYPE_STRING).with_flags(ColumnFlags::SET_FLAG),
+        vec![bytes("a,b"), bytes(""), Value::NULL],
+        set_list_type(),
+        Arc::new(set.finish()),
+      ),
+      (
+        Column::new(MYSQL_TYPE_DATE),
+        vec![bytes("1970-01-02"), Value::Date(2024, 1, 1, 0, 0, 0, 0)],
+        DataType::Date32,
+        Arc::new(Date32Array::from(vec![1, 19723])),
+      ),
+      (
+        Column::new(MYSQL_TYPE_DATETIME),
+        vec![bytes("1970-01-01 00:01:00"), bytes("0000-00-00 00:00:00")],
+        timestamp_type(),
+        Arc::new(TimestampMicrosecondArray::from(vec![
+          Some(60_000_000),
+          None,
+        ])),
+      ),
+      (
+        Column::new(MYSQL_TYPE_TIME),
+        vec![bytes("12:30:05.25"), bytes("-01:00:00")],
+        time_type(),
+        Arc::new(DurationMicrosecondArray::from(vec![
+          Some(45_005_250_000),
+          Some(-3_600_000_000),
+        ])),
+      ),
+    ];
+    let conn = super::super::MySqlConnection::default();
+    for (col, values, data_type, expected) in cases {
+      let (fields, _) = conn.arrow_fields(std::slice::from_ref(&col));
+      assert_eq!(fields[0].data_type(), &data_type, "{:?}", col.column_type());
+      let built = build_column(&data_type, &values).unwrap();
+      assert_eq!(&built, &expected, "{:?}", col.column_type());
+    }
+
+    let err = build_column(&DataType::Float16, &[Value::NULL]).unwrap_err();
+    assert!(err.to_string().contains("Float16"), "{err}");
+  }
+}
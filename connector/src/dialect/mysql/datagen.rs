@@ -0,0 +1,272 @@
+use mysql::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::{MySqlConnection, escape_literal, quote_ident, quote_table};
+
+/// Rows per generated INSERT statement.
+const INSERT_BATCH_ROWS: usize = 1_000;
+
+const STRING_CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Column description from `information_schema.columns` needed to produce a
+/// valid literal.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ColumnSpec {
+  pub name: String,
+  pub data_type: String,
+  pub column_type: String,
+  pub nullable: bool,
+  pub max_length: Option<u64>,
+  pub precision: Option<u64>,
+  pub scale: Option<u64>,
+}
+
+/// Values of an `enum('a','b')`/`set('a','b')` column type.
+fn enum_values(column_type: &str) -> Vec<String> {
+  let Some(body) = column_type
+    .find('(')
+    .and_then(|start| column_type[start + 1..].strip_suffix(')'))
+  else {
+    return vec![];
+  };
+  body
+    .split("','")
+    .map(|v| v.trim_matches('\'').replace("''", "'"))
+    .collect()
+}
+
+fn random_string(rng: &mut StdRng, max_length: u64) -> String {
+  let len = rng.gen_range(1..=max_length.clamp(1, 16)) as usize;
+  (0..len)
+    .map(|_| STRING_CHARSET[rng.gen_range(0..STRING_CHARSET.len())] as char)
+    .collect()
+}
+
+/// A random SQL literal for the column; types without a generator fall back to
+/// `DEFAULT`.
+fn random_literal(rng: &mut StdRng, column: &ColumnSpec) -> String {
+  if column.nullable && rng.gen_bool(0.1) {
+    return "NULL".to_string();
+  }
+  let unsigned = column.column_type.contains("unsigned");
+  match column.data_type.as_str() {
+    "tinyint" if column.column_type.starts_with("tinyint(1)") => rng.gen_range(0..=1).to_string(),
+    "tinyint" => rng.gen_range(0..=127).to_string(),
+    "smallint" => rng.gen_range(0..=32_767).to_string(),
+    "mediumint" | "int" | "integer" | "bigint" => rng.gen_range(0..=1_000_000).to_string(),
+    "decimal" | "numeric" => {
+      let scale = column.scale.unwrap_or_default().min(6) as i32;
+      let digits = column.precision.unwrap_or(10) as i32 - scale;
+      let max = 10f64.powi(digits.clamp(0, 9)) - 1.0;
+      let value = if unsigned || max <= 0.0 {
+        rng.gen_range(0.0..=max.max(0.0))
+      } else {
+        rng.gen_range(-max..=max)
+      };
+      format!("{:.*}", scale as usize, value)
+    }
+    "float" | "double" | "real" => format!("{:.4}", rng.gen_range(0.0..10_000.0)),
+    "char" | "varchar" | "tinytext" | "text" | "mediumtext" | "longtext" => {
+      format!("'{}'", random_string(rng, column.max_length.unwrap_or(16)))
+    }
+    "enum" | "set" => {
+      let values = enum_values(&column.column_type);
+      if values.is_empty() {
+        return "DEFAULT".to_string();
+      }
+      let value = &values[rng.gen_range(0..values.len())];
+      format!("'{}'", escape_literal(value))
+    }
+    "date" => format!(
+      "'{:04}-{:02}-{:02}'",
+      rng.gen_range(2000..=2030),
+      rng.gen_range(1..=12),
+      rng.gen_range(1..=28)
+    ),
+    "datetime" | "timestamp" => format!(
+      "'{:04}-{:02}-{:02} {:02}:{:02}:{:02}'",
+      rng.gen_range(2000..=2030),
+      rng.gen_range(1..=12),
+      rng.gen_range(1..=28),
+      rng.gen_range(0..24),
+      rng.gen_range(0..60),
+      rng.gen_range(0..60)
+    ),
+    "time" => format!(
+      "'{:02}:{:02}:{:02}'",
+      rng.gen_range(0..24),
+      rng.gen_range(0..60),
+      rng.gen_range(0..60)
+    ),
+    "year" => rng.gen_range(2000..=2030).to_string(),
+    _ => "DEFAULT".to_string(),
+  }
+}
+
+/// INSERT statements for `rows` random rows, deterministic for a given seed.
+pub(crate) fn insert_statements(
+  table: &str,
+  columns: &[ColumnSpec],
+  rows: usize,
+  seed: u64,
+) -> Vec<String> {
+  let mut rng = StdRng::seed_from_u64(seed);
+  let names = columns
+    .iter()
+    .map(|c| quote_ident(&c.name))
+    .collect::<Vec<_>>()
+    .join(", ");
+  let mut statements = vec![];
+  let mut remaining = rows;
+  while remaining > 0 {
+    let n = remaining.min(INSERT_BATCH_ROWS);
+    let values = (0..n)
+      .map(|_| {
+        let row: Vec<String> = columns
+          .iter()
+          .map(|c| random_literal(&mut rng, c))
+          .collect();
+        format!("({})", row.join(", "))
+      })
+      .collect::<Vec<_>>()
+      .join(", ");
+    statements.push(format!("INSERT INTO {table} ({names}) VALUES {values}"));
+    remaining -= n;
+  }
+  statements
+}
+
+impl MySqlConnection {
+  /// Insert `rows` random rows into the table for development seeding.
+  ///
+  /// Auto-increment and generated columns are left to the server. Returns the
+  /// number of inserted rows.
+  pub fn generate_test_data(
+    &self,
+    schema: &str,
+    table: &str,
+    rows: usize,
+    seed: u64,
+  ) -> anyhow::Result<u64> {
+    let mut conn = self.get_conn()?;
+    let sql = r"
+    select COLUMN_NAME, DATA_TYPE, COLUMN_TYPE, IS_NULLABLE,
+      CHARACTER_MAXIMUM_LENGTH, NUMERIC_PRECISION, NUMERIC_SCALE, EXTRA
+    from information_schema.columns
+    where TABLE_SCHEMA = ? and TABLE_NAME = ?
+    order by ORDINAL_POSITION
+    ";
+    type Row = (
+      String,
+      String,
+      String,
+      String,
+      Option<u64>,
+      Option<u64>,
+      Option<u64>,
+      String,
+    );
+    let columns: Vec<ColumnSpec> = conn
+      .exec::<Row, _, _>(sql, (schema, table))?
+      .into_iter()
+      .filter(|row| {
+        let extra = row.7.to_ascii_lowercase();
+        !extra.contains("auto_increment") && !extra.contains("generated")
+      })
+      .map(|row| ColumnSpec {
+        name: row.0,
+        data_type: row.1.to_ascii_lowercase(),
+        column_type: row.2.to_ascii_lowercase(),
+        nullable: row.3 == "YES",
+        max_length: row.4,
+        precision: row.5,
+        scale: row.6,
+      })
+      .collect();
+    if columns.is_empty() {
+      anyhow::bail!("no insertable columns in {schema}.{table}");
+    }
+
+    let target = quote_table(Some(schema), table);
+    let mut inserted = 0;
+    for stmt in insert_statements(&target, &columns, rows, seed) {
+      conn.query_drop(stmt)?;
+      inserted += conn.affected_rows();
+    }
+    Ok(inserted)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dialect::mysql::duckling_test_table;
+
+  fn column(name: &str, data_type: &str, column_type: &str, nullable: bool) -> ColumnSpec {
+    ColumnSpec {
+      name: name.to_string(),
+      data_type: data_type.to_string(),
+      column_type: column_type.to_string(),
+      nullable,
+      max_length: Some(32),
+      precision: Some(10),
+      scale: Some(2),
+    }
+  }
+
+  fn five_columns() -> Vec<ColumnSpec> {
+    vec![
+      column("id", "int", "int", false),
+      column("name", "varchar", "varchar(32)", true),
+      column("price", "decimal", "decimal(10,2)", true),
+      column("created", "datetime", "datetime", false),
+      column("status", "enum", "enum('new','it''s done')", true),
+    ]
+  }
+
+  #[test]
+  fn test_generate_rows() {
+    let stmts = insert_statements("`t`", &five_columns(), 100, 42);
+    assert_eq!(stmts.len(), 1);
+    let rows = stmts[0].matches("), (").count() + 1;
+    assert_eq!(rows, 100);
+    assert!(stmts[0].starts_with("INSERT INTO `t` (`id`, `name`, `price`, `created`, `status`)"));
+
+    let stmts = insert_statements("`t`", &five_columns(), 2_500, 42);
+    assert_eq!(stmts.len(), 3);
+  }
+
+  #[test]
+  fn test_generate_deterministic() {
+    let a = insert_statements("`t`", &five_columns(), 100, 7);
+    let b = insert_statements("`t`", &five_columns(), 100, 7);
+    let c = insert_statements("`t`", &five_columns(), 100, 8);
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+  }
+
+  #[test]
+  fn test_enum_values() {
+    assert_eq!(
+      enum_values("enum('new','it''s done')"),
+      ["new", "it's done"]
+    );
+  }
+
+  #[test]
+  #[ignore = "requires a running MySQL server"]
+  fn test_generate_test_data() {
+    let conn = crate::dialect::mysql::local_connection();
+    duckling_test_table(
+      &conn,
+      "seeded",
+      "(id int, name varchar(32), price decimal(10,2), \
+       created datetime, status enum('new','done'))",
+    );
+    let inserted = conn
+      .generate_test_data("duckling_test", "seeded", 100, 42)
+      .unwrap();
+    assert_eq!(inserted, 100);
+  }
+}
@@ -0,0 +1,294 @@
+use std::fmt::Write;
+use std::time::Instant;
+
+use mysql::PooledConn;
+use mysql::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::MySqlConnection;
+use super::replication::{replica_column, replica_status_rows};
+use super::status::{VarMap, var_bool, var_str};
+
+/// Checks run by [`MySqlConnection::diagnostic_report`], in order.
+pub const DIAGNOSTIC_CHECKS: [&str; 8] = [
+  "connection",
+  "server_version",
+  "privileges",
+  "performance_schema",
+  "binary_log",
+  "replication",
+  "slow_query_log",
+  "sample_query",
+];
+
+/// A `select 1` round trip slower than this is reported as a warning.
+const SLOW_SAMPLE_QUERY_MS: f64 = 100.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CheckStatus {
+  Pass,
+  Warning(String),
+  Fail(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticCheck {
+  pub name: String,
+  pub status: CheckStatus,
+  pub details: String,
+}
+
+impl DiagnosticCheck {
+  /// A check that errored counts as failed.
+  fn from_result(name: &str, result: anyhow::Result<(CheckStatus, String)>) -> Self {
+    let (status, details) =
+      result.unwrap_or_else(|e| (CheckStatus::Fail(e.to_string()), String::new()));
+    Self {
+      name: name.to_string(),
+      status,
+      details,
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticReport {
+  pub checks: Vec<DiagnosticCheck>,
+}
+
+impl DiagnosticReport {
+  /// Every check failed because no connection could be made.
+  fn unreachable(err: &anyhow::Error) -> Self {
+    let checks = DIAGNOSTIC_CHECKS
+      .iter()
+      .map(|name| {
+        let reason = match *name {
+          "connection" => err.to_string(),
+          _ => "not connected".to_string(),
+        };
+        DiagnosticCheck::from_result(name, Err(anyhow::anyhow!(reason)))
+      })
+      .collect();
+    Self { checks }
+  }
+
+  pub fn has_failures(&self) -> bool {
+    self
+      .checks
+      .iter()
+      .any(|c| matches!(c.status, CheckStatus::Fail(_)))
+  }
+
+  /// A count line followed by one `[PASS|WARN|FAIL] name: details` line per
+  /// check.
+  pub fn summary(&self) -> String {
+    let count = |f: fn(&CheckStatus) -> bool| self.checks.iter().filter(|c| f(&c.status)).count();
+    let mut summary = format!(
+      "{} passed, {} warnings, {} failed",
+      count(|s| matches!(s, CheckStatus::Pass)),
+      count(|s| matches!(s, CheckStatus::Warning(_))),
+      count(|s| matches!(s, CheckStatus::Fail(_))),
+    );
+    for check in &self.checks {
+      let (label, reason) = match &check.status {
+        CheckStatus::Pass => ("PASS", None),
+        CheckStatus::Warning(reason) => ("WARN", Some(reason)),
+        CheckStatus::Fail(reason) => ("FAIL", Some(reason)),
+      };
+      let text = match (reason, check.details.is_empty()) {
+        (Some(reason), true) => reason.clone(),
+        (Some(reason), false) => format!("{reason} ({})", check.details),
+        (None, _) => check.details.clone(),
+      };
+      let _ = write!(summary, "\n[{label}] {}: {text}", check.name);
+    }
+    summary
+  }
+}
+
+/// Enabled flags are a pass, disabled ones a warning.
+fn flag_check(vars: &VarMap, name: &str, details: String) -> (CheckStatus, String) {
+  match var_bool(vars, &[name]) {
+    true => (CheckStatus::Pass, details),
+    false => (CheckStatus::Warning(format!("{name} is disabled")), details),
+  }
+}
+
+fn replication_check(conn: &mut PooledConn) -> anyhow::Result<(CheckStatus, String)> {
+  let Some(row) = replica_status_rows(conn)?.into_iter().next() else {
+    return Ok((CheckStatus::Pass, "not a replica".to_string()));
+  };
+  let io: String =
+    replica_column(&row, &["Replica_IO_Running", "Slave_IO_Running"]).unwrap_or_default();
+  let sql: String =
+    replica_column(&row, &["Replica_SQL_Running", "Slave_SQL_Running"]).unwrap_or_default();
+  let details = format!("IO thread: {io}, SQL thread: {sql}");
+  if io.eq_ignore_ascii_case("Yes") && sql.eq_ignore_ascii_case("Yes") {
+    return Ok((CheckStatus::Pass, details));
+  }
+  let error = replica_column::<String>(&row, &["Last_Error"])
+    .filter(|e| !e.is_empty())
+    .unwrap_or_else(|| "replication threads are not running".to_string());
+  Ok((CheckStatus::Fail(error), details))
+}
+
+impl MySqlConnection {
+  /// Run the [`DIAGNOSTIC_CHECKS`] and collect their outcome; failing checks
+  /// are reported rather than returned as errors.
+  pub fn diagnostic_report(&self) -> anyhow::Result<DiagnosticReport> {
+    let mut conn = match self.get_conn() {
+      Ok(conn) => conn,
+      Err(err) => return Ok(DiagnosticReport::unreachable(&err)),
+    };
+    let vars = self.global_variables_in(&[
+      "performance_schema",
+      "log_bin",
+      "binlog_format",
+      "slow_query_log",
+      "slow_query_log_file",
+      "long_query_time",
+    ]);
+
+    let mut checks = vec![DiagnosticCheck::from_result(
+      "connection",
+      Ok((
+        CheckStatus::Pass,
+        format!("connected to {}:{}", self.host, self.port),
+      )),
+    )];
+
+    let (major, minor, patch) = conn.server_version();
+    let version = format!("{major}.{minor}.{patch}");
+    let status = match (major, minor) < (5, 7) {
+      true => CheckStatus::Warning("MySQL 5.7 or later is recommended".to_string()),
+      false => CheckStatus::Pass,
+    };
+    checks.push(DiagnosticCheck::from_result(
+      "server_version",
+      Ok((status, version)),
+    ));
+
+    let grants = conn
+      .query::<String, _>("SHOW GRANTS")
+      .map_err(anyhow::Error::from)
+      .map(|grants| {
+        let monitoring = grants.iter().any(|g| {
+          let g = g.to_ascii_uppercase();
+          g.contains("ALL PRIVILEGES") || g.contains("PROCESS")
+        });
+        let status = match monitoring {
+          true => CheckStatus::Pass,
+          false => CheckStatus::Warning("PROCESS privilege missing".to_string()),
+        };
+        (status, grants.join("; "))
+      });
+    checks.push(DiagnosticCheck::from_result("privileges", grants));
+
+    let var_check = |name: &str, details: fn(&VarMap) -> String| match &vars {
+      Ok(vars) => Ok(flag_check(vars, name, details(vars))),
+      Err(e) => Err(anyhow::anyhow!("{e}")),
+    };
+    checks.push(DiagnosticCheck::from_result(
+      "performance_schema",
+      var_check("performance_schema", |_| String::new()),
+    ));
+    checks.push(DiagnosticCheck::from_result(
+      "binary_log",
+      var_check("log_bin", |vars| {
+        var_str(vars, &["binlog_format"])
+          .map(|f| format!("format {f}"))
+          .unwrap_or_default()
+      }),
+    ));
+    checks.push(DiagnosticCheck::from_result(
+      "replication",
+      replication_check(&mut conn),
+    ));
+    checks.push(DiagnosticCheck::from_result(
+      "slow_query_log",
+      var_check("slow_query_log", |vars| {
+        format!(
+          "{} (long_query_time {})",
+          var_str(vars, &["slow_query_log_file"]).unwrap_or_default(),
+          var_str(vars, &["long_query_time"]).unwrap_or_default()
+        )
+      }),
+    ));
+
+    let start = Instant::now();
+    let sample = conn
+      .query_drop("SELECT 1")
+      .map_err(anyhow::Error::from)
+      .map(|_| {
+        let ms = start.elapsed().as_secs_f64() * 1000.0;
+        let status = match ms > SLOW_SAMPLE_QUERY_MS {
+          true => CheckStatus::Warning(format!("slower than {SLOW_SAMPLE_QUERY_MS} ms")),
+          false => CheckStatus::Pass,
+        };
+        (status, format!("{ms:.2} ms"))
+      });
+    checks.push(DiagnosticCheck::from_result("sample_query", sample));
+
+    Ok(DiagnosticReport { checks })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_unreachable_report() {
+    let report = DiagnosticReport::unreachable(&anyhow::anyhow!("connection refused"));
+    let names: Vec<&str> = report.checks.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, DIAGNOSTIC_CHECKS);
+    assert!(report.has_failures());
+    assert_eq!(
+      report.checks[0].status,
+      CheckStatus::Fail("connection refused".to_string())
+    );
+  }
+
+  #[test]
+  fn test_summary() {
+    let check = |name: &str, status, details: &str| DiagnosticCheck {
+      name: name.to_string(),
+      status,
+      details: details.to_string(),
+    };
+    let report = DiagnosticReport {
+      checks: vec![
+        check("server_version", CheckStatus::Pass, "8.0.36"),
+        check(
+          "binary_log",
+          CheckStatus::Warning("log_bin is disabled".to_string()),
+          "",
+        ),
+        check(
+          "replication",
+          CheckStatus::Fail("Duplicate entry".to_string()),
+          "IO thread: Yes, SQL thread: No",
+        ),
+      ],
+    };
+    assert!(report.has_failures());
+    assert_eq!(
+      report.summary(),
+      "1 passed, 1 warnings, 1 failed\n\
+       [PASS] server_version: 8.0.36\n\
+       [WARN] binary_log: log_bin is disabled\n\
+       [FAIL] replication: Duplicate entry (IO thread: Yes, SQL thread: No)"
+    );
+    assert!(!DiagnosticReport::default().has_failures());
+  }
+
+  #[test]
+  #[ignore = "requires a running MySQL server"]
+  fn test_diagnostic_report() {
+    let conn = crate::dialect::mysql::local_connection();
+    let report = conn.diagnostic_report().unwrap();
+    let names: Vec<&str> = report.checks.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, DIAGNOSTIC_CHECKS);
+    assert_eq!(report.checks[0].status, CheckStatus::Pass);
+  }
+}
@@ -0,0 +1,1043 @@
+use std::time::{Duration, Instant};
+
+use mysql::Error;
+use mysql::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::status::{VarMap, var_str, var_u64};
+use super::{MySqlConnection, escape_literal, quote_ident, quote_table};
+use crate::utils::RawArrowData;
+
+const ER_SPECIFIC_ACCESS_DENIED_ERROR: u16 = 1227;
+const ER_UNKNOWN_TABLE: u16 = 1109;
+
+/// How often buffer pool load/dump progress is polled.
+const BUFFER_POOL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Give up waiting for a buffer pool load/dump after this long.
+const BUFFER_POOL_POLL_TIMEOUT: Duration = Duration::from_secs(3600);
+
+fn is_rule(line: &str) -> bool {
+  let line = line.trim();
+  line.len() >= 3 && line.chars().all(|c| c == '-' || c == '=')
+}
+
+/// Extract a section of `SHOW ENGINE INNODB STATUS` output, e.g. `LOG`.
+///
+/// Sections are headed by their name between two dashed rules.
+pub fn innodb_section<'a>(status: &'a str, name: &str) -> Option<&'a str> {
+  let mut lines = vec![];
+  let mut offsets = vec![];
+  let mut pos = 0;
+  for line in status.split_inclusive('\n') {
+    lines.push(line.trim_end());
+    offsets.push(pos);
+    pos += line.len();
+  }
+  offsets.push(pos);
+
+  let header = (1..lines.len().saturating_sub(1))
+    .find(|&i| lines[i].trim() == name && is_rule(lines[i - 1]) && is_rule(lines[i + 1]))?;
+  let start = header + 2;
+  let end = (start..lines.len())
+    .find(|&j| {
+      (is_rule(lines[j]) && j + 2 < lines.len() && is_rule(lines[j + 2]))
+        || lines[j].starts_with("END OF INNODB MONITOR OUTPUT")
+    })
+    .unwrap_or(lines.len());
+  Some(status[offsets[start]..offsets[end]].trim_end())
+}
+
+/// Value of a `Label      12345` line in an InnoDB status section.
+fn labelled_u64(section: &str, label: &str) -> Option<u64> {
+  section
+    .lines()
+    .find_map(|line| line.trim().strip_prefix(label))
+    .and_then(|rest| rest.split_whitespace().next())
+    .and_then(|v| v.parse().ok())
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedoLogStatus {
+  pub current_lsn: u64,
+  pub flushed_to_lsn: u64,
+  pub checkpoint_lsn: u64,
+  pub log_file_size_bytes: u64,
+  pub redo_log_pending_bytes: u64,
+  /// Checkpoint age as a percentage of the async flush point, which InnoDB
+  /// places at 7/8 of the redo log capacity.
+  pub async_flush_pct: f64,
+}
+
+impl RedoLogStatus {
+  /// Build from the status text and the `innodb_log%`/`Innodb_lsn%` variables.
+  pub fn from_status(status: &str, vars: &VarMap) -> anyhow::Result<Self> {
+    let log = innodb_section(status, "LOG")
+      .ok_or_else(|| anyhow::anyhow!("LOG section not found in InnoDB status"))?;
+    let mut current_lsn = labelled_u64(log, "Log sequence number").unwrap_or_default();
+    // Percona Server / MariaDB expose the LSN as a status variable
+    match var_u64(vars, &["Innodb_lsn_current"]) {
+      0 => {}
+      lsn => current_lsn = lsn,
+    }
+    let flushed_to_lsn = labelled_u64(log, "Log flushed up to").unwrap_or_default();
+    let checkpoint_lsn = labelled_u64(log, "Last checkpoint at").unwrap_or_default();
+
+    let log_file_size_bytes = var_u64(vars, &["innodb_log_file_size"]);
+    let capacity = match var_u64(vars, &["innodb_redo_log_capacity"]) {
+      0 => log_file_size_bytes * var_u64(vars, &["innodb_log_files_in_group"]).max(1),
+      capacity => capacity,
+    };
+    let async_point = capacity as f64 * 7.0 / 8.0;
+    let async_flush_pct = if async_point > 0.0 {
+      current_lsn.saturating_sub(checkpoint_lsn) as f64 / async_point * 100.0
+    } else {
+      0.0
+    };
+
+    Ok(Self {
+      current_lsn,
+      flushed_to_lsn,
+      checkpoint_lsn,
+      log_file_size_bytes,
+      redo_log_pending_bytes: current_lsn.saturating_sub(flushed_to_lsn),
+      async_flush_pct,
+    })
+  }
+}
+
+/// Split an InnoDB tablespace name like `db/orders` or `db/orders#p#p0`
+/// into schema and table.
+pub(crate) fn split_tablespace_name(name: &str) -> Option<(&str, &str)> {
+  let (schema, table) = name.split_once('/')?;
+  let table = table.split_once('#').map_or(table, |(t, _)| t);
+  Some((schema, table))
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncryptionStatus {
+  pub tablespace_name: String,
+  pub encrypted: bool,
+  pub table_name: Option<String>,
+}
+
+impl EncryptionStatus {
+  pub fn from_row(tablespace_name: String, encryption: &str) -> Self {
+    let table_name = split_tablespace_name(&tablespace_name).map(|(_, t)| t.to_string());
+    Self {
+      encrypted: encryption.eq_ignore_ascii_case("Y"),
+      tablespace_name,
+      table_name,
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TablespaceFreeSpace {
+  pub name: String,
+  pub file_path: Option<String>,
+  pub total_size_bytes: u64,
+  pub free_size_bytes: u64,
+  pub used_pct: f64,
+}
+
+impl TablespaceFreeSpace {
+  pub fn from_row(name: String, file_path: Option<String>, total: u64, free: u64) -> Self {
+    let free = free.min(total);
+    Self {
+      name,
+      file_path,
+      total_size_bytes: total,
+      free_size_bytes: free,
+      used_pct: if total == 0 {
+        0.0
+      } else {
+        (total - free) as f64 * 100.0 / total as f64
+      },
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BufferPoolPage {
+  /// `` `db`.`table` `` as reported by InnoDB, `None` for system pages.
+  pub table_name: Option<String>,
+  pub page_type: String,
+  pub is_dirty: bool,
+  pub oldest_modification_lsn: u64,
+}
+
+impl BufferPoolPage {
+  pub fn from_row(table_name: Option<String>, page_type: String, oldest_modification: u64) -> Self {
+    Self {
+      table_name,
+      page_type,
+      // a page is dirty until flushed, when its oldest modification resets
+      is_dirty: oldest_modification != 0,
+      oldest_modification_lsn: oldest_modification,
+    }
+  }
+}
+
+/// `LIKE` pattern matching literal `value`.
+fn escape_like(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('%', "\\%")
+    .replace('_', "\\_")
+}
+
+const BUFFER_PAGE_WARNING: &str =
+  "information_schema.innodb_buffer_page scans the whole buffer pool and can stall a busy server";
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadlockTransaction {
+  pub thread_id: u64,
+  pub query: String,
+  pub lock_mode: String,
+  pub lock_type: String,
+  pub table_name: String,
+  pub victim: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadlockEvent {
+  pub detected_at: String,
+  pub transactions: Vec<DeadlockTransaction>,
+}
+
+#[derive(PartialEq)]
+enum DeadlockBlock {
+  Transaction,
+  Holds,
+  Waiting,
+  Other,
+}
+
+/// `RECORD LOCKS ... of table `db`.`t` ... lock_mode X locks rec but not gap waiting`
+/// or `TABLE LOCK table `db`.`t` ... lock mode IX waiting`
+fn parse_lock_line(line: &str, tx: &mut DeadlockTransaction) {
+  let (lock_type, table_marker) = if line.starts_with("RECORD LOCKS") {
+    ("RECORD", " of table ")
+  } else if line.starts_with("TABLE LOCK") {
+    ("TABLE", "TABLE LOCK table ")
+  } else {
+    return;
+  };
+  tx.lock_type = lock_type.to_string();
+  if let Some((_, rest)) = line.split_once(table_marker) {
+    let table = rest.split_whitespace().next().unwrap_or_default();
+    tx.table_name = table.replace('`', "");
+  }
+  let mode = line
+    .split_once("lock_mode ")
+    .or_else(|| line.split_once("lock mode "))
+    .map(|(_, mode)| mode.trim());
+  if let Some(mode) = mode {
+    tx.lock_mode = mode.strip_suffix(" waiting").unwrap_or(mode).to_string();
+  }
+}
+
+/// Parse the body of the `LATEST DETECTED DEADLOCK` section.
+pub fn parse_deadlock(section: &str) -> Option<DeadlockEvent> {
+  let mut lines = section.lines().map(str::trim).filter(|l| !l.is_empty());
+  let detected_at = lines
+    .next()?
+    .split_whitespace()
+    .take(2)
+    .collect::<Vec<_>>()
+    .join(" ");
+
+  let mut transactions: Vec<DeadlockTransaction> = vec![];
+  let mut block = DeadlockBlock::Other;
+  let mut in_query = false;
+  let mut victim = None;
+  for line in lines {
+    if let Some(marker) = line.strip_prefix("*** ") {
+      in_query = false;
+      if let Some(n) = marker.strip_prefix("WE ROLL BACK TRANSACTION (") {
+        victim = n.trim_end_matches(')').parse::<usize>().ok();
+        block = DeadlockBlock::Other;
+      } else if marker.ends_with("TRANSACTION:") {
+        transactions.push(DeadlockTransaction::default());
+        block = DeadlockBlock::Transaction;
+      } else if marker.contains("HOLDS THE LOCK") {
+        block = DeadlockBlock::Holds;
+      } else if marker.contains("WAITING FOR THIS LOCK") {
+        block = DeadlockBlock::Waiting;
+      }
+      continue;
+    }
+    let Some(tx) = transactions.last_mut() else {
+      continue;
+    };
+    match block {
+      DeadlockBlock::Transaction => {
+        if let Some(rest) = line.strip_prefix("MySQL thread id ") {
+          tx.thread_id = rest
+            .split(|c: char| !c.is_ascii_digit())
+            .next()
+            .and_then(|id| id.parse().ok())
+            .unwrap_or_default();
+          in_query = true;
+        } else if in_query {
+          if !tx.query.is_empty() {
+            tx.query.push('\n');
+          }
+          tx.query.push_str(line);
+        }
+      }
+      // the lock being waited for is the one that caused the deadlock;
+      // fall back to a held lock for servers that omit the waiting block
+      DeadlockBlock::Waiting => parse_lock_line(line, tx),
+      DeadlockBlock::Holds if tx.lock_type.is_empty() => parse_lock_line(line, tx),
+      _ => {}
+    }
+  }
+  if let Some(tx) = victim.and_then(|n| transactions.get_mut(n.wrapping_sub(1))) {
+    tx.victim = true;
+  }
+  Some(DeadlockEvent {
+    detected_at,
+    transactions,
+  })
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WarmupResult {
+  pub pages_loaded: u64,
+  pub elapsed_seconds: f64,
+  pub completion_pct: f64,
+}
+
+/// State reported by `Innodb_buffer_pool_load_status`/`_dump_status`.
+#[derive(Debug, PartialEq)]
+pub(crate) enum BufferPoolProgress {
+  /// `Loaded 1234/5678 pages`
+  Loading { done: u64, total: u64 },
+  /// `Buffer pool(s) load completed at 250520 10:11:12`
+  Completed,
+  /// `Buffer pool(s) load aborted ...` or an error message
+  Failed(String),
+  /// Anything else, e.g. `Loading buffer pool(s) from /var/lib/mysql/ib_buffer_pool`
+  Pending,
+}
+
+pub(crate) fn parse_buffer_pool_status(status: &str) -> BufferPoolProgress {
+  let status = status.trim();
+  let lower = status.to_ascii_lowercase();
+  if lower.contains("completed") {
+    return BufferPoolProgress::Completed;
+  }
+  if lower.contains("aborted") || lower.contains("error") || lower.contains("cannot") {
+    return BufferPoolProgress::Failed(status.to_string());
+  }
+  let progress = status
+    .strip_prefix("Loaded ")
+    .and_then(|rest| rest.split_whitespace().next())
+    .and_then(|pair| pair.split_once('/'))
+    .and_then(|(done, total)| Some((done.parse().ok()?, total.parse().ok()?)));
+  match progress {
+    Some((done, total)) => BufferPoolProgress::Loading { done, total },
+    None => BufferPoolProgress::Pending,
+  }
+}
+
+/// Poll `read_status` until the `operation` (load or dump) completes, fails
+/// or times out.
+///
+/// `previous` is the status read before the operation was triggered. The
+/// server keeps reporting it until the new operation starts, and since the
+/// completion message carries a timestamp, a status equal to it is stale
+/// rather than a sign that the new operation already finished.
+pub(crate) fn poll_buffer_pool(
+  operation: &str,
+  previous: &str,
+  mut read_status: impl FnMut() -> anyhow::Result<String>,
+  interval: Duration,
+  timeout: Duration,
+) -> anyhow::Result<WarmupResult> {
+  let start = Instant::now();
+  let mut result = WarmupResult::default();
+  loop {
+    let status = read_status()?;
+    let progress = if status.trim() == previous.trim() {
+      BufferPoolProgress::Pending
+    } else {
+      parse_buffer_pool_status(&status)
+    };
+    match progress {
+      BufferPoolProgress::Loading { done, total } => {
+        result.pages_loaded = done;
+        result.completion_pct = if total > 0 {
+          done as f64 / total as f64 * 100.0
+        } else {
+          0.0
+        };
+      }
+      BufferPoolProgress::Completed => {
+        result.completion_pct = 100.0;
+        break;
+      }
+      BufferPoolProgress::Failed(msg) => anyhow::bail!("buffer pool {operation} failed: {msg}"),
+      BufferPoolProgress::Pending => {}
+    }
+    if start.elapsed() >= timeout {
+      anyhow::bail!(
+        "buffer pool {operation} not finished after {:?} ({:.1}% done)",
+        timeout,
+        result.completion_pct
+      );
+    }
+    std::thread::sleep(interval);
+  }
+  result.elapsed_seconds = start.elapsed().as_secs_f64();
+  Ok(result)
+}
+
+/// Transparent page compression (`COMPRESSION='zlib'`/`'lz4'`).
+fn is_page_compressed(create_options: &str) -> bool {
+  let options = create_options.to_ascii_lowercase();
+  options.contains("compression=") && !options.contains("compression='none'")
+}
+
+/// Compression ratio of a page-compressed tablespace, which punches holes
+/// so that fewer bytes are allocated than the file size.
+fn size_ratio(file_size: u64, allocated_size: u64) -> Option<f64> {
+  if file_size == 0 || allocated_size == 0 {
+    return None;
+  }
+  Some(file_size as f64 / allocated_size as f64)
+}
+
+impl MySqlConnection {
+  /// Raw text of `SHOW ENGINE INNODB STATUS`.
+  pub fn innodb_status(&self) -> anyhow::Result<String> {
+    let mut conn = self.get_conn()?;
+    let row: Option<(String, String, String)> = conn.query_first("SHOW ENGINE INNODB STATUS")?;
+    row
+      .map(|(_, _, status)| status)
+      .ok_or_else(|| anyhow::anyhow!("empty InnoDB status"))
+  }
+
+  /// The most recent deadlock InnoDB detected, if any since startup.
+  pub fn last_deadlock(&self) -> anyhow::Result<Option<DeadlockEvent>> {
+    let status = self.innodb_status()?;
+    Ok(innodb_section(&status, "LATEST DETECTED DEADLOCK").and_then(parse_deadlock))
+  }
+
+  fn set_buffer_pool_filename(&self, file: &str) -> anyhow::Result<()> {
+    if !file.is_empty() {
+      let mut conn = self.get_conn()?;
+      conn.query_drop(format!(
+        "SET GLOBAL innodb_buffer_pool_filename = '{}'",
+        escape_literal(file)
+      ))?;
+    }
+    Ok(())
+  }
+
+  /// Load a buffer pool dump now and wait for it to finish.
+  ///
+  /// Also enables `innodb_buffer_pool_dump_at_shutdown` so the next restart
+  /// has a fresh dump to load. An empty `dump_file_path` keeps the server's
+  /// `innodb_buffer_pool_filename`.
+  pub fn buffer_pool_warmup(&self, dump_file_path: &str) -> anyhow::Result<WarmupResult> {
+    self.set_buffer_pool_filename(dump_file_path)?;
+    let pages_before = self.buffer_pool_pages_data()?;
+    let previous = self.buffer_pool_status("Innodb_buffer_pool_load_status")?;
+    {
+      let mut conn = self.get_conn()?;
+      conn.query_drop("SET GLOBAL innodb_buffer_pool_dump_at_shutdown = ON")?;
+      conn.query_drop("SET GLOBAL innodb_buffer_pool_load_now = ON")?;
+    }
+    let mut result = poll_buffer_pool(
+      "load",
+      &previous,
+      || self.buffer_pool_status("Innodb_buffer_pool_load_status"),
+      BUFFER_POOL_POLL_INTERVAL,
+      BUFFER_POOL_POLL_TIMEOUT,
+    )?;
+    // completed loads no longer report a page count
+    let loaded = self.buffer_pool_pages_data()?.saturating_sub(pages_before);
+    result.pages_loaded = result.pages_loaded.max(loaded);
+    Ok(result)
+  }
+
+  /// Dump the buffer pool to `output_file` and wait for it to finish.
+  ///
+  /// Returns the approximate number of pages dumped, i.e.
+  /// `innodb_buffer_pool_dump_pct` percent of the pages currently cached.
+  pub fn dump_buffer_pool(&self, output_file: &str) -> anyhow::Result<u64> {
+    self.set_buffer_pool_filename(output_file)?;
+    let previous = self.buffer_pool_status("Innodb_buffer_pool_dump_status")?;
+    {
+      let mut conn = self.get_conn()?;
+      conn.query_drop("SET GLOBAL innodb_buffer_pool_dump_now = ON")?;
+    }
+    poll_buffer_pool(
+      "dump",
+      &previous,
+      || self.buffer_pool_status("Innodb_buffer_pool_dump_status"),
+      BUFFER_POOL_POLL_INTERVAL,
+      BUFFER_POOL_POLL_TIMEOUT,
+    )?;
+    let pct = var_u64(
+      &self.global_variables("innodb_buffer_pool_dump_pct")?,
+      &["innodb_buffer_pool_dump_pct"],
+    );
+    let pct = if pct == 0 { 100 } else { pct };
+    Ok(self.buffer_pool_pages_data()? * pct / 100)
+  }
+
+  fn buffer_pool_status(&self, name: &str) -> anyhow::Result<String> {
+    let vars = self.global_status(name)?;
+    Ok(var_str(&vars, &[name]).unwrap_or_default().to_string())
+  }
+
+  fn buffer_pool_pages_data(&self) -> anyhow::Result<u64> {
+    let vars = self.global_status("Innodb_buffer_pool_pages_data")?;
+    Ok(var_u64(&vars, &["Innodb_buffer_pool_pages_data"]))
+  }
+
+  pub fn redo_log_status(&self) -> anyhow::Result<RedoLogStatus> {
+    let status = self.innodb_status()?;
+    let mut vars = self.global_variables("innodb_%log%")?;
+    vars.extend(self.global_status("Innodb_lsn%")?);
+    RedoLogStatus::from_status(&status, &vars)
+  }
+
+  /// Tablespace encryption state from `information_schema.innodb_tablespaces`
+  /// (MySQL 8.0.13+).
+  pub fn encryption_status(&self, schema: Option<&str>) -> anyhow::Result<Vec<EncryptionStatus>> {
+    let mut conn = self.get_conn()?;
+    let sql = "select NAME, ENCRYPTION from information_schema.innodb_tablespaces order by NAME";
+    let rows: Vec<(String, String)> = conn.query(sql)?;
+    Ok(
+      rows
+        .into_iter()
+        .filter(|(name, _)| match schema {
+          Some(schema) => split_tablespace_name(name).is_some_and(|(s, _)| s == schema),
+          None => true,
+        })
+        .map(|(name, encryption)| EncryptionStatus::from_row(name, &encryption))
+        .collect(),
+    )
+  }
+
+  /// Tables of `schema` whose tablespace is not encrypted.
+  pub fn unencrypted_tables(&self, schema: &str) -> anyhow::Result<Vec<String>> {
+    let mut tables: Vec<String> = self
+      .encryption_status(Some(schema))?
+      .into_iter()
+      .filter(|s| !s.encrypted)
+      .filter_map(|s| s.table_name)
+      .collect();
+    tables.dedup();
+    Ok(tables)
+  }
+
+  /// Size and free space of each InnoDB tablespace file. Free space comes
+  /// from `information_schema.files`, as the tablespace views only carry
+  /// sizes.
+  pub fn tablespace_free_space(&self) -> anyhow::Result<Vec<TablespaceFreeSpace>> {
+    let mut conn = self.get_conn()?;
+    let sql = r"
+    select t.NAME, d.PATH, t.FILE_SIZE, f.DATA_FREE
+    from information_schema.innodb_tablespaces t
+    left join information_schema.innodb_datafiles d on d.SPACE = t.SPACE
+    left join information_schema.files f on f.FILE_ID = t.SPACE
+    order by t.NAME
+    ";
+    type Row = (String, Option<String>, Option<u64>, Option<u64>);
+    let rows: Vec<Row> = conn.query(sql)?;
+    Ok(
+      rows
+        .into_iter()
+        .map(|(name, path, total, free)| {
+          TablespaceFreeSpace::from_row(
+            name,
+            path,
+            total.unwrap_or_default(),
+            free.unwrap_or_default(),
+          )
+        })
+        .collect(),
+    )
+  }
+
+  /// File size over allocated size of a page-compressed table, e.g. `2.0`
+  /// when it takes half the space.
+  ///
+  /// Only page compression is measured. `None` for uncompressed tables and
+  /// for `ROW_FORMAT=COMPRESSED`, whose file size says nothing about how
+  /// large the rows would be uncompressed.
+  pub async fn compression_ratio(&self, schema: &str, table: &str) -> anyhow::Result<Option<f64>> {
+    let mut conn = self.get_conn()?;
+    let row: Option<Option<String>> = conn.exec_first(
+      r"
+      select CREATE_OPTIONS from information_schema.tables
+      where TABLE_SCHEMA = ? and TABLE_NAME = ?
+      ",
+      (schema, table),
+    )?;
+    let Some(create_options) = row else {
+      anyhow::bail!("table {schema}.{table} not found");
+    };
+    if !is_page_compressed(create_options.as_deref().unwrap_or_default()) {
+      return Ok(None);
+    }
+    let sizes: Option<(u64, u64)> = conn.exec_first(
+      r"
+      select FILE_SIZE, ALLOCATED_SIZE
+      from information_schema.innodb_tablespaces where NAME = concat(?, '/', ?)
+      ",
+      (schema, table),
+    )?;
+    Ok(sizes.and_then(|(file, allocated)| size_ratio(file, allocated)))
+  }
+
+  /// Rebuild the table with a null `ALTER TABLE ... ENGINE=InnoDB`, returning
+  /// its free pages to the file system under file-per-table.
+  pub fn reclaim_tablespace_space(&self, schema: &str, table: &str) -> anyhow::Result<()> {
+    let mut conn = self.get_conn()?;
+    let sql = format!(
+      "ALTER TABLE {} ENGINE=InnoDB",
+      quote_table(Some(schema), table)
+    );
+    conn.query_drop(sql)?;
+    Ok(())
+  }
+
+  /// Pages currently in the buffer pool, optionally limited to a schema or
+  /// table.
+  ///
+  /// Expensive: it walks every page of the buffer pool while holding its
+  /// mutexes, so avoid running it against a busy production server.
+  pub fn buffer_pool_pages(
+    &self,
+    schema: Option<&str>,
+    table: Option<&str>,
+  ) -> anyhow::Result<Vec<BufferPoolPage>> {
+    log::warn!("{BUFFER_PAGE_WARNING}");
+    let pattern = match (schema, table) {
+      (Some(schema), Some(table)) => escape_like(&quote_table(Some(schema), table)),
+      (Some(schema), None) => format!("{}.%", escape_like(&quote_ident(schema))),
+      (None, Some(table)) => format!("%.{}", escape_like(&quote_ident(table))),
+      (None, None) => "%".to_string(),
+    };
+    let mut conn = self.get_conn()?;
+    let sql = r"
+    select TABLE_NAME, PAGE_TYPE, OLDEST_MODIFICATION
+    from information_schema.innodb_buffer_page
+    where (? = '%' or TABLE_NAME like ?)
+    ";
+    let rows: Vec<(Option<String>, String, u64)> = conn.exec(sql, (&pattern, &pattern))?;
+    Ok(
+      rows
+        .into_iter()
+        .map(|(table, page_type, lsn)| BufferPoolPage::from_row(table, page_type, lsn))
+        .collect(),
+    )
+  }
+
+  /// Tables with the most pages in the buffer pool. Same cost caveat as
+  /// [`Self::buffer_pool_pages`].
+  pub fn hot_pages_by_table(&self, top_n: usize) -> anyhow::Result<Vec<(String, u64)>> {
+    log::warn!("{BUFFER_PAGE_WARNING}");
+    let mut conn = self.get_conn()?;
+    let sql = format!(
+      r"
+      select TABLE_NAME, count(*) from information_schema.innodb_buffer_page
+      where TABLE_NAME is not null
+      group by TABLE_NAME
+      order by count(*) desc
+      limit {top_n}
+      "
+    );
+    Ok(conn.query(sql)?)
+  }
+
+  /// Size, data/free/dirty pages and hit rate per buffer pool instance.
+  ///
+  /// `hit_rate` is the share of page reads served from memory since the
+  /// last status refresh, `NULL` when there were no reads.
+  pub async fn buffer_pool_stats(&self) -> anyhow::Result<RawArrowData> {
+    let mut conn = self.get_conn()?;
+    match conn.query_drop("select POOL_ID from information_schema.innodb_buffer_pool_stats limit 1")
+    {
+      Ok(_) => {}
+      Err(Error::MySqlError(e)) if e.code == ER_SPECIFIC_ACCESS_DENIED_ERROR => {
+        anyhow::bail!(
+          "reading buffer pool stats needs the PROCESS privilege: {}",
+          e.message
+        );
+      }
+      Err(Error::MySqlError(e)) if e.code == ER_UNKNOWN_TABLE => {
+        anyhow::bail!(
+          "buffer pool stats are unavailable, is InnoDB enabled? {}",
+          e.message
+        );
+      }
+      Err(e) => return Err(e.into()),
+    }
+    // HIT_RATE is per mille; NUMBER_PAGES_GET counts reads in the interval
+    self.query_on(
+      &mut conn,
+      r"
+      select POOL_ID as pool_id, POOL_SIZE as pool_size,
+        DATABASE_PAGES as pages_data, FREE_BUFFERS as pages_free,
+        MODIFIED_DATABASE_PAGES as pages_dirty,
+        case when NUMBER_PAGES_GET > 0 then HIT_RATE / 1000 end as hit_rate
+      from information_schema.innodb_buffer_pool_stats
+      order by POOL_ID
+      ",
+    )
+  }
+
+  pub fn encrypt_table(&self, schema: &str, table: &str) -> anyhow::Result<()> {
+    let mut conn = self.get_conn()?;
+    let sql = format!(
+      "ALTER TABLE {} ENCRYPTION='Y'",
+      quote_table(Some(schema), table)
+    );
+    conn.query_drop(sql)?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dialect::mysql::duckling_test_db;
+  use crate::dialect::mysql::status::var_map;
+
+  const LOG_STATUS: &str = "
+=====================================
+2025-05-20 10:12:01 0x7f INNODB MONITOR OUTPUT
+=====================================
+---
+LOG
+---
+Log sequence number          19126742
+Log buffer assigned up to    19126742
+Log buffer completed up to   19126742
+Log written up to            19126742
+Log flushed up to            19126500
+Added dirty pages up to      19126742
+Pages flushed up to          19120000
+Last checkpoint at           19000000
+16 log i/o's done, 0.00 log i/o's/second
+----------------------
+BUFFER POOL AND MEMORY
+----------------------
+Total large memory allocated 0
+";
+
+  const DEADLOCK_STATUS: &str = "
+------------------------
+LATEST DETECTED DEADLOCK
+------------------------
+2025-05-20 10:11:45 140241263535872
+*** (1) TRANSACTION:
+TRANSACTION 2078, ACTIVE 6 sec starting index read
+mysql tables in use 1, locked 1
+LOCK WAIT 3 lock struct(s), heap size 1128, 2 row lock(s)
+MySQL thread id 9, OS thread handle 140241263535872, query id 62 localhost root updating
+UPDATE t1 SET v = 2 WHERE id = 2
+
+*** (1) HOLDS THE LOCK(S):
+RECORD LOCKS space id 2 page no 4 n bits 72 index PRIMARY of table `test`.`t1` trx id 2078 lock_mode X locks rec but not gap
+Record lock, heap no 2 PHYSICAL RECORD: n_fields 4; compact format; info bits 0
+
+*** (1) WAITING FOR THIS LOCK TO BE GRANTED:
+RECORD LOCKS space id 2 page no 4 n bits 72 index PRIMARY of table `test`.`t1` trx id 2078 lock_mode X locks rec but not gap waiting
+Record lock, heap no 3 PHYSICAL RECORD: n_fields 4; compact format; info bits 0
+
+*** (2) TRANSACTION:
+TRANSACTION 2079, ACTIVE 4 sec starting index read
+mysql tables in use 1, locked 1
+LOCK WAIT 3 lock struct(s), heap size 1128, 2 row lock(s)
+MySQL thread id 10, OS thread handle 140241262479104, query id 63 localhost root updating
+UPDATE t1 SET v = 3 WHERE id = 1
+
+*** (2) HOLDS THE LOCK(S):
+RECORD LOCKS space id 2 page no 4 n bits 72 index PRIMARY of table `test`.`t1` trx id 2079 lock_mode X locks rec but not gap
+Record lock, heap no 3 PHYSICAL RECORD: n_fields 4; compact format; info bits 0
+
+*** (2) WAITING FOR THIS LOCK TO BE GRANTED:
+TABLE LOCK table `test`.`t2` trx id 2079 lock mode IX waiting
+
+*** WE ROLL BACK TRANSACTION (2)
+------------
+TRANSACTIONS
+------------
+Trx id counter 2085
+";
+
+  #[test]
+  fn test_parse_deadlock() {
+    let section = innodb_section(DEADLOCK_STATUS, "LATEST DETECTED DEADLOCK").unwrap();
+    let event = parse_deadlock(section).unwrap();
+    assert_eq!(event.detected_at, "2025-05-20 10:11:45");
+    assert_eq!(event.transactions.len(), 2);
+
+    let first = &event.transactions[0];
+    assert_eq!(first.thread_id, 9);
+    assert_eq!(first.query, "UPDATE t1 SET v = 2 WHERE id = 2");
+    assert_eq!(first.lock_type, "RECORD");
+    assert_eq!(first.lock_mode, "X locks rec but not gap");
+    assert_eq!(first.table_name, "test.t1");
+    assert!(!first.victim);
+
+    let second = &event.transactions[1];
+    assert_eq!(second.thread_id, 10);
+    assert_eq!(second.lock_type, "TABLE");
+    assert_eq!(second.lock_mode, "IX");
+    assert_eq!(second.table_name, "test.t2");
+    assert!(second.victim);
+  }
+
+  #[test]
+  fn test_buffer_pool_load_polling() {
+    let mut statuses = vec![
+      "Loading buffer pool(s) from /var/lib/mysql/ib_buffer_pool",
+      "Loaded 1000/4000 pages",
+      "Loaded 3000/4000 pages",
+      "Buffer pool(s) load completed at 250520 10:11:12",
+    ]
+    .into_iter();
+    let mut polls = 0;
+    let result = poll_buffer_pool(
+      "load",
+      "",
+      || {
+        polls += 1;
+        Ok(statuses.next().unwrap().to_string())
+      },
+      Duration::ZERO,
+      Duration::from_secs(10),
+    )
+    .unwrap();
+    assert_eq!(polls, 4);
+    assert_eq!(result.pages_loaded, 3000);
+    assert_eq!(result.completion_pct, 100.0);
+
+    assert_eq!(
+      parse_buffer_pool_status("Loaded 1000/4000 pages"),
+      BufferPoolProgress::Loading {
+        done: 1000,
+        total: 4000
+      }
+    );
+    let err = poll_buffer_pool(
+      "load",
+      "",
+      || Ok("Buffer pool(s) load aborted on request".to_string()),
+      Duration::ZERO,
+      Duration::from_secs(10),
+    );
+    assert!(err.is_err());
+  }
+
+  #[test]
+  fn test_buffer_pool_polling_skips_stale_status() {
+    let stale = "Buffer pool(s) dump completed at 250519 08:00:00";
+    let mut statuses = vec![
+      stale,
+      "Dumping buffer pool(s) to /var/lib/mysql/ib_buffer_pool",
+      "Buffer pool(s) dump completed at 250520 10:11:12",
+    ]
+    .into_iter();
+    let mut polls = 0;
+    let result = poll_buffer_pool(
+      "dump",
+      stale,
+      || {
+        polls += 1;
+        Ok(statuses.next().unwrap().to_string())
+      },
+      Duration::ZERO,
+      Duration::from_secs(10),
+    )
+    .unwrap();
+    assert_eq!(polls, 3);
+    assert_eq!(result.completion_pct, 100.0);
+
+    // a server that never moves past the old status times out
+    let err = poll_buffer_pool(
+      "dump",
+      stale,
+      || Ok(stale.to_string()),
+      Duration::ZERO,
+      Duration::ZERO,
+    );
+    assert!(err.is_err());
+  }
+
+  #[test]
+  fn test_redo_log_status() {
+    let log = innodb_section(LOG_STATUS, "LOG").unwrap();
+    assert!(log.starts_with("Log sequence number"));
+    assert!(log.ends_with("log i/o's/second"));
+
+    let vars = var_map(&[
+      ("innodb_log_file_size", "1048576"),
+      ("innodb_log_files_in_group", "2"),
+    ]);
+    let status = RedoLogStatus::from_status(LOG_STATUS, &vars).unwrap();
+    assert_eq!(status.current_lsn, 19126742);
+    assert_eq!(status.flushed_to_lsn, 19126500);
+    assert_eq!(status.checkpoint_lsn, 19000000);
+    assert_eq!(status.redo_log_pending_bytes, 242);
+    assert_eq!(status.log_file_size_bytes, 1048576);
+    let expected = 126742.0 / (2.0 * 1048576.0 * 7.0 / 8.0) * 100.0;
+    assert!((status.async_flush_pct - expected).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_encryption_status() {
+    let fixture = [
+      ("mysql", "N"),
+      ("shop/orders", "Y"),
+      ("shop/users", "N"),
+      ("shop/events#p#p0", "N"),
+      ("shop/events#p#p1", "N"),
+    ];
+    let status: Vec<_> = fixture
+      .iter()
+      .map(|(name, enc)| EncryptionStatus::from_row(name.to_string(), enc))
+      .collect();
+    assert_eq!(status[0].table_name, None);
+    assert!(status[1].encrypted);
+    assert_eq!(status[1].table_name.as_deref(), Some("orders"));
+    assert!(!status[2].encrypted);
+    assert_eq!(status[3].table_name.as_deref(), Some("events"));
+  }
+
+  #[test]
+  fn test_tablespace_free_space() {
+    let rows = [
+      (
+        "shop/orders",
+        Some("./shop/orders.ibd"),
+        100 << 20,
+        25 << 20,
+      ),
+      ("shop/empty", Some("./shop/empty.ibd"), 0, 0),
+      ("innodb_system", None, 12 << 20, 20 << 20),
+    ];
+    let spaces: Vec<TablespaceFreeSpace> = rows
+      .into_iter()
+      .map(|(name, path, total, free)| {
+        TablespaceFreeSpace::from_row(name.into(), path.map(String::from), total, free)
+      })
+      .collect();
+    assert_eq!(spaces[0].used_pct, 75.0);
+    assert_eq!(spaces[1].used_pct, 0.0);
+    // free space reported past the file size is clamped
+    assert_eq!(spaces[2].free_size_bytes, 12 << 20);
+    assert_eq!(spaces[2].used_pct, 0.0);
+  }
+
+  #[test]
+  fn test_buffer_pool_pages() {
+    let rows = [
+      (Some("`shop`.`orders`"), "INDEX", 0),
+      (Some("`shop`.`orders`"), "INDEX", 81_223_412),
+      (None, "SYSTEM", 0),
+      (Some("`shop`.`order_items`"), "BLOB", 81_224_001),
+    ];
+    let pages: Vec<BufferPoolPage> = rows
+      .into_iter()
+      .map(|(t, ty, lsn)| BufferPoolPage::from_row(t.map(String::from), ty.into(), lsn))
+      .collect();
+    assert!(!pages[0].is_dirty);
+    assert!(pages[1].is_dirty);
+    assert_eq!(pages[1].oldest_modification_lsn, 81_223_412);
+    assert_eq!(pages[2].table_name, None);
+    assert_eq!(pages.iter().filter(|p| p.is_dirty).count(), 2);
+
+    assert_eq!(escape_like("`my_db`.%"), "`my\\_db`.\\%");
+  }
+
+  #[test]
+  fn test_size_ratio() {
+    assert!(is_page_compressed("COMPRESSION=\"zlib\""));
+    assert!(!is_page_compressed(
+      "row_format=COMPRESSED KEY_BLOCK_SIZE=8"
+    ));
+    assert!(!is_page_compressed("COMPRESSION='None'"));
+    assert!(!is_page_compressed(""));
+
+    // a quarter of the file is allocated
+    assert_eq!(size_ratio(1 << 20, 1 << 18), Some(4.0));
+    assert_eq!(size_ratio(1 << 20, 0), None);
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_compression_ratio() {
+    let conn = crate::dialect::mysql::local_connection();
+    let mut c = duckling_test_db(&conn);
+    c.query_drop(
+      "drop table if exists duckling_test.compressed, duckling_test.zipped, duckling_test.plain",
+    )
+    .unwrap();
+    c.query_drop(
+      "create table duckling_test.compressed (id int primary key, note text) \
+       compression='zlib'",
+    )
+    .unwrap();
+    c.query_drop(
+      "create table duckling_test.zipped (id int primary key) \
+       row_format=compressed key_block_size=4",
+    )
+    .unwrap();
+    c.query_drop("create table duckling_test.plain (id int primary key)")
+      .unwrap();
+    c.query_drop(
+      "insert into duckling_test.compressed \
+       with recursive n (i) as (select 1 union all select i + 1 from n where i < 2000) \
+       select i, repeat('duckling', 50) from n",
+    )
+    .unwrap();
+
+    let ratio = conn
+      .compression_ratio("duckling_test", "compressed")
+      .await
+      .unwrap();
+    // without hole punching in the file system the ratio stays at 1.0
+    assert!(ratio.is_some_and(|r| r >= 1.0), "{ratio:?}");
+    let zipped = conn
+      .compression_ratio("duckling_test", "zipped")
+      .await
+      .unwrap();
+    assert_eq!(zipped, None);
+    let plain = conn
+      .compression_ratio("duckling_test", "plain")
+      .await
+      .unwrap();
+    assert_eq!(plain, None);
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_buffer_pool_stats() {
+    let conn = crate::dialect::mysql::local_connection();
+    let res = conn.buffer_pool_stats().await.unwrap();
+    assert!(res.batch.num_rows() >= 1);
+    let schema = res.batch.schema();
+    let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(
+      names,
+      [
+        "pool_id",
+        "pool_size",
+        "pages_data",
+        "pages_free",
+        "pages_dirty",
+        "hit_rate"
+      ]
+    );
+  }
+}
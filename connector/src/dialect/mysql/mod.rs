@@ -1,26 +1,269 @@
 use anyhow::anyhow;
 use arrow::array::*;
-use arrow::datatypes::{DataType, Field, Schema};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use async_trait::async_trait;
+use mysql::consts::ColumnFlags;
 use mysql::consts::ColumnType::*;
 use mysql::prelude::*;
 use mysql::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::mpsc;
 
 use crate::dialect::Connection;
-use crate::utils::{Metadata, RawArrowData};
+use crate::utils::{ExecResult, Metadata, RawArrowData, csv_writer};
 use crate::utils::{Table, build_tree};
 use crate::utils::{Title, TreeNode};
+use convert::{
+  ValueRewrite, build_batch, build_batch_strict, cast_batch, decimal_type, dictionary_encode,
+  epoch_days, epoch_micros, geometry_geojson, geometry_wkt, infer_semantic_type, join_lists,
+  looks_like_json, rewrite_value, set_list_type, time_type, timestamp_type, value_size,
+};
 
-#[derive(Debug, Default)]
+mod account;
+mod convert;
+mod datagen;
+mod diagnostic;
+mod innodb;
+mod perf_schema;
+mod profile;
+mod replication;
+mod retry;
+mod schema;
+mod server;
+mod session;
+mod srv;
+mod status;
+mod table;
+
+pub use diagnostic::{CheckStatus, DIAGNOSTIC_CHECKS, DiagnosticCheck, DiagnosticReport};
+pub use innodb::{
+  BufferPoolPage, DeadlockEvent, DeadlockTransaction, EncryptionStatus, RedoLogStatus,
+  TablespaceFreeSpace, WarmupResult,
+};
+pub use perf_schema::{
+  ActiveTransaction, NetworkStats, QueryDigest, TableAccessFreq, TableLockStats, UserNetworkStats,
+};
+pub use profile::HistogramBucket;
+pub use replication::{GtidGap, GtidSet, SemiSyncStatus, parse_gtid_set};
+pub use retry::RetryConfig;
+pub use schema::{CharsetMismatch, FkGraph};
+pub use server::{EngineSupport, MonitoringPrereqs, QueryCacheStats, StorageEngine};
+pub use session::Session;
+pub use srv::{SrvResolver, SrvTarget, SystemResolver};
+pub use status::VarMap;
+pub use table::{AlterEstimate, MaintenanceOp, OscCompatibility, PartitionPruningResult};
+
+/// Collation id of the `binary` character set, which BLOB columns use and
+/// TEXT columns do not.
+const BINARY_CHARSET: u16 = 63;
+
+const ER_DBACCESS_DENIED_ERROR: u16 = 1044;
+const ER_ACCESS_DENIED_ERROR: u16 = 1045;
+const ER_BAD_DB_ERROR: u16 = 1049;
+const ER_DUP_FIELDNAME: u16 = 1060;
+/// Client-side "Can't connect to MySQL server".
+const CR_CONN_HOST_ERROR: u16 = 2003;
+
+/// Rows per batch when exporting query results.
+const EXPORT_BATCH_SIZE: usize = 10_000;
+
+/// Connect timeout used when `MySqlConnection::connect_timeout` is unset.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Default, Clone)]
 pub struct MySqlConnection {
   pub host: String,
   pub port: String,
   pub username: String,
   pub password: String,
   pub database: Option<String>,
+  /// Tag prepended to executed queries as `/* duckling:<tag> */` so they
+  /// can be traced in the slow query log and processlist.
+  pub query_tag: Option<String>,
+  /// Case applied to Arrow field names; `Title` keeps the original name.
+  pub normalize_column_case: Option<Case>,
+  /// What `query` does when a statement yields several result sets.
+  pub multi_result: MultiResultPolicy,
+  /// How geometry columns are returned.
+  pub geometry_format: GeometryFormat,
+  /// How NULL is written to CSV exports, e.g. `\\N` or `NULL`, so it can be
+  /// told apart from an empty string. Empty by default.
+  pub null_string: String,
+  /// Sample TEXT/VARCHAR values and flag columns holding JSON documents
+  /// with `Title::is_json`; the Arrow type stays Utf8.
+  pub detect_json_text: bool,
+  /// Sample text values and set `Title::semantic_type` for columns of
+  /// emails, URLs, UUIDs, IP addresses or phone numbers.
+  pub infer_semantic_types: bool,
+  /// Return DATE as days since the Unix epoch (Int32) and DATETIME/TIMESTAMP
+  /// as microseconds since the epoch (Int64) instead of Arrow Date32 and
+  /// Timestamp columns. TIME is not a point in time and stays a Duration
+  /// either way.
+  pub temporal_as_epoch: bool,
+  /// Fail with the column and row when a value does not convert to its
+  /// column's Arrow type, instead of returning a null. The text protocol
+  /// sends every value as bytes, so these must parse exactly.
+  pub strict_types: bool,
+  /// Return string columns of `query` results as `Dictionary<Int32, Utf8>`
+  /// when their distinct/non-null ratio is below this threshold, e.g. `0.1`.
+  /// Streamed results decide on the first batch.
+  pub dictionary_encode_strings: Option<f64>,
+  /// Abort `query` with `ResultTooLarge` once the values read so far take
+  /// more than this many bytes, independent of any row limit.
+  pub max_result_bytes: Option<usize>,
+  /// Unix socket path, e.g. `/var/run/mysqld/mysqld.sock`; used instead of
+  /// `host` and `port` when set.
+  pub socket: Option<String>,
+  /// Whether the connection is encrypted with TLS. `SslMode::Preferred`
+  /// skips certificate checks; see `SslMode`.
+  pub ssl_mode: SslMode,
+  /// CA certificate (PEM or DER) the server certificate is verified against
+  /// in `SslMode::Required`; the system roots are used when unset.
+  pub ca_cert_path: Option<PathBuf>,
+  /// Time allowed to establish the TCP connection; 10 seconds when unset.
+  pub connect_timeout: Option<Duration>,
+  /// Time a read from the server may block, bounding how long `query`
+  /// waits for results. No limit when unset.
+  pub read_timeout: Option<Duration>,
+  /// Time a write to the server may block. No limit when unset.
+  pub write_timeout: Option<Duration>,
+  /// Retries of `get_conn` on dropped or reset connections. Statements are
+  /// never re-run, since they may not be idempotent.
+  pub retry_config: RetryConfig,
+  /// Connection pool reused across calls and shared by clones.
+  pub pool: PoolCache,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SslMode {
+  /// Plaintext.
+  #[default]
+  Disabled,
+  /// TLS without verifying the certificate or host name, so it protects
+  /// against passive eavesdropping only; falls back to plaintext when the
+  /// first connection fails. Use `Required` to authenticate the server.
+  Preferred,
+  /// TLS with a verified server certificate, failing otherwise.
+  Required,
+}
+
+/// The pool for the last server used, built on first use and rebuilt when
+/// the connection settings change. Switching `database` keeps the pool, as
+/// `get_conn` selects it on each checkout.
+#[derive(Default, Clone)]
+pub struct PoolCache(Arc<Mutex<Option<(String, Pool)>>>);
+
+impl PoolCache {
+  /// The pool cached for `key`, or a new one from `build`. Holds the lock
+  /// while building, so concurrent callers share one pool.
+  fn get(&self, key: &str, build: impl FnOnce() -> anyhow::Result<Pool>) -> anyhow::Result<Pool> {
+    let mut cached = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some((_, pool)) = cached.as_ref().filter(|(k, _)| k == key) {
+      return Ok(pool.clone());
+    }
+    let pool = build()?;
+    *cached = Some((key.to_string(), pool.clone()));
+    Ok(pool)
+  }
+}
+
+impl Debug for PoolCache {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let cached = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+    f.debug_struct("PoolCache")
+      .field("initialized", &cached.is_some())
+      .finish()
+  }
+}
+
+/// Representation of geometry columns of any subtype (`POINT`, `POLYGON`,
+/// `MULTI*`, `GEOMETRYCOLLECTION`, ...).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryFormat {
+  /// MySQL's internal SRID + WKB bytes.
+  #[default]
+  Raw,
+  /// Well-known text, decoded from the WKB on the client.
+  Wkt,
+  /// GeoJSON text, decoded from the WKB on the client.
+  GeoJson,
+}
+
+impl GeometryFormat {
+  /// Converts the raw value of a geometry column, decoded on the client so
+  /// that the statement itself is run unchanged. This is not what
+  /// `ST_AsText`/`ST_AsGeoJSON` return: coordinates are written in storage
+  /// axis order, which for geographic SRIDs such as 4326 is
+  /// longitude-latitude, and numbers use Rust's shortest round-trip
+  /// formatting rather than the server's.
+  fn rewrite(self) -> Option<ValueRewrite> {
+    match self {
+      GeometryFormat::Raw => None,
+      GeometryFormat::Wkt => Some(geometry_wkt),
+      GeometryFormat::GeoJson => Some(geometry_geojson),
+    }
+  }
+}
+
+/// Error returned when a result outgrows `MySqlConnection::max_result_bytes`,
+/// so callers can tell it apart from a failed query with `downcast_ref`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResultTooLarge {
+  /// The `max_result_bytes` that was exceeded.
+  pub max_bytes: usize,
+}
+
+impl std::fmt::Display for ResultTooLarge {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "result exceeds {} bytes", self.max_bytes)
+  }
+}
+
+impl std::error::Error for ResultTooLarge {}
+
+/// Output of `MySqlConnection::explain`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExplainFormat {
+  /// One row per table access, as plain `EXPLAIN` prints it.
+  #[default]
+  Traditional,
+  /// A single JSON document with costs, via `EXPLAIN FORMAT=JSON`.
+  Json,
+}
+
+/// Handling of statements (multi-statement strings, `CALL`) that return more
+/// than one result set.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MultiResultPolicy {
+  /// Keep the first result set and discard the rest.
+  #[default]
+  FirstOnly,
+  /// Fail if any result set after the first has columns.
+  Error,
+}
+
+/// Identifier case for result field names.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+  Lower,
+  Upper,
+  #[default]
+  AsIs,
+}
+
+impl Case {
+  pub fn apply(self, name: &str) -> String {
+    match self {
+      Case::Lower => name.to_lowercase(),
+      Case::Upper => name.to_uppercase(),
+      Case::AsIs => name.to_string(),
+    }
+  }
 }
 
 #[async_trait]
@@ -42,39 +285,81 @@ impl Connection for MySqlConnection {
     Ok(self._all_columns()?)
   }
 
-  async fn query(&self, sql: &str, _limit: usize, _offset: usize) -> anyhow::Result<RawArrowData> {
-    self._query(sql)
+  async fn query(&self, sql: &str, limit: usize, offset: usize) -> anyhow::Result<RawArrowData> {
+    self._query(&effective_sql(sql, limit, offset))
+  }
+
+  async fn query_with_params(
+    &self,
+    sql: &str,
+    params: Vec<serde_json::Value>,
+    limit: usize,
+    offset: usize,
+  ) -> anyhow::Result<RawArrowData> {
+    let sql = effective_sql(sql, limit, offset);
+    let params: Vec<Value> = params.iter().map(param_value).collect();
+    let mut conn = self.get_conn()?;
+    let result = conn.exec_iter(self.tagged(&sql), params)?;
+    self.result_to_arrow(result, &sql)
   }
 
   async fn query_all(&self, sql: &str) -> anyhow::Result<RawArrowData> {
     self._query(sql)
   }
 
+  async fn execute(&self, sql: &str) -> anyhow::Result<ExecResult> {
+    let mut conn = self.get_conn()?;
+    conn.query_drop(self.tagged(sql))?;
+    Ok(ExecResult {
+      affected_rows: conn.affected_rows(),
+      last_insert_id: conn.last_insert_id(),
+    })
+  }
+
   async fn table_row_count(&self, table: &str, r#where: &str) -> anyhow::Result<usize> {
     self._table_row_count(table, r#where)
   }
 
   async fn show_schema(&self, schema: &str) -> anyhow::Result<RawArrowData> {
-    let sql = format!(
-      "select * from information_schema.tables where TABLE_SCHEMA='{schema}' order by TABLE_TYPE, TABLE_NAME"
-    );
-    self.query(&sql, 0, 0).await
+    let schema = match schema {
+      "" => self.current_database().unwrap_or_default(),
+      schema => schema,
+    };
+    self.query(&show_schema_sql(schema), 0, 0).await
   }
 
   async fn show_column(&self, schema: Option<&str>, table: &str) -> anyhow::Result<RawArrowData> {
-    let (db, tbl) = if schema.is_none() && table.contains('.') {
-      let parts: Vec<&str> = table.splitn(2, '.').collect();
-      (parts[0], parts[1])
-    } else {
-      ("", table)
-    };
-    let sql = format!(
-      "select * from information_schema.columns where table_schema='{db}' and table_name='{tbl}'"
-    );
+    let (db, tbl) = self.split_table(schema, table);
+    let sql = show_column_sql(db, tbl);
     log::info!("show columns: {}", &sql);
     self.query(&sql, 0, 0).await
   }
 
+  async fn show_indexes(&self, schema: Option<&str>, table: &str) -> anyhow::Result<RawArrowData> {
+    let (db, tbl) = self.split_table(schema, table);
+    self.query(&show_indexes_sql(db, tbl), 0, 0).await
+  }
+
+  async fn export(&self, sql: &str, file: &str) -> anyhow::Result<()> {
+    self.export_query(sql, file, EXPORT_BATCH_SIZE, None)?;
+    Ok(())
+  }
+
+  async fn test_connection(&self) -> anyhow::Result<()> {
+    let checked = self
+      .get_conn()
+      .and_then(|mut conn| Ok(conn.query_drop("SELECT 1")?));
+    checked.map_err(|e| {
+      match e
+        .downcast_ref::<Error>()
+        .and_then(|err| self.connect_error_message(err))
+      {
+        Some(message) => e.context(message),
+        None => e,
+      }
+    })
+  }
+
   #[allow(clippy::unused_async)]
   async fn query_count(&self, sql: &str) -> anyhow::Result<usize> {
     let mut conn = self.get_conn()?;
@@ -93,26 +378,155 @@ impl MySqlConnection {
       port: port.to_string(),
       username: username.to_string(),
       password: password.to_string(),
-      database: None,
+      ..Self::default()
     }
   }
 
+  /// A copy of this connection with `db` as its database. It shares the
+  /// connection pool, so nothing is reconnected.
+  pub fn with_database(&self, db: &str) -> Self {
+    Self {
+      database: Some(db.to_string()),
+      ..self.clone()
+    }
+  }
+
+  /// Switch the database later calls run in.
+  pub fn use_database(&mut self, db: &str) {
+    self.database = Some(db.to_string());
+  }
+
+  /// The schema and table name of `table`, which may be `schema.table`
+  /// when no schema is given; the selected database otherwise.
+  fn split_table<'a>(&'a self, schema: Option<&'a str>, table: &'a str) -> (&'a str, &'a str) {
+    match (schema, table.split_once('.')) {
+      (Some(schema), _) => (schema, table),
+      (None, Some((db, tbl))) => (db, tbl),
+      (None, None) => (self.current_database().unwrap_or_default(), table),
+    }
+  }
+
+  /// The selected database, if any.
+  fn current_database(&self) -> Option<&str> {
+    self.database.as_deref().filter(|db| !db.is_empty())
+  }
+
   fn get_url(&self) -> String {
     format!(
-      "mysql://{}:{}@{}:{}/{}",
-      self.username,
-      self.password,
-      self.host,
-      self.port,
-      self.database.clone().unwrap_or_default(),
+      "{}{}",
+      self.server_url(),
+      self.database.clone().unwrap_or_default()
     )
   }
 
+  /// `get_url` without the database, which the pool does not depend on.
+  fn server_url(&self) -> String {
+    format!(
+      "mysql://{}:{}@{}:{}/",
+      self.username, self.password, self.host, self.port,
+    )
+  }
+
+  /// What a failed connection attempt means for the user, for the errors
+  /// caused by wrong connection settings.
+  fn connect_error_message(&self, err: &Error) -> Option<String> {
+    let address = format!("{}:{}", self.host, self.port);
+    match err {
+      Error::MySqlError(e) => match e.code {
+        ER_ACCESS_DENIED_ERROR => Some(format!(
+          "access denied for user '{}': check the username and password",
+          self.username
+        )),
+        ER_DBACCESS_DENIED_ERROR => Some(format!(
+          "user '{}' has no access to database '{}'",
+          self.username,
+          self.database.as_deref().unwrap_or_default()
+        )),
+        ER_BAD_DB_ERROR => Some(format!(
+          "unknown database '{}'",
+          self.database.as_deref().unwrap_or_default()
+        )),
+        CR_CONN_HOST_ERROR => Some(format!("cannot reach MySQL server at {address}")),
+        _ => None,
+      },
+      Error::IoError(_) | Error::DriverError(DriverError::CouldNotConnect(_)) => {
+        Some(format!("cannot reach MySQL server at {address}"))
+      }
+      _ => None,
+    }
+  }
+
+  fn connect_timeout(&self) -> Duration {
+    self.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT)
+  }
+
+  fn ssl_opts(&self) -> Option<SslOpts> {
+    match self.ssl_mode {
+      SslMode::Disabled => None,
+      SslMode::Preferred => Some(SslOpts::default().with_danger_accept_invalid_certs(true)),
+      SslMode::Required => Some(SslOpts::default().with_root_cert_path(self.ca_cert_path.clone())),
+    }
+  }
+
+  fn build_pool(&self, url: &str) -> anyhow::Result<Pool> {
+    let opts: Opts = OptsBuilder::from_opts(Opts::from_url(url)?)
+      .socket(self.socket.clone())
+      .tcp_connect_timeout(Some(self.connect_timeout()))
+      .read_timeout(self.read_timeout)
+      .write_timeout(self.write_timeout)
+      .into();
+    let tls = OptsBuilder::from_opts(opts.clone()).ssl_opts(self.ssl_opts());
+    // `Pool::new` may succeed without a TLS session being set up, so the
+    // first checkout decides whether Preferred falls back.
+    let pool = Pool::new(tls).and_then(|pool| pool.get_conn().map(|_| pool));
+    match pool {
+      Err(e) if self.ssl_mode == SslMode::Preferred => {
+        log::warn!("TLS connection failed, falling back to plaintext: {e}");
+        Ok(Pool::new(opts)?)
+      }
+      pool => Ok(pool?),
+    }
+  }
+
   fn get_conn(&self) -> anyhow::Result<PooledConn> {
-    let binding = self.get_url();
-    let url = binding.as_str();
-    let pool = Pool::new(url)?;
-    Ok(pool.get_conn()?)
+    let url = self.server_url();
+    // connections without a database are kept apart, since one selected by
+    // a `USE` cannot be unselected
+    let key = format!(
+      "{url} {} {:?} {:?} {:?} {:?} {:?} {:?}",
+      self.current_database().is_some(),
+      self.socket,
+      self.ssl_mode,
+      self.ca_cert_path,
+      self.connect_timeout,
+      self.read_timeout,
+      self.write_timeout
+    );
+    self
+      .with_retry(|| {
+        let mut conn = self.pool.get(&key, || self.build_pool(&url))?.get_conn()?;
+        // a pooled connection may have been switched by an earlier `USE`
+        if let Some(db) = self.current_database() {
+          conn.query_drop(format!("USE {}", quote_ident(db)))?;
+        }
+        Ok(conn)
+      })
+      .map_err(|e| {
+        with_timeout_context(e, || {
+          format!(
+            "connection to {}:{} timed out after {:?}",
+            self.host,
+            self.port,
+            self.connect_timeout()
+          )
+        })
+      })
+  }
+
+  /// The SQL actually sent to the server, carrying the query tag if set.
+  /// An existing tag (e.g. SQL copied from the processlist) is replaced.
+  fn tagged(&self, sql: &str) -> String {
+    tag_sql(strip_tag(sql), self.query_tag.as_deref())
   }
 
   fn get_schema(&self) -> Vec<Table> {
@@ -127,18 +541,21 @@ impl MySqlConnection {
       TABLE_NAME as table_name,
       TABLE_TYPE as table_type,
       if(TABLE_TYPE='BASE TABLE', 'table', 'view') as type,
-      CAST(round(((data_length + IFNULL(index_length, 0)) / 1024 / 1024)) AS UNSIGNED)  AS size
+      CAST(round(((data_length + IFNULL(index_length, 0)) / 1024 / 1024)) AS UNSIGNED)  AS size,
+      -- views carry the placeholder comment 'VIEW'
+      nullif(if(TABLE_TYPE='VIEW', '', TABLE_COMMENT), '') as comment
     from information_schema.tables
     ";
     let tables = conn.query_map(
       sql,
-      |(table_schema, table_name, table_type, r#type, size)| Table {
+      |(table_schema, table_name, table_type, r#type, size, comment)| Table {
         db_name: table_schema,
         table_name,
         table_type,
         r#type,
         size: Some(size),
         schema: None,
+        comment,
       },
     )?;
     Ok(tables)
@@ -151,128 +568,442 @@ impl MySqlConnection {
         table_schema,
         table_name,
         column_name,
-        column_type
+        column_type,
+        column_comment
     FROM information_schema.columns
     -- WHERE table_schema NOT IN ('mysql', 'performance_schema', 'information_schema', 'sys') -- 排除系统库
     ORDER BY table_schema, table_name, ordinal_position;
     ";
 
-    let rows: Vec<(String, String, String, String)> = conn.query(sql)?;
+    let rows: Vec<(String, String, String, String, String)> = conn.query(sql)?;
 
     // 使用 HashMap 按数据库和表名分组列信息
-    let mut groups: HashMap<(String, String), Vec<(String, String)>> = HashMap::new();
-    for (db, table, col, dtype) in rows {
-      groups
-        .entry((db, table))
-        .or_insert_with(Vec::new)
-        .push((col, dtype));
+    let mut groups: HashMap<(String, String), Metadata> = HashMap::new();
+    for (db, table, col, dtype, comment) in rows {
+      let metadata = groups
+        .entry((db.clone(), table.clone()))
+        .or_insert_with(|| Metadata {
+          database: db,
+          table,
+          ..Default::default()
+        });
+      if !comment.is_empty() {
+        metadata.comments.insert(col.clone(), comment);
+      }
+      metadata.columns.push((col, dtype));
     }
     // 转换为最终结构
-    let metadata_list: Vec<Metadata> = groups
-      .into_iter()
-      .map(|((database, table), columns)| Metadata {
-        database,
-        table,
-        columns,
-      })
-      .collect();
+    let metadata_list: Vec<Metadata> = groups.into_values().collect();
 
     Ok(metadata_list)
   }
 
   fn _query(&self, sql: &str) -> anyhow::Result<RawArrowData> {
     let mut conn = self.get_conn()?;
+    self.query_on(&mut conn, sql).map_err(|e| {
+      with_timeout_context(e, || match self.read_timeout {
+        Some(timeout) => format!("query timed out after {timeout:?}"),
+        None => "query timed out".to_string(),
+      })
+    })
+  }
+
+  /// Run `sql` on an already checked out connection.
+  fn query_on(&self, conn: &mut PooledConn, sql: &str) -> anyhow::Result<RawArrowData> {
+    let result = conn.query_iter(self.tagged(sql))?;
+    self.result_to_arrow(result, sql)
+  }
+
+  /// Read `result`, the result of `sql` over either protocol, into Arrow.
+  fn result_to_arrow<P: Protocol>(
+    &self,
+    mut result: QueryResult<'_, '_, '_, P>,
+    sql: &str,
+  ) -> anyhow::Result<RawArrowData> {
+    if let Some(affected) = skip_to_result_set(&mut result)? {
+      return affected_rows_data(sql, affected);
+    }
+    let columns = result.columns();
+    let mut converter = BatchConverter::new(self, columns.as_ref());
+    let mut tables: Vec<Vec<Value>> = vec![vec![]; converter.num_columns()];
+    let mut size = 0;
+    if let Some(result_set) = result.iter() {
+      for row in result_set {
+        size += converter.push_row(&mut tables, row?);
+        self.check_result_size(size)?;
+      }
+    }
+    self.finish_result(&mut result)?;
+
+    let batch = converter.batch(tables, 0)?;
+    Ok(RawArrowData {
+      total: batch.num_rows(),
+      batch,
+      titles: Some(converter.titles),
+      sql: Some(sql.to_string()),
+    })
+  }
+
+  /// Fail with `ResultTooLarge` once `size` bytes exceed `max_result_bytes`.
+  fn check_result_size(&self, size: usize) -> Result<(), ResultTooLarge> {
+    match self.max_result_bytes {
+      Some(max_bytes) if size > max_bytes => Err(ResultTooLarge { max_bytes }),
+      _ => Ok(()),
+    }
+  }
+
+  /// Read the result sets after the first, which may have a different
+  /// shape. The trailing OK packet of a `CALL` has no columns and is not
+  /// counted.
+  fn finish_result<P: Protocol>(
+    &self,
+    result: &mut QueryResult<'_, '_, '_, P>,
+  ) -> anyhow::Result<()> {
+    let mut extra = 0;
+    while let Some(result_set) = result.iter() {
+      if !result_set.columns().as_ref().is_empty() {
+        extra += 1;
+      }
+    }
+    if extra > 0 && self.multi_result == MultiResultPolicy::Error {
+      return Err(anyhow!(
+        "statement returned {} result sets, expected one",
+        extra + 1
+      ));
+    }
+    Ok(())
+  }
+
+  fn to_batch(
+    &self,
+    schema: SchemaRef,
+    tables: Vec<Vec<Value>>,
+    row_offset: usize,
+  ) -> anyhow::Result<RecordBatch> {
+    match self.strict_types {
+      true => build_batch_strict(schema, tables, row_offset),
+      false => build_batch(schema, tables),
+    }
+  }
+
+  /// The execution plan of `sql`. A JSON plan is one `EXPLAIN` row with its
+  /// title marked as JSON.
+  pub async fn explain(&self, sql: &str, format: ExplainFormat) -> anyhow::Result<RawArrowData> {
+    let mut data = self._query(&explain_sql(sql, format))?;
+    if format == ExplainFormat::Json {
+      for title in data.titles.iter_mut().flatten() {
+        title.is_json = true;
+      }
+    }
+    Ok(data)
+  }
+
+  /// Run `sql` and rename the result fields and titles to `names`.
+  pub async fn query_as(&self, sql: &str, names: &[String]) -> anyhow::Result<RawArrowData> {
+    rename_columns(self._query(sql)?, names)
+  }
+
+  /// Run `sql` with `db` as the default database, so unqualified table names
+  /// resolve there; the connection's previous default is restored after.
+  /// The query result is returned even if restoring fails.
+  pub async fn query_in_db(&self, db: &str, sql: &str) -> anyhow::Result<RawArrowData> {
+    let mut conn = self.get_conn()?;
+    let previous: Option<String> = conn.query_first("select database()")?.flatten();
+    conn.query_drop(format!("USE {}", quote_ident(db)))?;
+    let result = self.query_on(&mut conn, sql);
+    let restored = match previous {
+      Some(previous) => conn
+        .query_drop(format!("USE {}", quote_ident(&previous)))
+        .inspect_err(|e| log::warn!("could not switch back to {previous}: {e}"))
+        .is_ok(),
+      // there is no `USE` back to no database
+      None => false,
+    };
+    if !restored {
+      // closed rather than returned to the pool still on `db`
+      drop(conn.unwrap());
+    }
+    result
+  }
 
-    let mut result = conn.query_iter(sql)?;
+  /// Page `page` (1-based) of `sql` plus the total number of matching rows,
+  /// which is also stored in `total`.
+  ///
+  /// Servers before 8.0.17 count with `SQL_CALC_FOUND_ROWS`; later ones,
+  /// where it is deprecated, with a separate `COUNT(*)` on the same
+  /// connection, unless the derived table it needs would have duplicate
+  /// column names. `sql` must not have a `LIMIT` of its own.
+  pub async fn query_page(
+    &self,
+    sql: &str,
+    page: usize,
+    page_size: usize,
+  ) -> anyhow::Result<(RawArrowData, usize)> {
+    if page == 0 {
+      anyhow::bail!("pages are numbered from 1");
+    }
+    let sql = sql.trim().trim_end_matches(';').trim_end();
+    if has_trailing_limit(sql) {
+      anyhow::bail!("query_page adds its own LIMIT; remove the one from the query");
+    }
+    let offset = (page - 1) * page_size;
+    let mut conn = self.get_conn()?;
+    let calc = calc_found_rows_sql(sql);
+    let mut counted = None;
+    if conn.server_version() >= (8, 0, 17) || calc.is_none() {
+      match conn.query_first::<usize, _>(format!("SELECT COUNT(*) FROM ({sql}) AS t")) {
+        Ok(total) => counted = Some(total.unwrap_or_default()),
+        // e.g. `SELECT *` over a join
+        Err(Error::MySqlError(e)) if e.code == ER_DUP_FIELDNAME && calc.is_some() => {}
+        Err(e) => return Err(e.into()),
+      }
+    }
+    let (mut data, total) = match counted {
+      Some(total) => (
+        self.query_on(&mut conn, &page_sql(sql, page_size, offset))?,
+        total,
+      ),
+      None => {
+        let calc = calc.as_deref().unwrap_or(sql);
+        let data = self.query_on(&mut conn, &page_sql(calc, page_size, offset))?;
+        let total: Option<usize> = conn.query_first("SELECT FOUND_ROWS()")?;
+        (data, total.unwrap_or_default())
+      }
+    };
+    data.total = total;
+    Ok((data, total))
+  }
+
+  /// Read `sql` in batches of at most `batch_size` rows, converted like
+  /// `_query` does; returns the row count and the titles, marked from the
+  /// first batch.
+  fn stream_batches(
+    &self,
+    sql: &str,
+    batch_size: usize,
+    mut on_batch: impl FnMut(RecordBatch) -> anyhow::Result<()>,
+  ) -> anyhow::Result<(usize, Vec<Title>)> {
+    if batch_size == 0 {
+      anyhow::bail!("batch_size must be at least 1");
+    }
+    let mut conn = self.get_conn()?;
+    let mut result = conn.query_iter(self.tagged(sql))?;
+    if skip_to_result_set(&mut result)?.is_some() {
+      return Ok((0, vec![]));
+    }
     let columns = result.columns();
-    let columns = columns.as_ref();
-    let k = columns.len();
+    let mut converter = BatchConverter::new(self, columns.as_ref());
+    let k = converter.num_columns();
 
-    // let stmt = conn.prep(sql)?;
-    // let k = stmt.num_columns();
-    // let columns = stmt.columns();
+    let mut total = 0;
+    let mut pending = 0;
+    let mut chunk: Vec<Vec<Value>> = vec![vec![]; k];
+    if let Some(result_set) = result.iter() {
+      for row in result_set {
+        converter.push_row(&mut chunk, row?);
+        pending += 1;
+        if pending == batch_size {
+          let tables = std::mem::replace(&mut chunk, vec![vec![]; k]);
+          on_batch(converter.batch(tables, total)?)?;
+          total += pending;
+          pending = 0;
+        }
+      }
+    }
+    if pending > 0 {
+      on_batch(converter.batch(chunk, total)?)?;
+      total += pending;
+    }
+    self.finish_result(&mut result)?;
+    Ok((total, converter.titles))
+  }
 
+  /// Run `sql` and hand its rows to `on_batch` in batches of at most
+  /// `batch_size` rows, so memory stays bounded by one batch whatever the
+  /// size of the result. An error from `on_batch` stops reading. Batches
+  /// have the schema `_query` would return, and the titles are returned at
+  /// the end.
+  pub async fn query_stream(
+    &self,
+    sql: &str,
+    batch_size: usize,
+    on_batch: impl FnMut(RecordBatch) -> anyhow::Result<()>,
+  ) -> anyhow::Result<Vec<Title>> {
+    let (_, titles) = self.stream_batches(sql, batch_size, on_batch)?;
+    Ok(titles)
+  }
+
+  /// Stream `sql` over a bounded channel from a blocking task, so a slow
+  /// consumer holds back reading. The channel closes after the last batch or
+  /// the first error, and the task stops once the receiver is dropped.
+  pub async fn query_channel(
+    &self,
+    sql: &str,
+    batch_size: usize,
+    buffer: usize,
+  ) -> anyhow::Result<mpsc::Receiver<anyhow::Result<RecordBatch>>> {
+    let (tx, rx) = mpsc::channel(buffer.max(1));
+    let conn = self.clone();
+    let sql = sql.to_string();
+    tokio::task::spawn_blocking(move || {
+      let res = conn.stream_batches(&sql, batch_size, |batch| {
+        tx.blocking_send(Ok(batch))
+          .map_err(|_| anyhow!("query channel closed"))
+      });
+      if let Err(err) = res {
+        let _ = tx.blocking_send(Err(err));
+      }
+    });
+    Ok(rx)
+  }
+
+  /// Export `sql` to a CSV file without buffering the whole result.
+  ///
+  /// `progress` is called with the cumulative row count after each batch.
+  /// SET columns are written comma-joined, as MySQL returns them.
+  pub fn export_query(
+    &self,
+    sql: &str,
+    file: &str,
+    batch_size: usize,
+    mut progress: Option<&mut dyn FnMut(usize)>,
+  ) -> anyhow::Result<usize> {
+    let mut writer = csv_writer(File::create(file)?, &self.null_string);
+    let mut written = 0;
+    let (total, _) = self.stream_batches(sql, batch_size, |batch| {
+      let batch = join_lists(batch, ",")?;
+      writer.write(&batch)?;
+      written += batch.num_rows();
+      if let Some(progress) = progress.as_mut() {
+        progress(written);
+      }
+      Ok(())
+    })?;
+    Ok(total)
+  }
+
+  /// Map MySQL result columns to Arrow fields and display titles.
+  fn arrow_fields(&self, columns: &[Column]) -> (Vec<Field>, Vec<Title>) {
+    let case = self.normalize_column_case.unwrap_or_default();
+    let names = unique_names(columns.iter().map(|col| case.apply(&col.name_str())));
     let mut fields = vec![];
     let mut titles = vec![];
-    let mut types = vec![];
     for (i, col) in columns.iter().enumerate() {
+      let flags = col.flags();
       let type_ = format!("{:?}", col.column_type());
-      let type_ = type_.strip_suffix("MYSQL_TYPE_").unwrap_or(type_.as_str());
-      println!("{i}: {:?}, {:?}", col.name_str(), type_);
+      let type_ = if flags.contains(ColumnFlags::SET_FLAG) {
+        "SET"
+      } else if flags.contains(ColumnFlags::ENUM_FLAG) {
+        "ENUM"
+      } else {
+        type_.strip_suffix("MYSQL_TYPE_").unwrap_or(type_.as_str())
+      };
+      log::debug!("column {i}: {:?}, {:?}", col.name_str(), type_);
       titles.push(Title {
         name: col.name_str().to_string(),
         r#type: type_.to_string(),
+        is_json: false,
+        semantic_type: None,
       });
-      types.push(col.column_type());
       let typ = match col.column_type() {
+        // the conventional BOOL/BOOLEAN column
+        MYSQL_TYPE_TINY if col.column_length() == 1 => DataType::Boolean,
+        MYSQL_TYPE_TINY | MYSQL_TYPE_INT24 | MYSQL_TYPE_SHORT | MYSQL_TYPE_LONG
+        | MYSQL_TYPE_LONGLONG
+          if flags.contains(ColumnFlags::UNSIGNED_FLAG) =>
+        {
+          DataType::UInt64
+        }
         MYSQL_TYPE_TINY | MYSQL_TYPE_INT24 | MYSQL_TYPE_SHORT | MYSQL_TYPE_LONG
         | MYSQL_TYPE_LONGLONG => DataType::Int64,
-        MYSQL_TYPE_DECIMAL
-        | MYSQL_TYPE_NEWDECIMAL
-        | MYSQL_TYPE_FLOAT
-        | MYSQL_TYPE_YEAR
-        | MYSQL_TYPE_DOUBLE => DataType::Float64,
-        MYSQL_TYPE_DATETIME => DataType::Utf8,
-        MYSQL_TYPE_DATE => DataType::Utf8,
-        MYSQL_TYPE_BLOB => DataType::Utf8,
+        // wider than Decimal128 stays text so no digit is lost
+        MYSQL_TYPE_DECIMAL | MYSQL_TYPE_NEWDECIMAL => {
+          decimal_type(decimal_precision(col), col.decimals() as u32).unwrap_or(DataType::Utf8)
+        }
+        MYSQL_TYPE_FLOAT | MYSQL_TYPE_YEAR | MYSQL_TYPE_DOUBLE => DataType::Float64,
+        MYSQL_TYPE_DATE | MYSQL_TYPE_NEWDATE if self.temporal_as_epoch => DataType::Int32,
+        MYSQL_TYPE_DATETIME | MYSQL_TYPE_TIMESTAMP if self.temporal_as_epoch => DataType::Int64,
+        MYSQL_TYPE_DATE | MYSQL_TYPE_NEWDATE => DataType::Date32,
+        MYSQL_TYPE_DATETIME | MYSQL_TYPE_TIMESTAMP => timestamp_type(),
+        MYSQL_TYPE_TIME => time_type(),
+        MYSQL_TYPE_BLOB | MYSQL_TYPE_TINY_BLOB | MYSQL_TYPE_MEDIUM_BLOB | MYSQL_TYPE_LONG_BLOB
+          if col.character_set() == BINARY_CHARSET =>
+        {
+          DataType::Binary
+        }
+        MYSQL_TYPE_BLOB | MYSQL_TYPE_TINY_BLOB | MYSQL_TYPE_MEDIUM_BLOB | MYSQL_TYPE_LONG_BLOB => {
+          DataType::Utf8
+        }
+        // SET values arrive as a comma-joined string
+        MYSQL_TYPE_STRING if flags.contains(ColumnFlags::SET_FLAG) => set_list_type(),
         MYSQL_TYPE_STRING | MYSQL_TYPE_VAR_STRING | MYSQL_TYPE_VARCHAR => DataType::Utf8,
+        MYSQL_TYPE_GEOMETRY if self.geometry_format != GeometryFormat::Raw => DataType::Utf8,
         _ => DataType::Binary,
       };
-      let field = Field::new(col.name_str(), typ, true);
-      fields.push(field);
-    }
-    let mut tables: Vec<Vec<Value>> = (0..k).map(|_| vec![]).collect();
-    while let Some(result_set) = result.iter() {
-      for row in result_set.flatten() {
-        for (i, _col) in row.columns_ref().iter().enumerate() {
-          let val = row.get::<Value, _>(i).unwrap();
-          tables[i].push(val);
-        }
-      }
+      // expressions have no originating table, and their flag is not
+      // reliable
+      let nullable = !flags.contains(ColumnFlags::NOT_NULL_FLAG) || col.org_table_ref().is_empty();
+      fields.push(Field::new(&names[i], typ, nullable));
     }
+    (fields, titles)
+  }
 
-    let mut arrs = vec![];
-    for (type_, col) in types.iter().zip(tables) {
-      let arr: ArrayRef = match type_ {
-        MYSQL_TYPE_TINY | MYSQL_TYPE_INT24 | MYSQL_TYPE_SHORT | MYSQL_TYPE_LONG
-        | MYSQL_TYPE_LONGLONG => Arc::new(Int64Array::from(convert_to_i64_arr(&col))),
-        MYSQL_TYPE_DECIMAL
-        | MYSQL_TYPE_NEWDECIMAL
-        | MYSQL_TYPE_FLOAT
-        | MYSQL_TYPE_YEAR
-        | MYSQL_TYPE_DOUBLE => Arc::new(Float64Array::from(convert_to_f64_arr(&col))),
-        MYSQL_TYPE_STRING | MYSQL_TYPE_VAR_STRING | MYSQL_TYPE_VARCHAR => {
-          Arc::new(StringArray::from(convert_to_str_arr(&col)))
+  /// Per-column conversion of raw values to what the chosen Arrow type's
+  /// builder expects, matching `arrow_fields`.
+  fn value_rewrites(&self, columns: &[Column]) -> Vec<Option<ValueRewrite>> {
+    columns
+      .iter()
+      .map(|col| match col.column_type() {
+        MYSQL_TYPE_DATE | MYSQL_TYPE_NEWDATE if self.temporal_as_epoch => {
+          Some(epoch_days as ValueRewrite)
         }
-        MYSQL_TYPE_DATETIME => Arc::new(StringArray::from(convert_to_str_arr(&col))),
-        MYSQL_TYPE_DATE => Arc::new(StringArray::from(convert_to_str_arr(&col))),
-        MYSQL_TYPE_BLOB => Arc::new(StringArray::from(convert_to_str_arr(&col))),
-        _ => Arc::new(StringArray::from(convert_to_str_arr(&col))),
-      };
-
-      arrs.push(arr);
-    }
-
-    let schema = Schema::new(fields);
-    let batch = RecordBatch::try_new(Arc::new(schema), arrs)?;
-    Ok(RawArrowData {
-      total: batch.num_rows(),
-      batch,
-      titles: Some(titles.clone()),
-      sql: Some(sql.to_string()),
-    })
+        MYSQL_TYPE_DATETIME | MYSQL_TYPE_TIMESTAMP if self.temporal_as_epoch => {
+          Some(epoch_micros as ValueRewrite)
+        }
+        MYSQL_TYPE_GEOMETRY => self.geometry_format.rewrite(),
+        _ => None,
+      })
+      .collect()
   }
 
   fn _table_row_count(&self, table: &str, cond: &str) -> anyhow::Result<usize> {
     let mut conn = self.get_conn()?;
     let mut sql = format!("select count(*) from {table}");
     if !cond.is_empty() {
+      // a failed index lookup just means a plain count
+      if let Ok(Some(index)) = self.count_index(&mut conn, table, cond) {
+        sql = format!("{sql} USE INDEX ({})", quote_ident(&index));
+      }
       sql = format!("{sql} where {cond}");
     }
     conn
       .query_first::<usize, _>(&sql)?
       .ok_or_else(|| anyhow!("No value found"))
   }
+  /// An index led by the column `cond` starts by comparing, to steer a
+  /// filtered `COUNT(*)` away from a full scan; `None` leaves the choice to
+  /// the optimizer.
+  fn count_index(
+    &self,
+    conn: &mut PooledConn,
+    table: &str,
+    cond: &str,
+  ) -> anyhow::Result<Option<String>> {
+    let Some(column) = leading_column(cond) else {
+      return Ok(None);
+    };
+    let (schema, table) = split_table_name(table);
+    let indexes: Vec<(String, String)> = conn.exec(
+      r"
+      select INDEX_NAME, COLUMN_NAME from information_schema.statistics
+      where TABLE_SCHEMA = coalesce(?, database()) and TABLE_NAME = ? and SEQ_IN_INDEX = 1
+      order by INDEX_NAME
+      ",
+      (schema, table),
+    )?;
+    Ok(choose_count_index(&indexes, &column))
+  }
+
   fn _sql_row_count(&self, sql: &str) -> anyhow::Result<usize> {
     let mut conn = self.get_conn()?;
     conn
@@ -281,99 +1012,2007 @@ impl MySqlConnection {
   }
 }
 
-fn convert_to_str(unknown_val: &Value) -> Option<String> {
-  match unknown_val {
-    val @ Value::Bytes(..) => {
-      let val = from_value::<Vec<u8>>(val.clone());
-      String::from_utf8(val).ok()
+/// Rename the columns of `data` and their titles to `names`, in order,
+/// keeping types and values; fails if the column count differs.
+fn rename_columns(data: RawArrowData, names: &[String]) -> anyhow::Result<RawArrowData> {
+  let schema = data.batch.schema();
+  if schema.fields().len() != names.len() {
+    return Err(anyhow!(
+      "query returned {} columns but {} names were given",
+      schema.fields().len(),
+      names.len()
+    ));
+  }
+  let fields: Vec<Field> = schema
+    .fields()
+    .iter()
+    .zip(names)
+    .map(|(f, name)| f.as_ref().clone().with_name(name))
+    .collect();
+  let schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+  let batch = RecordBatch::try_new(schema, data.batch.columns().to_vec())?;
+  let titles = data.titles.map(|titles| {
+    titles
+      .into_iter()
+      .zip(names)
+      .map(|(title, name)| Title {
+        name: name.clone(),
+        ..title
+      })
+      .collect()
+  });
+  Ok(RawArrowData {
+    batch,
+    titles,
+    ..data
+  })
+}
+
+/// Move `result` to the first result set with columns, skipping statements
+/// before it such as the `INSERT` of `INSERT ...; SELECT ...`. Returns the
+/// rows they affected if no result set has columns.
+fn skip_to_result_set<P: Protocol>(
+  result: &mut QueryResult<'_, '_, '_, P>,
+) -> anyhow::Result<Option<u64>> {
+  let mut affected = 0;
+  while result.columns().as_ref().is_empty() {
+    affected += result.affected_rows();
+    match result.iter() {
+      Some(result_set) => {
+        for row in result_set {
+          row?;
+        }
+      }
+      None => return Ok(Some(affected)),
     }
-    _ => None,
   }
+  Ok(None)
 }
 
-fn convert_to_str_arr(values: &[Value]) -> Vec<Option<String>> {
-  values.iter().map(convert_to_str).collect()
+/// Turns the rows of one result set into Arrow batches, the same way for
+/// `_query` and the streaming methods.
+struct BatchConverter<'a> {
+  conn: &'a MySqlConnection,
+  schema: SchemaRef,
+  titles: Vec<Title>,
+  rewrites: Vec<Option<ValueRewrite>>,
+  text_columns: Vec<bool>,
+  /// Schema of the first batch. Later batches are cast to exactly its
+  /// fields, so dictionary encoding is decided once per result.
+  first_schema: Option<SchemaRef>,
 }
 
-fn convert_to_i64(unknown_val: &Value) -> Option<i64> {
-  match unknown_val {
-    val @ Value::Int(..) => {
-      let val = from_value::<i64>(val.clone());
-      Some(val)
+impl<'a> BatchConverter<'a> {
+  fn new(conn: &'a MySqlConnection, columns: &[Column]) -> Self {
+    let (fields, titles) = conn.arrow_fields(columns);
+    // zero dates become nulls even in NOT NULL columns; allowing for them up
+    // front keeps the fields the same for every batch of a stream
+    let fields: Vec<Field> = fields
+      .into_iter()
+      .zip(columns)
+      .map(|(field, col)| {
+        let zero_dates = !conn.strict_types
+          && matches!(
+            col.column_type(),
+            MYSQL_TYPE_DATE | MYSQL_TYPE_NEWDATE | MYSQL_TYPE_DATETIME | MYSQL_TYPE_TIMESTAMP
+          );
+        let nullable = field.is_nullable() || zero_dates;
+        field.with_nullable(nullable)
+      })
+      .collect();
+    Self {
+      conn,
+      schema: Arc::new(Schema::new(fields)),
+      titles,
+      rewrites: conn.value_rewrites(columns),
+      text_columns: columns.iter().map(is_text_column).collect(),
+      first_schema: None,
+    }
+  }
+
+  fn num_columns(&self) -> usize {
+    self.rewrites.len()
+  }
+
+  /// Append `row` to the column-major `tables`; returns its approximate
+  /// size in bytes.
+  fn push_row(&self, tables: &mut [Vec<Value>], row: Row) -> usize {
+    let mut size = 0;
+    for (i, val) in row.unwrap().into_iter().enumerate() {
+      size += value_size(&val);
+      tables[i].push(rewrite_value(self.rewrites[i], val));
+    }
+    size
+  }
+
+  /// The batch of `tables`, whose first row is row `row_offset` of the
+  /// result. The title marks are sampled from the first batch only.
+  fn batch(&mut self, tables: Vec<Vec<Value>>, row_offset: usize) -> anyhow::Result<RecordBatch> {
+    let conn = self.conn;
+    if self.first_schema.is_none() {
+      if conn.detect_json_text {
+        mark_json_titles(&self.text_columns, &tables, &mut self.titles);
+      }
+      if conn.infer_semantic_types {
+        mark_semantic_titles(&self.text_columns, &tables, &mut self.titles);
+      }
     }
-    val @ Value::UInt(..) => {
-      let val = from_value::<i64>(val.clone());
-      Some(val)
+    let batch = conn.to_batch(self.schema.clone(), tables, row_offset)?;
+    if let Some(schema) = &self.first_schema {
+      return cast_batch(batch, schema);
     }
-    val @ Value::Bytes(..) => {
-      let val = from_value::<i64>(val.clone());
-      Some(val)
+    let batch = match conn.dictionary_encode_strings {
+      Some(threshold) => dictionary_encode(batch, threshold)?,
+      None => batch,
+    };
+    self.first_schema = Some(batch.schema());
+    Ok(batch)
+  }
+}
+
+/// Split `db.t`, `` `db`.`t` `` or `t` into schema and table name.
+fn split_table_name(name: &str) -> (Option<String>, String) {
+  let mut parts = vec![];
+  let mut part = String::new();
+  let mut quoted = false;
+  let mut chars = name.trim().chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '`' if quoted && chars.peek() == Some(&'`') => {
+        chars.next();
+        part.push('`');
+      }
+      '`' => quoted = !quoted,
+      '.' if !quoted => parts.push(std::mem::take(&mut part)),
+      _ => part.push(c),
     }
-    _ => None,
   }
+  parts.push(part);
+  let table = parts.pop().unwrap_or_default();
+  (parts.pop(), table)
 }
 
-fn convert_to_i64_arr(values: &[Value]) -> Vec<Option<i64>> {
-  values.iter().map(convert_to_i64).collect()
+/// A bare or backquoted identifier at the start of `s`, and the rest.
+fn read_ident(s: &str) -> Option<(String, &str)> {
+  if let Some(quoted) = s.strip_prefix('`') {
+    let end = quoted.find('`')?;
+    return Some((quoted[..end].to_string(), &quoted[end + 1..]));
+  }
+  let end = s
+    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+    .unwrap_or(s.len());
+  let starts_with_digit = s.starts_with(|c: char| c.is_ascii_digit());
+  (end > 0 && !starts_with_digit).then(|| (s[..end].to_string(), &s[end..]))
 }
 
-fn convert_to_i32(unknown_val: &Value) -> Option<i32> {
-  match unknown_val {
-    val @ Value::Int(..) => {
-      let val = from_value::<i32>(val.clone());
-      Some(val)
+/// The column a WHERE clause starts by comparing, e.g. `customer_id` in
+/// `o.customer_id = 3 and ...`; `None` if it starts with anything else,
+/// such as `NOT`, a function call or a literal.
+fn leading_column(cond: &str) -> Option<String> {
+  let mut rest = cond.trim_start_matches(|c: char| c == '(' || c.is_whitespace());
+  let column = loop {
+    let (ident, tail) = read_ident(rest)?;
+    match tail.strip_prefix('.') {
+      Some(tail) => rest = tail,
+      None => {
+        rest = tail.trim_start();
+        break ident;
+      }
     }
-    _ => None,
+  };
+  let word = rest
+    .split(|c: char| !c.is_ascii_alphabetic())
+    .next()
+    .unwrap_or_default();
+  let predicate = rest.starts_with(['=', '<', '>', '!'])
+    || ["in", "between", "like", "is"]
+      .iter()
+      .any(|k| word.eq_ignore_ascii_case(k));
+  predicate.then_some(column)
+}
+
+/// Pick an index whose first column is `column`, preferring secondary
+/// indexes since counting over the clustered PRIMARY key reads whole rows.
+fn choose_count_index(indexes: &[(String, String)], column: &str) -> Option<String> {
+  let mut candidates = indexes
+    .iter()
+    .filter(|(_, col)| col.eq_ignore_ascii_case(column));
+  let first = candidates.clone().next();
+  candidates
+    .find(|(name, _)| name != "PRIMARY")
+    .or(first)
+    .map(|(name, _)| name.clone())
+}
+
+/// Result of statements without a result set: one `affected_rows` row.
+fn affected_rows_data(sql: &str, affected: u64) -> anyhow::Result<RawArrowData> {
+  let schema = Schema::new(vec![Field::new("affected_rows", DataType::UInt64, false)]);
+  let batch = RecordBatch::try_new(
+    Arc::new(schema),
+    vec![Arc::new(UInt64Array::from(vec![affected]))],
+  )?;
+  Ok(RawArrowData {
+    total: affected as usize,
+    batch,
+    titles: Some(vec![Title {
+      name: "affected_rows".to_string(),
+      r#type: "LONGLONG".to_string(),
+      ..Title::default()
+    }]),
+    sql: Some(sql.to_string()),
+  })
+}
+
+fn show_schema_sql(schema: &str) -> String {
+  format!(
+    "select * from information_schema.tables where TABLE_SCHEMA='{}' order by TABLE_TYPE, TABLE_NAME",
+    escape_literal(schema)
+  )
+}
+
+fn show_column_sql(schema: &str, table: &str) -> String {
+  format!(
+    "select * from information_schema.columns where table_schema='{}' and table_name='{}'",
+    escape_literal(schema),
+    escape_literal(table)
+  )
+}
+
+/// `sql` without trailing semicolons, prefixed with the `EXPLAIN` for
+/// `format`.
+fn explain_sql(sql: &str, format: ExplainFormat) -> String {
+  let sql = sql
+    .trim()
+    .trim_end_matches(|c: char| c == ';' || c.is_whitespace());
+  match format {
+    ExplainFormat::Traditional => format!("EXPLAIN {sql}"),
+    ExplainFormat::Json => format!("EXPLAIN FORMAT=JSON {sql}"),
   }
 }
 
-fn convert_to_i32_arr(values: &[Value]) -> Vec<Option<i32>> {
-  values.iter().map(convert_to_i32).collect()
+/// Index columns of a table, the primary key first and composite indexes in
+/// column order.
+fn show_indexes_sql(schema: &str, table: &str) -> String {
+  format!(
+    "select INDEX_NAME as index_name, COLUMN_NAME as column_name, \
+     SEQ_IN_INDEX as seq_in_index, NON_UNIQUE = 0 as is_unique, INDEX_TYPE as index_type \
+     from information_schema.statistics where table_schema='{}' and table_name='{}' \
+     order by INDEX_NAME <> 'PRIMARY', INDEX_NAME, SEQ_IN_INDEX",
+    escape_literal(schema),
+    escape_literal(table)
+  )
 }
 
-fn convert_to_u64(unknown_val: &Value) -> Option<u64> {
-  match unknown_val {
-    val @ Value::UInt(..) => {
-      let val = from_value::<u64>(val.clone());
-      Some(val)
-    }
-    val @ Value::Int(..) => {
-      let val = from_value::<u64>(val.clone());
-      Some(val)
+/// `sql` with `SQL_CALC_FOUND_ROWS` after its leading `SELECT`, or `None`
+/// for other statements.
+fn calc_found_rows_sql(sql: &str) -> Option<String> {
+  let sql = sql.trim_start();
+  let keyword = sql.get(..6)?;
+  let rest = &sql[6..];
+  if !keyword.eq_ignore_ascii_case("select") || !rest.starts_with(char::is_whitespace) {
+    return None;
+  }
+  Some(format!("SELECT SQL_CALC_FOUND_ROWS{rest}"))
+}
+
+/// Whether `sql` ends in `LIMIT n`, `LIMIT m, n` or `LIMIT n OFFSET m`,
+/// optionally followed by a locking clause. The counts may be literals,
+/// `?` placeholders or variables.
+fn has_trailing_limit(sql: &str) -> bool {
+  let tokens = sql_tokens(sql);
+  let end = locking_clause(&tokens).unwrap_or(tokens.len());
+  let tokens = &tokens[..end];
+  let Some(pos) = tokens.iter().rposition(|t| t.depth == 0 && t.is("limit")) else {
+    return false;
+  };
+  let operand =
+    |t: &Token| t.depth == 0 && !matches!(t.text, "(" | ")" | "," | ";") && !t.is("offset");
+  match &tokens[pos + 1..] {
+    [n] => operand(n),
+    [m, comma, n] if comma.text == "," => operand(m) && operand(n),
+    [n, keyword, m] if keyword.is("offset") => operand(n) && operand(m),
+    _ => false,
+  }
+}
+
+/// A token of SQL text outside string literals, quoted identifiers and
+/// comments, with its byte offset and parenthesis depth.
+struct Token<'a> {
+  text: &'a str,
+  start: usize,
+  depth: usize,
+}
+
+impl Token<'_> {
+  fn is(&self, keyword: &str) -> bool {
+    self.text.eq_ignore_ascii_case(keyword)
+  }
+}
+
+/// End of the quoted string, identifier or comment starting at `i`, or
+/// `None` if none starts there.
+fn skip_quoted_or_comment(bytes: &[u8], i: usize) -> Option<usize> {
+  let rest = &bytes[i..];
+  match rest[0] {
+    quote @ (b'\'' | b'"' | b'`') => {
+      let mut j = i + 1;
+      while j < bytes.len() {
+        match bytes[j] {
+          b'\\' if quote != b'`' => j += 2,
+          b if b == quote && bytes.get(j + 1) == Some(&quote) => j += 2,
+          b if b == quote => return Some(j + 1),
+          _ => j += 1,
+        }
+      }
+      Some(bytes.len())
     }
-    val @ Value::Bytes(..) => {
-      let val = from_value::<u64>(val.clone());
-      Some(val)
+    b'#' => Some(line_end(bytes, i)),
+    b'-' if rest.starts_with(b"--") && rest.get(2).is_none_or(|b| b.is_ascii_whitespace()) => {
+      Some(line_end(bytes, i))
     }
+    b'/' if rest.starts_with(b"/*") => Some(
+      bytes[i + 2..]
+        .windows(2)
+        .position(|w| w == b"*/")
+        .map_or(bytes.len(), |p| i + 2 + p + 2),
+    ),
     _ => None,
   }
 }
 
-fn convert_to_u64_arr(values: &[Value]) -> Vec<Option<u64>> {
-  values.iter().map(convert_to_u64).collect()
+fn line_end(bytes: &[u8], i: usize) -> usize {
+  bytes[i..]
+    .iter()
+    .position(|&b| b == b'\n')
+    .map_or(bytes.len(), |p| i + p)
 }
 
-fn convert_to_f64(unknown_val: &Value) -> Option<f64> {
-  match unknown_val {
-    val @ Value::Float(..) => {
-      let val = from_value::<f64>(val.clone());
-      Some(val)
+/// Words, numbers, `?` placeholders, `@variables` and punctuation of `sql`.
+fn sql_tokens(sql: &str) -> Vec<Token<'_>> {
+  let bytes = sql.as_bytes();
+  let word =
+    |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'@' | b'$' | b'.') || b >= 0x80;
+  let mut tokens = vec![];
+  let mut depth = 0;
+  let mut i = 0;
+  while i < bytes.len() {
+    if let Some(end) = skip_quoted_or_comment(bytes, i) {
+      i = end;
+      continue;
+    }
+    let start = i;
+    match bytes[i] {
+      b if b.is_ascii_whitespace() => {
+        i += 1;
+        continue;
+      }
+      b if word(b) => {
+        while i < bytes.len() && word(bytes[i]) {
+          i += 1;
+        }
+      }
+      _ => i += 1,
     }
-    val @ Value::Double(..) => {
-      let val = from_value::<f64>(val.clone());
-      Some(val)
+    let text = &sql[start..i];
+    if text == ")" {
+      depth = usize::saturating_sub(depth, 1);
     }
-    val @ Value::Bytes(..) => {
-      let val = from_value::<f64>(val.clone());
-      Some(val)
+    tokens.push(Token { text, start, depth });
+    if text == "(" {
+      depth += 1;
     }
+  }
+  tokens
+}
+
+/// Index of the token starting a trailing `FOR UPDATE`, `FOR SHARE` or
+/// `LOCK IN SHARE MODE` clause, with any `OF`, `NOWAIT` or `SKIP LOCKED`.
+fn locking_clause(tokens: &[Token]) -> Option<usize> {
+  let starts = |i: usize| {
+    let next = |n: usize, keyword: &str| tokens.get(i + n).is_some_and(|t| t.is(keyword));
+    tokens[i].depth == 0
+      && ((tokens[i].is("for") && (next(1, "update") || next(1, "share")))
+        || (tokens[i].is("lock") && next(1, "in") && next(2, "share") && next(3, "mode")))
+  };
+  let i = (0..tokens.len()).rev().find(|&i| starts(i))?;
+  // only table names and lock options may follow
+  tokens[i..]
+    .iter()
+    .all(|t| t.depth == 0 && !matches!(t.text, "(" | ")") && !t.is("limit") && !t.is("into"))
+    .then_some(i)
+}
+
+/// The statement `query` runs for `sql`: queries with `LIMIT limit OFFSET
+/// offset` appended when `limit` is non-zero, ahead of any locking clause.
+/// Anything else, including queries that already end in a `LIMIT` and
+/// `SELECT ... INTO`, is run as given.
+pub fn effective_sql(sql: &str, limit: usize, offset: usize) -> String {
+  let body = sql.trim().trim_end_matches(';').trim_end();
+  let keyword = strip_tag(body)
+    .split(|c: char| !c.is_ascii_alphabetic())
+    .next()
+    .unwrap_or_default();
+  let is_query = ["select", "with"]
+    .iter()
+    .any(|k| keyword.eq_ignore_ascii_case(k));
+  if limit == 0 || !is_query || has_trailing_limit(body) {
+    return sql.to_string();
+  }
+  let tokens = sql_tokens(body);
+  if tokens.iter().any(|t| t.depth == 0 && t.is("into")) {
+    return sql.to_string();
+  }
+  with_limit(body, &tokens, limit, offset)
+}
+
+/// `body` with `LIMIT limit OFFSET offset` added ahead of any locking
+/// clause; `tokens` are those of `body`.
+fn with_limit(body: &str, tokens: &[Token], limit: usize, offset: usize) -> String {
+  let (main, locking) = match locking_clause(tokens) {
+    Some(i) => body.split_at(tokens[i].start),
+    None => (body, ""),
+  };
+  // on its own line, so a trailing `--` comment cannot swallow it
+  let mut sql = format!("{}\nLIMIT {limit} OFFSET {offset}", main.trim_end());
+  if !locking.is_empty() {
+    sql = format!("{sql} {locking}");
+  }
+  sql
+}
+
+/// One page of `sql`, which has no `LIMIT` of its own.
+fn page_sql(sql: &str, page_size: usize, offset: usize) -> String {
+  with_limit(sql, &sql_tokens(sql), page_size, offset)
+}
+
+/// Declared precision of a DECIMAL column, from its display length which
+/// counts the sign and decimal point.
+fn decimal_precision(col: &Column) -> u32 {
+  let scale = col.decimals() as u32;
+  let sign = !col.flags().contains(ColumnFlags::UNSIGNED_FLAG) as u32;
+  let point = (scale > 0) as u32;
+  col.column_length().saturating_sub(sign + point)
+}
+
+/// CHAR/VARCHAR/TEXT, but not their binary counterparts.
+fn is_text_column(col: &Column) -> bool {
+  matches!(
+    col.column_type(),
+    MYSQL_TYPE_VARCHAR | MYSQL_TYPE_VAR_STRING | MYSQL_TYPE_STRING | MYSQL_TYPE_BLOB
+  ) && !col.flags().contains(ColumnFlags::BINARY_FLAG)
+}
+
+/// Flag text columns whose sampled values are JSON documents.
+fn mark_json_titles(text_columns: &[bool], tables: &[Vec<Value>], titles: &mut [Title]) {
+  for ((text, values), title) in text_columns.iter().zip(tables).zip(titles) {
+    title.is_json = *text && looks_like_json(values);
+  }
+}
+
+/// Tag text columns whose sampled values share a semantic type.
+fn mark_semantic_titles(text_columns: &[bool], tables: &[Vec<Value>], titles: &mut [Title]) {
+  for ((text, values), title) in text_columns.iter().zip(tables).zip(titles) {
+    title.semantic_type = text.then(|| infer_semantic_type(values)).flatten();
+  }
+}
+
+/// Make field names unique by suffixing repeats with `_1`, `_2`, ...
+fn unique_names(names: impl Iterator<Item = String>) -> Vec<String> {
+  let mut seen = HashSet::new();
+  names
+    .map(|name| {
+      let mut unique = name.clone();
+      let mut n = 1;
+      while !seen.insert(unique.clone()) {
+        unique = format!("{name}_{n}");
+        n += 1;
+      }
+      unique
+    })
+    .collect()
+}
+
+/// A JSON query parameter as a MySQL value; arrays and objects are bound
+/// as their JSON text.
+fn param_value(param: &serde_json::Value) -> Value {
+  match param {
+    serde_json::Value::Null => Value::NULL,
+    serde_json::Value::Bool(b) => Value::Int(*b as i64),
+    serde_json::Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+      (Some(i), _) => Value::Int(i),
+      (None, Some(u)) => Value::UInt(u),
+      _ => Value::Double(n.as_f64().unwrap_or_default()),
+    },
+    serde_json::Value::String(s) => Value::Bytes(s.as_bytes().to_vec()),
+    other => Value::Bytes(other.to_string().into_bytes()),
+  }
+}
+
+/// Adds `message` to errors caused by a connect, read or write timeout.
+fn with_timeout_context(e: anyhow::Error, message: impl FnOnce() -> String) -> anyhow::Error {
+  let kind = match e.downcast_ref::<Error>() {
+    Some(Error::IoError(io)) => Some(io.kind()),
+    // reads and writes of packets fail through the codec
+    Some(Error::CodecError(codec)) => std::error::Error::source(codec)
+      .and_then(|source| source.downcast_ref::<std::io::Error>())
+      .map(std::io::Error::kind),
+    Some(Error::DriverError(DriverError::CouldNotConnect(Some((_, _, kind))))) => Some(*kind),
     _ => None,
+  };
+  match kind {
+    // a socket timeout surfaces as `WouldBlock` on Unix
+    Some(std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock) => e.context(message()),
+    _ => e,
+  }
+}
+
+const TAG_PREFIX: &str = "/* duckling:";
+
+/// Prefix `sql` with a `/* duckling:<tag> */` comment.
+pub(crate) fn tag_sql(sql: &str, tag: Option<&str>) -> String {
+  match tag.filter(|t| !t.is_empty()) {
+    // a `*/` inside the tag would end the comment early
+    Some(tag) => format!("{TAG_PREFIX}{} */ {sql}", tag.replace("*/", "* /")),
+    None => sql.to_string(),
+  }
+}
+
+/// Remove a tag comment added by [`tag_sql`], so statement-kind checks
+/// look at the statement itself.
+pub(crate) fn strip_tag(sql: &str) -> &str {
+  let trimmed = sql.trim_start();
+  trimmed
+    .strip_prefix(TAG_PREFIX)
+    .and_then(|rest| rest.split_once("*/"))
+    .map_or(trimmed, |(_, rest)| rest.trim_start())
+}
+
+/// Quote an identifier with backticks, doubling any embedded backtick.
+pub(crate) fn quote_ident(name: &str) -> String {
+  format!("`{}`", name.replace('`', "``"))
+}
+
+/// Escape a value for use inside a single-quoted SQL string literal.
+pub(crate) fn escape_literal(value: &str) -> String {
+  value.replace('\\', "\\\\").replace('\'', "''")
+}
+
+/// Quote a possibly schema-qualified table name.
+pub(crate) fn quote_table(schema: Option<&str>, table: &str) -> String {
+  match schema {
+    Some(schema) if !schema.is_empty() => format!("{}.{}", quote_ident(schema), quote_ident(table)),
+    _ => quote_ident(table),
   }
 }
 
-fn convert_to_f64_arr(values: &[Value]) -> Vec<Option<f64>> {
-  values.iter().map(convert_to_f64).collect()
+/// The local server the ignored live tests run against.
+#[cfg(test)]
+pub(crate) fn local_connection() -> MySqlConnection {
+  MySqlConnection::new("localhost", "3306", "root", "")
+}
+
+/// A connection for a live test after making sure the `duckling_test`
+/// schema exists.
+#[cfg(test)]
+pub(crate) fn duckling_test_db(conn: &MySqlConnection) -> PooledConn {
+  let mut c = conn.get_conn().unwrap();
+  c.query_drop("create database if not exists duckling_test")
+    .unwrap();
+  c
+}
+
+/// Like `duckling_test_db`, and (re)create `duckling_test.<table>` from
+/// `definition`, everything after the table name in `CREATE TABLE`.
+#[cfg(test)]
+pub(crate) fn duckling_test_table(
+  conn: &MySqlConnection,
+  table: &str,
+  definition: &str,
+) -> PooledConn {
+  let mut c = duckling_test_db(conn);
+  c.query_drop(format!("drop table if exists duckling_test.{table}"))
+    .unwrap();
+  c.query_drop(format!("create table duckling_test.{table} {definition}"))
+    .unwrap();
+  c
 }
 
 #[tokio::test]
 async fn test_query() {}
+
+#[test]
+fn test_tinyint1_as_boolean() {
+  let cols = [
+    Column::new(MYSQL_TYPE_TINY)
+      .with_name(b"active")
+      .with_column_length(1),
+    Column::new(MYSQL_TYPE_TINY)
+      .with_name(b"level")
+      .with_column_length(4),
+  ];
+  let (fields, _) = local_connection().arrow_fields(&cols);
+  assert_eq!(fields[0].data_type(), &DataType::Boolean);
+  assert_eq!(fields[1].data_type(), &DataType::Int64);
+
+  let values = vec![
+    Value::Bytes(b"0".to_vec()),
+    Value::Bytes(b"1".to_vec()),
+    Value::NULL,
+  ];
+  let batch = build_batch(Arc::new(Schema::new(fields)), vec![values.clone(), values]).unwrap();
+  let active = batch.column(0).as_boolean();
+  assert!(!active.value(0));
+  assert!(active.value(1));
+  assert!(active.is_null(2));
+}
+
+#[test]
+fn test_set_column_as_list() {
+  let col = Column::new(MYSQL_TYPE_STRING)
+    .with_name(b"tags")
+    .with_flags(ColumnFlags::SET_FLAG);
+  let (fields, titles) = local_connection().arrow_fields(&[col]);
+  assert_eq!(titles[0].r#type, "SET");
+  let schema = Arc::new(Schema::new(fields));
+  let values = vec![Value::Bytes(b"a,c".to_vec()), Value::NULL];
+  let batch = build_batch(schema, vec![values]).unwrap();
+  let list = batch.column(0).as_list::<i32>();
+  let first = list.value(0);
+  let first = first.as_string::<i32>();
+  assert_eq!(first.len(), 2);
+  assert_eq!(first.value(0), "a");
+  assert_eq!(first.value(1), "c");
+  assert!(list.is_null(1));
+}
+
+#[test]
+fn test_normalize_column_case() {
+  let conn = MySqlConnection {
+    normalize_column_case: Some(Case::Lower),
+    ..local_connection()
+  };
+  let cols = ["UserId", "USERID", "Name"]
+    .map(|name| Column::new(MYSQL_TYPE_VAR_STRING).with_name(name.as_bytes()));
+  let (fields, titles) = conn.arrow_fields(&cols);
+  let names: Vec<&str> = fields.iter().map(|f| f.name().as_str()).collect();
+  assert_eq!(names, ["userid", "userid_1", "name"]);
+  assert_eq!(titles[1].name, "USERID");
+}
+
+#[test]
+fn test_rename_columns() {
+  let data = || {
+    let schema = Arc::new(Schema::new(vec![
+      Field::new("a", DataType::Int64, true),
+      Field::new("b", DataType::Utf8, true),
+    ]));
+    let batch = RecordBatch::try_new(
+      schema,
+      vec![
+        Arc::new(Int64Array::from(vec![1])),
+        Arc::new(StringArray::from(vec!["x"])),
+      ],
+    )
+    .unwrap();
+    let title = |name: &str, r#type: &str| Title {
+      name: name.to_string(),
+      r#type: r#type.to_string(),
+      is_json: false,
+      semantic_type: None,
+    };
+    RawArrowData {
+      titles: Some(vec![title("a", "LONG"), title("b", "VAR_STRING")]),
+      ..RawArrowData::from_batch(batch)
+    }
+  };
+  let names = ["id".to_string(), "label".to_string()];
+  let renamed = rename_columns(data(), &names).unwrap();
+  assert_eq!(renamed.batch.schema().field(0).name(), "id");
+  assert_eq!(renamed.batch.schema().field(1).data_type(), &DataType::Utf8);
+  let titles = renamed.titles.unwrap();
+  assert_eq!(titles[1].name, "label");
+  assert_eq!(titles[1].r#type, "VAR_STRING");
+  assert!(rename_columns(data(), &names[..1]).is_err());
+}
+
+#[test]
+fn test_count_index() {
+  assert_eq!(split_table_name("orders"), (None, "orders".to_string()));
+  assert_eq!(
+    split_table_name("`shop`.`my.orders`"),
+    (Some("shop".to_string()), "my.orders".to_string())
+  );
+  let column = |cond| leading_column(cond);
+  assert_eq!(column("customer_id > 10").as_deref(), Some("customer_id"));
+  assert_eq!(
+    column("(o.`customer_id` in (1, 2)) and status = 'x'").as_deref(),
+    Some("customer_id")
+  );
+  assert_eq!(column("status IS NULL").as_deref(), Some("status"));
+  // keywords, functions and literals are not columns
+  assert_eq!(column("not status = 'x'"), None);
+  assert_eq!(column("exists (select 1)"), None);
+  assert_eq!(column("lower(status) = 'x'"), None);
+  assert_eq!(column("1 = 1"), None);
+
+  let indexes = [
+    ("PRIMARY", "id"),
+    ("idx_customer", "customer_id"),
+    ("idx_created", "created_at"),
+  ]
+  .map(|(i, c)| (i.to_string(), c.to_string()));
+  assert_eq!(
+    choose_count_index(&indexes, "Customer_ID").as_deref(),
+    Some("idx_customer")
+  );
+  assert_eq!(
+    choose_count_index(&indexes, "id").as_deref(),
+    Some("PRIMARY")
+  );
+  assert_eq!(choose_count_index(&indexes, "name"), None);
+}
+
+#[test]
+#[ignore = "requires a running MySQL server"]
+fn test_indexed_count_matches_naive() {
+  let conn = local_connection();
+  let mut c = duckling_test_table(
+    &conn,
+    "counted",
+    "(id int primary key, customer_id int, status varchar(8), key idx_customer (customer_id))",
+  );
+  c.query_drop(
+    "insert into duckling_test.counted values \
+     (1, 1, 'new'), (2, 1, 'done'), (3, 2, 'new'), (4, null, 'new'), (5, 3, 'done')",
+  )
+  .unwrap();
+  let table = "duckling_test.counted";
+  assert_eq!(
+    conn.count_index(&mut c, table, "customer_id >= 1").unwrap(),
+    Some("idx_customer".to_string())
+  );
+  for cond in [
+    "customer_id >= 1",
+    "customer_id in (1, 3) and status = 'new'",
+    "status = 'new'",
+    "not customer_id = 1",
+  ] {
+    let fast = conn._table_row_count(table, cond).unwrap();
+    let naive = conn
+      ._sql_row_count(&format!("select count(*) from {table} where {cond}"))
+      .unwrap();
+    assert_eq!(fast, naive, "{cond}");
+  }
+}
+
+#[test]
+#[ignore = "requires a running MySQL server"]
+fn test_geometry_as_geojson() {
+  let conn = MySqlConnection {
+    geometry_format: GeometryFormat::GeoJson,
+    ..local_connection()
+  };
+  let res = conn
+    ._query("select 1 as id, ST_GeomFromText('POINT(1 2)') as pt")
+    .unwrap();
+  assert_eq!(res.batch.schema().field(1).data_type(), &DataType::Utf8);
+  let pt = res.batch.column(1).as_string::<i32>().value(0);
+  let json: serde_json::Value = serde_json::from_str(pt).unwrap();
+  assert_eq!(json["type"], "Point");
+  assert_eq!(json["coordinates"], serde_json::json!([1.0, 2.0]));
+}
+
+#[test]
+#[ignore = "requires a running MySQL server"]
+fn test_geometry_collection_as_wkt() {
+  let conn = MySqlConnection {
+    geometry_format: GeometryFormat::Wkt,
+    ..local_connection()
+  };
+  let mut c = duckling_test_table(&conn, "shapes", "(id int, g geometrycollection)");
+  c.query_drop(
+    "insert into duckling_test.shapes values \
+     (1, ST_GeomFromText('GEOMETRYCOLLECTION(POINT(1 2),LINESTRING(0 0,1 1))')), \
+     (2, NULL)",
+  )
+  .unwrap();
+
+  let res = conn
+    ._query("select id, g from duckling_test.shapes order by id")
+    .unwrap();
+  assert_eq!(res.batch.schema().field(1).data_type(), &DataType::Utf8);
+  let g = res.batch.column(1).as_string::<i32>();
+  assert!(
+    g.value(0)
+      .to_ascii_uppercase()
+      .starts_with("GEOMETRYCOLLECTION(")
+  );
+  assert!(g.value(0).to_ascii_uppercase().contains("LINESTRING"));
+  assert!(g.is_null(1));
+}
+
+#[test]
+fn test_calc_found_rows_sql() {
+  assert_eq!(
+    calc_found_rows_sql("  select id from t").as_deref(),
+    Some("SELECT SQL_CALC_FOUND_ROWS id from t")
+  );
+  assert_eq!(calc_found_rows_sql("selectivity"), None);
+  assert_eq!(calc_found_rows_sql("show tables"), None);
+  assert_eq!(page_sql("select 1", 10, 20), "select 1\nLIMIT 10 OFFSET 20");
+  assert_eq!(
+    page_sql("select * from t for update", 10, 0),
+    "select * from t\nLIMIT 10 OFFSET 0 for update"
+  );
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_query_page() {
+  let conn = local_connection();
+  let sql = "with recursive n (i) as (select 1 union all select i + 1 from n where i < 25) \
+             select i from n order by i";
+  let (first, total) = conn.query_page(sql, 1, 10).await.unwrap();
+  assert_eq!(total, 25);
+  assert_eq!(first.total, 25);
+  assert_eq!(first.batch.num_rows(), 10);
+  let (last, total) = conn.query_page(sql, 3, 10).await.unwrap();
+  assert_eq!(total, 25);
+  assert_eq!(last.batch.num_rows(), 5);
+  assert!(conn.query_page(sql, 0, 10).await.is_err());
+
+  let limited = "select TABLE_NAME from information_schema.tables limit 5";
+  let Err(err) = conn.query_page(limited, 1, 10).await else {
+    panic!("a query with its own LIMIT was paged");
+  };
+  assert!(err.to_string().contains("LIMIT"), "{err}");
+
+  // duplicate column names cannot be counted through a derived table
+  let join = "select * from information_schema.schemata a \
+     join information_schema.schemata b on a.SCHEMA_NAME = b.SCHEMA_NAME";
+  let schemata: Option<usize> = conn
+    .get_conn()
+    .unwrap()
+    .query_first("select count(*) from information_schema.schemata")
+    .unwrap();
+  let (page, total) = conn.query_page(join, 1, 2).await.unwrap();
+  assert_eq!(Some(total), schemata);
+  assert_eq!(page.batch.num_rows(), 2);
+}
+
+#[test]
+fn test_param_value() {
+  use serde_json::json;
+  assert_eq!(param_value(&json!(null)), Value::NULL);
+  assert_eq!(param_value(&json!(true)), Value::Int(1));
+  assert_eq!(param_value(&json!(-3)), Value::Int(-3));
+  assert_eq!(param_value(&json!(u64::MAX)), Value::UInt(u64::MAX));
+  assert_eq!(param_value(&json!(1.5)), Value::Double(1.5));
+  assert_eq!(
+    param_value(&json!("O'Brien")),
+    Value::Bytes(b"O'Brien".to_vec())
+  );
+  assert_eq!(
+    param_value(&json!({"a": [1]})),
+    Value::Bytes(br#"{"a":[1]}"#.to_vec())
+  );
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_query_with_params() {
+  let conn = local_connection();
+  let mut c = duckling_test_table(&conn, "params", "(id int, name varchar(20))");
+  c.query_drop("insert into duckling_test.params values (1, 'a'), (2, 'b'), (3, 'c')")
+    .unwrap();
+  drop(c);
+
+  let sql = "select id, name from duckling_test.params where id = ?";
+  let res = conn
+    .query_with_params(sql, vec![serde_json::json!(2)], 0, 0)
+    .await
+    .unwrap();
+  assert_eq!(res.batch.num_rows(), 1);
+  assert_eq!(
+    res
+      .batch
+      .column(0)
+      .as_primitive::<arrow::datatypes::Int64Type>()
+      .value(0),
+    2
+  );
+  assert_eq!(res.batch.column(1).as_string::<i32>().value(0), "b");
+
+  // a parameter is never read as SQL
+  let res = conn
+    .query_with_params(sql, vec![serde_json::json!("1 or 1=1")], 0, 0)
+    .await
+    .unwrap();
+  assert_eq!(res.batch.num_rows(), 1);
+
+  let sql = "select id from duckling_test.params where id > ? order by id";
+  let res = conn
+    .query_with_params(sql, vec![serde_json::json!(0)], 1, 1)
+    .await
+    .unwrap();
+  assert_eq!(res.batch.num_rows(), 1);
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_execute() {
+  let conn = local_connection();
+  conn
+    .execute("create database if not exists duckling_test")
+    .await
+    .unwrap();
+  conn
+    .execute("drop table if exists duckling_test.exec")
+    .await
+    .unwrap();
+  conn
+    .execute("create table duckling_test.exec (id int auto_increment primary key, name text)")
+    .await
+    .unwrap();
+
+  let first = conn
+    .execute("insert into duckling_test.exec (name) values ('a')")
+    .await
+    .unwrap();
+  assert_eq!(first.affected_rows, 1);
+  let second = conn
+    .execute("insert into duckling_test.exec (name) values ('b'), ('c')")
+    .await
+    .unwrap();
+  assert_eq!(second.affected_rows, 2);
+  // the id of the first row of a multi-row insert
+  assert_eq!(second.last_insert_id, first.last_insert_id + 1);
+
+  let update = conn
+    .execute("update duckling_test.exec set name = 'x'")
+    .await
+    .unwrap();
+  assert_eq!(update.affected_rows, 3);
+  assert_eq!(update.last_insert_id, 0);
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_comments() {
+  let conn = local_connection();
+  duckling_test_table(
+    &conn,
+    "commented",
+    "(id int comment 'primary id', name text) comment 'documented table'",
+  );
+
+  let tables = conn.get_tables().unwrap();
+  let table = tables
+    .iter()
+    .find(|t| t.db_name == "duckling_test" && t.table_name == "commented")
+    .unwrap();
+  assert_eq!(table.comment.as_deref(), Some("documented table"));
+
+  let metadata = conn.all_columns().await.unwrap();
+  let table = metadata
+    .iter()
+    .find(|m| m.database == "duckling_test" && m.table == "commented")
+    .unwrap();
+  assert_eq!(table.columns.len(), 2);
+  assert_eq!(table.comments.get("id").unwrap(), "primary id");
+  assert!(!table.comments.contains_key("name"));
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_query_in_db_without_database() {
+  let conn = local_connection();
+  conn
+    .query_in_db("mysql", "select count(*) from user")
+    .await
+    .unwrap();
+  // more checkouts than the pool holds, so every connection is seen
+  for _ in 0..12 {
+    let mut c = conn.get_conn().unwrap();
+    let db: Option<Option<String>> = c.query_first("select database()").unwrap();
+    assert_eq!(db.flatten(), None);
+  }
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_query_in_db() {
+  let conn = MySqlConnection {
+    database: Some("mysql".to_string()),
+    ..local_connection()
+  };
+  let mut c = duckling_test_db(&conn);
+  c.query_drop("create table if not exists duckling_test.in_db (id int)")
+    .unwrap();
+  c.query_drop("truncate table duckling_test.in_db").unwrap();
+  c.query_drop("insert into duckling_test.in_db values (1), (2)")
+    .unwrap();
+  drop(c);
+
+  let res = conn
+    .query_in_db("duckling_test", "select id from in_db")
+    .await
+    .unwrap();
+  assert_eq!(res.batch.num_rows(), 2);
+  assert!(
+    conn
+      .query_in_db("duckling_test", "select * from user")
+      .await
+      .is_err()
+  );
+}
+
+#[test]
+fn test_check_result_size() {
+  let conn = MySqlConnection {
+    max_result_bytes: Some(4096),
+    ..Default::default()
+  };
+  assert!(conn.check_result_size(4096).is_ok());
+  let err = anyhow::Error::from(conn.check_result_size(4097).unwrap_err());
+  assert_eq!(
+    err.downcast_ref::<ResultTooLarge>(),
+    Some(&ResultTooLarge { max_bytes: 4096 })
+  );
+  assert_eq!(err.to_string(), "result exceeds 4096 bytes");
+  assert!(
+    MySqlConnection::default()
+      .check_result_size(usize::MAX)
+      .is_ok()
+  );
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_max_result_bytes() {
+  let sql = "with recursive n (i) as (select 1 union all select i + 1 from n where i < 1000) \
+             select i, repeat('x', 100) as s from n";
+  let conn = MySqlConnection {
+    max_result_bytes: Some(4096),
+    ..local_connection()
+  };
+  let Err(err) = conn.query(sql, 0, 0).await else {
+    panic!("result over the byte budget was returned");
+  };
+  assert_eq!(
+    err.downcast_ref::<ResultTooLarge>(),
+    Some(&ResultTooLarge { max_bytes: 4096 })
+  );
+  let res = conn.query("select 1", 0, 0).await.unwrap();
+  assert_eq!(res.batch.num_rows(), 1);
+}
+
+#[test]
+fn test_field_nullability() {
+  let cols = [
+    Column::new(MYSQL_TYPE_LONGLONG)
+      .with_name(b"id")
+      .with_org_table(b"orders")
+      .with_flags(ColumnFlags::NOT_NULL_FLAG),
+    Column::new(MYSQL_TYPE_DATETIME)
+      .with_name(b"shipped_at")
+      .with_org_table(b"orders")
+      .with_flags(ColumnFlags::NOT_NULL_FLAG),
+    Column::new(MYSQL_TYPE_VAR_STRING)
+      .with_name(b"note")
+      .with_org_table(b"orders"),
+    Column::new(MYSQL_TYPE_LONGLONG)
+      .with_name(b"n")
+      .with_flags(ColumnFlags::NOT_NULL_FLAG),
+  ];
+  let (fields, _) = local_connection().arrow_fields(&cols);
+  let nullable: Vec<bool> = fields.iter().map(|f| f.is_nullable()).collect();
+  assert_eq!(nullable, [false, false, true, true]);
+
+  // a zero date in a NOT NULL column becomes null, so the field is relaxed
+  let tables = vec![
+    vec![Value::Int(1)],
+    vec![Value::Bytes(b"0000-00-00 00:00:00".to_vec())],
+    vec![Value::NULL],
+    vec![Value::Int(2)],
+  ];
+  let batch = build_batch(Arc::new(Schema::new(fields)), tables).unwrap();
+  let schema = batch.schema();
+  assert!(!schema.field(0).is_nullable());
+  assert!(schema.field(1).is_nullable());
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_query_nullability() {
+  let conn = local_connection();
+  let mut c = duckling_test_table(
+    &conn,
+    "nullability",
+    "(id int not null, name varchar(8) not null, note text null)",
+  );
+  c.query_drop("insert into duckling_test.nullability values (1, 'a', null)")
+    .unwrap();
+  let res = conn
+    .query_all("select id, name, note, id + 1 as next from duckling_test.nullability")
+    .await
+    .unwrap();
+  let nullable: Vec<bool> = res
+    .batch
+    .schema()
+    .fields()
+    .iter()
+    .map(|f| f.is_nullable())
+    .collect();
+  assert_eq!(nullable, [false, false, true, true]);
+}
+
+#[test]
+fn test_blob_fields() {
+  let cols = [
+    Column::new(MYSQL_TYPE_BLOB)
+      .with_name(b"image")
+      .with_character_set(BINARY_CHARSET),
+    Column::new(MYSQL_TYPE_BLOB)
+      .with_name(b"body")
+      .with_character_set(255),
+  ];
+  let (fields, _) = local_connection().arrow_fields(&cols);
+  assert_eq!(fields[0].data_type(), &DataType::Binary);
+  assert_eq!(fields[1].data_type(), &DataType::Utf8);
+
+  let png = vec![0x89, b'P', b'N', b'G', 0xff, 0x00];
+  let tables = vec![
+    vec![Value::Bytes(png.clone())],
+    vec![Value::Bytes("héllo".as_bytes().to_vec())],
+  ];
+  let batch = build_batch(Arc::new(Schema::new(fields)), tables).unwrap();
+  assert_eq!(batch.column(0).as_binary::<i32>().value(0), png.as_slice());
+  assert_eq!(batch.column(1).as_string::<i32>().value(0), "héllo");
+}
+
+#[test]
+fn test_unsigned_bigint() {
+  let cols = [
+    Column::new(MYSQL_TYPE_LONGLONG)
+      .with_name(b"u")
+      .with_flags(ColumnFlags::UNSIGNED_FLAG),
+    Column::new(MYSQL_TYPE_LONGLONG).with_name(b"i"),
+  ];
+  let (fields, _) = local_connection().arrow_fields(&cols);
+  assert_eq!(fields[0].data_type(), &DataType::UInt64);
+  assert_eq!(fields[1].data_type(), &DataType::Int64);
+
+  let tables = vec![
+    vec![
+      Value::Bytes(b"18446744073709551615".to_vec()),
+      Value::UInt(u64::MAX),
+    ],
+    vec![
+      Value::Bytes(b"-9223372036854775808".to_vec()),
+      Value::Int(-1),
+    ],
+  ];
+  let batch = build_batch(Arc::new(Schema::new(fields)), tables).unwrap();
+  let u = batch
+    .column(0)
+    .as_primitive::<arrow::datatypes::UInt64Type>();
+  assert_eq!(u.values(), &[u64::MAX, 18446744073709551615]);
+  let i = batch
+    .column(1)
+    .as_primitive::<arrow::datatypes::Int64Type>();
+  assert_eq!(i.values(), &[i64::MIN, -1]);
+}
+
+#[test]
+fn test_decimal_fields() {
+  let cols = [
+    Column::new(MYSQL_TYPE_NEWDECIMAL)
+      .with_name(b"price")
+      .with_column_length(12)
+      .with_decimals(2),
+    Column::new(MYSQL_TYPE_NEWDECIMAL)
+      .with_name(b"qty")
+      .with_column_length(5)
+      .with_flags(ColumnFlags::UNSIGNED_FLAG),
+    Column::new(MYSQL_TYPE_NEWDECIMAL)
+      .with_name(b"huge")
+      .with_column_length(67)
+      .with_decimals(10),
+  ];
+  let (fields, _) = local_connection().arrow_fields(&cols);
+  assert_eq!(fields[0].data_type(), &DataType::Decimal128(10, 2));
+  assert_eq!(fields[1].data_type(), &DataType::Decimal128(5, 0));
+  assert_eq!(fields[2].data_type(), &DataType::Utf8);
+
+  let tables = vec![
+    vec![Value::Bytes(b"-12345678.90".to_vec())],
+    vec![Value::Bytes(b"99999".to_vec())],
+    vec![Value::Bytes(
+      b"123456789012345678901234567890123456789.0123456789".to_vec(),
+    )],
+  ];
+  let batch = build_batch(Arc::new(Schema::new(fields)), tables).unwrap();
+  let price = batch
+    .column(0)
+    .as_primitive::<arrow::datatypes::Decimal128Type>();
+  assert_eq!(price.value_as_string(0), "-12345678.90");
+  let huge = batch.column(2).as_string::<i32>();
+  assert_eq!(
+    huge.value(0),
+    "123456789012345678901234567890123456789.0123456789"
+  );
+}
+
+#[test]
+fn test_show_sql_escaped() {
+  assert_eq!(
+    show_schema_sql("foo'bar"),
+    "select * from information_schema.tables where TABLE_SCHEMA='foo''bar' \
+     order by TABLE_TYPE, TABLE_NAME"
+  );
+  assert_eq!(
+    show_column_sql("foo'bar", "t\\' or '1'='1"),
+    "select * from information_schema.columns \
+     where table_schema='foo''bar' and table_name='t\\\\'' or ''1''=''1'"
+  );
+}
+
+#[test]
+fn test_explain_sql() {
+  assert_eq!(
+    explain_sql(
+      " select * from t where id = 1 ; ;\n",
+      ExplainFormat::Traditional
+    ),
+    "EXPLAIN select * from t where id = 1"
+  );
+  assert_eq!(
+    explain_sql("select 1", ExplainFormat::Json),
+    "EXPLAIN FORMAT=JSON select 1"
+  );
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_explain() {
+  let conn = local_connection();
+  let sql = "select * from information_schema.tables where table_schema = 'mysql';";
+  let plan = conn.explain(sql, ExplainFormat::Traditional).await.unwrap();
+  assert!(plan.batch.num_rows() > 0);
+  assert!(plan.batch.schema().index_of("select_type").is_ok());
+
+  let plan = conn.explain(sql, ExplainFormat::Json).await.unwrap();
+  assert_eq!(plan.batch.num_rows(), 1);
+  assert!(plan.titles.unwrap()[0].is_json);
+  let doc = plan.batch.column(0).as_string::<i32>().value(0);
+  assert!(serde_json::from_str::<serde_json::Value>(doc).is_ok());
+}
+
+#[test]
+fn test_split_table() {
+  let conn = local_connection().with_database("shop");
+  assert_eq!(conn.split_table(Some("a"), "b.c"), ("a", "b.c"));
+  assert_eq!(conn.split_table(None, "a.b"), ("a", "b"));
+  assert_eq!(conn.split_table(None, "orders"), ("shop", "orders"));
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_show_indexes() {
+  let conn = local_connection();
+  duckling_test_table(
+    &conn,
+    "indexed",
+    "(id int primary key, a int, b int, \
+     unique key uk_a (a), key idx_ba (b, a))",
+  );
+
+  let res = conn
+    .show_indexes(Some("duckling_test"), "indexed")
+    .await
+    .unwrap();
+  let by_name = |name: &str| res.batch.schema().index_of(name).unwrap();
+  let index = res.batch.column(by_name("index_name"));
+  let index = index.as_string::<i32>();
+  let column = res.batch.column(by_name("column_name"));
+  let column = column.as_string::<i32>();
+  let rows: Vec<(&str, &str)> = (0..res.batch.num_rows())
+    .map(|i| (index.value(i), column.value(i)))
+    .collect();
+  assert_eq!(
+    rows,
+    [
+      ("PRIMARY", "id"),
+      ("idx_ba", "b"),
+      ("idx_ba", "a"),
+      ("uk_a", "a")
+    ]
+  );
+
+  let res = conn
+    .show_indexes(None, "duckling_test.indexed")
+    .await
+    .unwrap();
+  assert_eq!(res.batch.num_rows(), 4);
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_show_quoted_schema() {
+  let conn = local_connection();
+  let mut c = conn.get_conn().unwrap();
+  c.query_drop("create database if not exists `foo'bar`")
+    .unwrap();
+  c.query_drop("create table if not exists `foo'bar`.`it's` (id int)")
+    .unwrap();
+  let tables = conn.show_schema("foo'bar").await.unwrap();
+  assert_eq!(tables.batch.num_rows(), 1);
+  let columns = conn.show_column(Some("foo'bar"), "it's").await.unwrap();
+  assert_eq!(columns.batch.num_rows(), 1);
+  c.query_drop("drop database `foo'bar`").unwrap();
+}
+
+#[test]
+fn test_effective_sql() {
+  assert_eq!(
+    effective_sql("select * from users;\n", 100, 200),
+    "select * from users\nLIMIT 100 OFFSET 200"
+  );
+  assert_eq!(
+    effective_sql("WITH a AS (SELECT 1) SELECT * FROM a", 10, 0),
+    "WITH a AS (SELECT 1) SELECT * FROM a\nLIMIT 10 OFFSET 0"
+  );
+  // duplicate column names would be rejected in a derived table
+  assert_eq!(
+    effective_sql("select * from a join b on a.id = b.id", 10, 0),
+    "select * from a join b on a.id = b.id\nLIMIT 10 OFFSET 0"
+  );
+  assert_eq!(
+    effective_sql("select * from t where id > 1 -- recent", 5, 0),
+    "select * from t where id > 1 -- recent\nLIMIT 5 OFFSET 0"
+  );
+  assert_eq!(
+    effective_sql("select * from t for update skip locked", 5, 0),
+    "select * from t\nLIMIT 5 OFFSET 0 for update skip locked"
+  );
+  assert_eq!(
+    effective_sql("SELECT * FROM t LOCK IN SHARE MODE", 5, 0),
+    "SELECT * FROM t\nLIMIT 5 OFFSET 0 LOCK IN SHARE MODE"
+  );
+  assert_eq!(
+    effective_sql("select * from t where note = 'for update'", 5, 0),
+    "select * from t where note = 'for update'\nLIMIT 5 OFFSET 0"
+  );
+  for sql in [
+    "select max(id) into @v from t",
+    "select 1 into outfile '/tmp/x'",
+  ] {
+    assert_eq!(effective_sql(sql, 5, 0), sql);
+  }
+  assert_eq!(effective_sql("select 1", 0, 0), "select 1");
+
+  // a LIMIT of its own is left alone, one in a subquery is not
+  for sql in [
+    "select * from users limit 10",
+    "SELECT * FROM users LIMIT 20, 10;",
+    "select * from users limit 10 offset 20",
+    "select * from users where id > ? limit ?",
+    "select * from users limit ? offset ?",
+    "select * from users limit @n",
+    "select * from users limit @offset, @n",
+    "select * from users limit 10 for update",
+    "select * from users LIMIT 1 FOR SHARE NOWAIT",
+  ] {
+    assert_eq!(effective_sql(sql, 100, 0), sql);
+  }
+  assert_eq!(
+    effective_sql("select * from (select * from t limit 5) s", 2, 1),
+    "select * from (select * from t limit 5) s\nLIMIT 2 OFFSET 1"
+  );
+  // a LIMIT in a string or comment is not the statement's
+  assert_eq!(
+    effective_sql("select * from t where s = 'limit 5'", 2, 0),
+    "select * from t where s = 'limit 5'\nLIMIT 2 OFFSET 0"
+  );
+  assert_eq!(
+    effective_sql("select * from unlimited", 2, 0),
+    "select * from unlimited\nLIMIT 2 OFFSET 0"
+  );
+  for sql in ["show tables", "update t set a = 1", "selection"] {
+    assert_eq!(effective_sql(sql, 100, 0), sql);
+  }
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_query_limit_duplicate_columns() {
+  let conn = local_connection();
+  let sql = "select * from information_schema.tables a \
+     join information_schema.tables b on a.TABLE_NAME = b.TABLE_NAME \
+     and a.TABLE_SCHEMA = b.TABLE_SCHEMA";
+  let res = conn.query(sql, 3, 1).await.unwrap();
+  assert_eq!(res.batch.num_rows(), 3);
+  let schema = res.batch.schema();
+  assert!(schema.index_of("TABLE_NAME").is_ok());
+  assert!(schema.index_of("TABLE_NAME_1").is_ok());
+}
+
+#[test]
+fn test_temporal_as_epoch() {
+  let conn = MySqlConnection {
+    temporal_as_epoch: true,
+    ..local_connection()
+  };
+  let cols = [
+    Column::new(MYSQL_TYPE_DATE).with_name(b"d"),
+    Column::new(MYSQL_TYPE_DATETIME).with_name(b"dt"),
+    Column::new(MYSQL_TYPE_TIME).with_name(b"t"),
+  ];
+  let (fields, _) = conn.arrow_fields(&cols);
+  let rewrites = conn.value_rewrites(&cols);
+  let tables = vec![
+    vec![rewrite_value(
+      rewrites[0],
+      Value::Bytes(b"2024-01-01".to_vec()),
+    )],
+    vec![rewrite_value(
+      rewrites[1],
+      Value::Bytes(b"2024-01-01 00:00:01.5".to_vec()),
+    )],
+    vec![rewrite_value(
+      rewrites[2],
+      Value::Bytes(b"12:00:00".to_vec()),
+    )],
+  ];
+  let batch = build_batch(Arc::new(Schema::new(fields)), tables).unwrap();
+  let days = batch
+    .column(0)
+    .as_primitive::<arrow::datatypes::Int32Type>();
+  assert_eq!(days.value(0), 19723);
+  let micros = batch
+    .column(1)
+    .as_primitive::<arrow::datatypes::Int64Type>();
+  assert_eq!(micros.value(0), 1_704_067_201_500_000);
+  // TIME is left alone
+  assert_eq!(batch.column(2).data_type(), &time_type());
+  let time = batch
+    .column(2)
+    .as_primitive::<arrow::datatypes::DurationMicrosecondType>();
+  assert_eq!(time.value(0), 43_200_000_000);
+
+  // without the option they are Arrow temporal types
+  let (fields, _) = local_connection().arrow_fields(&cols);
+  assert_eq!(fields[0].data_type(), &DataType::Date32);
+  assert_eq!(fields[1].data_type(), &timestamp_type());
+  assert_eq!(fields[2].data_type(), &time_type());
+  let tables = vec![
+    vec![Value::NULL],
+    vec![Value::NULL],
+    vec![Value::Bytes(b"08:15:00".to_vec())],
+  ];
+  let batch = build_batch(Arc::new(Schema::new(fields)), tables).unwrap();
+  assert_eq!(batch.column(2).data_type(), &time_type());
+}
+
+#[test]
+fn test_csv_null_string() {
+  let schema = Arc::new(Schema::new(vec![
+    Field::new("id", DataType::Int64, false),
+    Field::new("s", DataType::Utf8, true),
+  ]));
+  let batch = RecordBatch::try_new(
+    schema,
+    vec![
+      Arc::new(Int64Array::from(vec![1, 2])),
+      Arc::new(StringArray::from(vec![None, Some("")])),
+    ],
+  )
+  .unwrap();
+  let conn = MySqlConnection {
+    null_string: "\\N".to_string(),
+    ..local_connection()
+  };
+  let mut out = vec![];
+  csv_writer(&mut out, &conn.null_string)
+    .write(&batch)
+    .unwrap();
+  assert_eq!(String::from_utf8(out).unwrap(), "id,s\n1,\\N\n2,\n");
+
+  let file = std::env::temp_dir().join(format!("duckling_null_{}.csv", std::process::id()));
+  let file = file.to_str().unwrap();
+  crate::utils::write_csv(file, &batch, &conn.null_string).unwrap();
+  let written = std::fs::read_to_string(file).unwrap();
+  std::fs::remove_file(file).unwrap();
+  assert_eq!(written, "id,s\n1,\\N\n2,\n");
+}
+
+#[test]
+fn test_csv_set_column() {
+  let schema = Arc::new(Schema::new(vec![Field::new("tags", set_list_type(), true)]));
+  let tables = vec![vec![
+    Value::Bytes(b"a,b".to_vec()),
+    Value::Bytes(vec![]),
+    Value::NULL,
+  ]];
+  let batch = join_lists(build_batch(schema, tables).unwrap(), ",").unwrap();
+  assert_eq!(batch.schema().field(0).data_type(), &DataType::Utf8);
+  let mut out = vec![];
+  csv_writer(&mut out, "\\N").write(&batch).unwrap();
+  assert_eq!(
+    String::from_utf8(out).unwrap(),
+    "tags\n\"a,b\"\n\"\"\n\\N\n"
+  );
+}
+
+#[test]
+fn test_detect_json_text() {
+  let cols = [
+    Column::new(MYSQL_TYPE_BLOB).with_name(b"payload"),
+    Column::new(MYSQL_TYPE_VAR_STRING).with_name(b"name"),
+  ];
+  let (_, mut titles) = local_connection().arrow_fields(&cols);
+  let tables = vec![
+    vec![
+      Value::Bytes(br#"{"a": 1}"#.to_vec()),
+      Value::NULL,
+      Value::Bytes(b"[1, 2]".to_vec()),
+    ],
+    vec![
+      Value::Bytes(b"alice".to_vec()),
+      Value::Bytes(b"42".to_vec()),
+    ],
+  ];
+  let text_columns: Vec<bool> = cols.iter().map(is_text_column).collect();
+  mark_json_titles(&text_columns, &tables, &mut titles);
+  assert!(titles[0].is_json);
+  assert!(!titles[1].is_json);
+}
+
+#[test]
+fn test_infer_semantic_types() {
+  use crate::utils::SemanticType;
+
+  let cols = [
+    Column::new(MYSQL_TYPE_VAR_STRING).with_name(b"email"),
+    Column::new(MYSQL_TYPE_VAR_STRING).with_name(b"name"),
+    Column::new(MYSQL_TYPE_LONGLONG).with_name(b"id"),
+  ];
+  let (_, mut titles) = local_connection().arrow_fields(&cols);
+  let tables = vec![
+    vec![
+      Value::Bytes(b"ada@example.org".to_vec()),
+      Value::NULL,
+      Value::Bytes(b"grace.hopper@navy.mil".to_vec()),
+    ],
+    vec![
+      Value::Bytes(b"Ada".to_vec()),
+      Value::Bytes(b"grace.hopper@navy.mil".to_vec()),
+    ],
+    vec![Value::Int(1), Value::Int(2)],
+  ];
+  let text_columns: Vec<bool> = cols.iter().map(is_text_column).collect();
+  mark_semantic_titles(&text_columns, &tables, &mut titles);
+  assert_eq!(titles[0].semantic_type, Some(SemanticType::Email));
+  assert_eq!(titles[1].semantic_type, None);
+  assert_eq!(titles[2].semantic_type, None);
+}
+
+#[test]
+fn test_query_tag() {
+  let conn = MySqlConnection {
+    query_tag: Some("report-42".to_string()),
+    ..local_connection()
+  };
+  let sql = "SELECT id FROM orders";
+  let executed = conn.tagged(sql);
+  assert_eq!(executed, "/* duckling:report-42 */ SELECT id FROM orders");
+  assert_eq!(strip_tag(&executed), sql);
+  assert_eq!(conn.tagged(&executed), executed);
+  assert!(
+    strip_tag(&executed)
+      .to_ascii_lowercase()
+      .starts_with("select")
+  );
+  assert_eq!(tag_sql(sql, None), sql);
+  assert_eq!(
+    tag_sql(sql, Some("a*/b")),
+    "/* duckling:a* /b */ SELECT id FROM orders"
+  );
+}
+
+#[test]
+#[ignore = "requires a running MySQL server"]
+fn test_pooled_conn_uses_database() {
+  let conn = MySqlConnection {
+    database: Some("mysql".to_string()),
+    ..local_connection()
+  };
+  for _ in 0..3 {
+    let mut c = conn.get_conn().unwrap();
+    let db: Option<String> = c.query_first("select database()").unwrap();
+    assert_eq!(db.as_deref(), Some("mysql"));
+    c.query_drop("use information_schema").unwrap();
+  }
+}
+
+#[test]
+fn test_with_timeout_context() {
+  let timed_out = |kind| {
+    let err = Error::IoError(std::io::Error::from(kind));
+    with_timeout_context(err.into(), || "query timed out".to_string()).to_string()
+  };
+  assert_eq!(timed_out(std::io::ErrorKind::TimedOut), "query timed out");
+  assert_eq!(timed_out(std::io::ErrorKind::WouldBlock), "query timed out");
+  assert_ne!(
+    timed_out(std::io::ErrorKind::ConnectionRefused),
+    "query timed out"
+  );
+
+  let err = Error::CodecError(std::io::Error::from(std::io::ErrorKind::WouldBlock).into());
+  let err = with_timeout_context(err.into(), || "query timed out".to_string());
+  assert_eq!(err.to_string(), "query timed out");
+
+  let err = Error::DriverError(DriverError::CouldNotConnect(Some((
+    "10.255.255.1:3306".to_string(),
+    "connection timed out".to_string(),
+    std::io::ErrorKind::TimedOut,
+  ))));
+  let err = with_timeout_context(err.into(), || "connect timed out".to_string());
+  assert_eq!(err.to_string(), "connect timed out");
+}
+
+#[tokio::test]
+#[ignore = "requires network access"]
+async fn test_connect_timeout() {
+  // a non-routable address, so the connection attempt hangs
+  let conn = MySqlConnection {
+    connect_timeout: Some(Duration::from_secs(1)),
+    ..MySqlConnection::new("10.255.255.1", "3306", "root", "")
+  };
+  let start = std::time::Instant::now();
+  let Err(err) = conn._query("SELECT 1") else {
+    panic!("connected to a non-routable address");
+  };
+  assert!(start.elapsed() < Duration::from_secs(5));
+  assert!(err.to_string().contains("timed out"), "{err}");
+}
+
+#[test]
+fn test_connect_error_message() {
+  let conn = MySqlConnection {
+    database: Some("shop".to_string()),
+    ..local_connection()
+  };
+  let server_error = |code| {
+    Error::MySqlError(MySqlError {
+      state: "28000".to_string(),
+      message: String::new(),
+      code,
+    })
+  };
+  let message = |err: Error| conn.connect_error_message(&err).unwrap();
+  assert!(message(server_error(1045)).starts_with("access denied for user 'root'"));
+  assert_eq!(message(server_error(1049)), "unknown database 'shop'");
+  assert_eq!(
+    message(server_error(2003)),
+    "cannot reach MySQL server at localhost:3306"
+  );
+  let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+  assert_eq!(
+    message(Error::IoError(refused)),
+    "cannot reach MySQL server at localhost:3306"
+  );
+  assert!(conn.connect_error_message(&server_error(1064)).is_none());
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_connection_check() {
+  Connection::test_connection(&local_connection())
+    .await
+    .unwrap();
+  let wrong_password = MySqlConnection {
+    password: "not the password".to_string(),
+    ..local_connection()
+  };
+  let Err(e) = Connection::test_connection(&wrong_password).await else {
+    panic!("wrong password accepted");
+  };
+  assert!(e.to_string().starts_with("access denied"), "{e:#}");
+}
+
+#[test]
+fn test_ssl_opts() {
+  assert!(local_connection().ssl_opts().is_none());
+  let conn = MySqlConnection {
+    ssl_mode: SslMode::Required,
+    ca_cert_path: Some(PathBuf::from("/etc/ssl/rds-ca.pem")),
+    ..local_connection()
+  };
+  let opts = conn.ssl_opts().unwrap();
+  assert_eq!(
+    opts.root_cert_path(),
+    Some(std::path::Path::new("/etc/ssl/rds-ca.pem"))
+  );
+  assert!(!opts.accept_invalid_certs());
+  let conn = MySqlConnection {
+    ssl_mode: SslMode::Preferred,
+    ..local_connection()
+  };
+  assert!(conn.ssl_opts().unwrap().accept_invalid_certs());
+}
+
+#[test]
+#[ignore = "requires a running MySQL server"]
+fn test_ssl_preferred_connects() {
+  let conn = MySqlConnection {
+    ssl_mode: SslMode::Preferred,
+    ..local_connection()
+  };
+  let mut c = conn.get_conn().unwrap();
+  let one: Option<i64> = c.query_first("select 1").unwrap();
+  assert_eq!(one, Some(1));
+}
+
+/// Set `MYSQL_SOCKET` to override the socket path.
+#[cfg(feature = "mysql-socket-tests")]
+#[tokio::test]
+async fn test_socket_get_db() {
+  let socket = std::env::var("MYSQL_SOCKET").unwrap_or("/var/run/mysqld/mysqld.sock".to_string());
+  let conn = MySqlConnection {
+    socket: Some(socket),
+    // unreachable over TCP, so only the socket can work
+    port: "1".to_string(),
+    ..local_connection()
+  };
+  let names = |tables: Vec<Table>| -> Vec<(String, String)> {
+    tables
+      .into_iter()
+      .map(|t| (t.db_name, t.table_name))
+      .collect()
+  };
+  let over_socket = names(conn.get_tables().unwrap());
+  let over_tcp = names(local_connection().get_tables().unwrap());
+  assert_eq!(over_socket, over_tcp);
+  assert!(conn.get_db().await.unwrap().children.is_some());
+}
+
+#[test]
+fn test_affected_rows_data() {
+  let res = affected_rows_data("delete from t", 3).unwrap();
+  assert_eq!(res.total, 3);
+  assert_eq!(res.batch.schema().field(0).name(), "affected_rows");
+  let rows = res
+    .batch
+    .column(0)
+    .as_primitive::<arrow::datatypes::UInt64Type>();
+  assert_eq!(rows.values(), &[3]);
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_query_affected_rows() {
+  let conn = local_connection();
+  let mut session = conn.begin_session().unwrap();
+  session
+    .execute("create temporary table tmp_affected (id int)")
+    .unwrap();
+  let res = session
+    .query("insert into tmp_affected values (1), (2), (3)")
+    .unwrap();
+  assert_eq!(res.total, 3);
+  assert_eq!(res.batch.schema().field(0).name(), "affected_rows");
+
+  let res = session
+    .query("update tmp_affected set id = id + 1 where id > 1; select id from tmp_affected")
+    .unwrap();
+  assert_eq!(res.batch.schema().field(0).name(), "id");
+  assert_eq!(res.batch.num_rows(), 3);
+}
+
+#[test]
+#[ignore = "requires a running MySQL server"]
+fn test_pool_reused() {
+  let conn = local_connection();
+  let connected = |c: &mut PooledConn| -> u64 {
+    let row: Option<(String, u64)> = c
+      .query_first("show status like 'Threads_connected'")
+      .unwrap();
+    row.unwrap().1
+  };
+  let before = connected(&mut conn.get_conn().unwrap());
+  for _ in 0..5 {
+    let mut c = conn.clone().get_conn().unwrap();
+    assert_eq!(connected(&mut c), before);
+  }
+  assert!(format!("{conn:?}").contains("initialized: true"));
+}
+
+#[test]
+fn test_with_database() {
+  let conn = local_connection();
+  let shop = conn.with_database("shop");
+  assert_eq!(shop.get_url(), "mysql://root:@localhost:3306/shop");
+  assert_eq!(conn.get_url(), "mysql://root:@localhost:3306/");
+  assert!(Arc::ptr_eq(&conn.pool.0, &shop.pool.0));
+
+  let mut conn = shop;
+  conn.use_database("billing");
+  assert_eq!(conn.get_url(), "mysql://root:@localhost:3306/billing");
+  assert_eq!(conn.current_database(), Some("billing"));
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_use_database() {
+  let mut conn = local_connection().with_database("mysql");
+  conn.get_conn().unwrap();
+  conn.use_database("information_schema");
+  let mut c = conn.get_conn().unwrap();
+  let db: Option<String> = c.query_first("select database()").unwrap();
+  assert_eq!(db.as_deref(), Some("information_schema"));
+  drop(c);
+
+  let res = conn.show_column(None, "TABLES").await.unwrap();
+  assert!(res.batch.num_rows() > 0);
+  let res = conn.show_schema("").await.unwrap();
+  assert!(res.batch.num_rows() > 0);
+}
+
+#[test]
+#[ignore = "requires a running MySQL server"]
+fn test_multi_result_policy() {
+  let sql = "select 1 as a, 2 as b; select 'x' as c";
+  let conn = local_connection();
+  let res = conn._query(sql).unwrap();
+  assert_eq!(res.batch.num_columns(), 2);
+  assert_eq!(res.batch.num_rows(), 1);
+
+  let conn = MySqlConnection {
+    multi_result: MultiResultPolicy::Error,
+    ..local_connection()
+  };
+  assert!(conn._query(sql).is_err());
+  assert!(conn._query("select 1").is_ok());
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_query_channel() {
+  let conn = local_connection();
+  let sql = "select * from information_schema.columns";
+  let expected = conn._query(sql).unwrap().total;
+  let mut rx = conn.query_channel(sql, 100, 2).await.unwrap();
+  let mut total = 0;
+  while let Some(batch) = rx.recv().await {
+    let batch = batch.unwrap();
+    assert!(batch.num_rows() <= 100);
+    total += batch.num_rows();
+  }
+  assert_eq!(total, expected);
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_query_stream() {
+  let conn = local_connection();
+  // 2500 rows while staying below the default CTE recursion limit of 1000
+  let sql = "with recursive seq (n) as \
+     (select 1 union all select n + 1 from seq where n < 100) \
+     select (t.n - 1) * 100 + u.n as n, concat('row ', u.n) as label \
+     from seq t join seq u where t.n <= 25 order by n";
+  let mut sizes = vec![];
+  let mut last = 0;
+  conn
+    .query_stream(sql, 1000, |batch| {
+      assert_eq!(batch.schema().field(1).name(), "label");
+      let n = batch
+        .column(0)
+        .as_primitive::<arrow::datatypes::Int64Type>();
+      last = n.value(n.len() - 1);
+      sizes.push(batch.num_rows());
+      Ok(())
+    })
+    .await
+    .unwrap();
+  assert_eq!(sizes, [1000, 1000, 500]);
+  assert_eq!(last, 2500);
+
+  // a failing consumer stops the stream
+  let mut batches = 0;
+  let res = conn
+    .query_stream(sql, 1000, |_| {
+      batches += 1;
+      Err(anyhow!("consumer failed"))
+    })
+    .await;
+  assert!(res.is_err());
+  assert_eq!(batches, 1);
+}
+
+#[test]
+fn test_batch_schema_is_fixed() {
+  let cols = [Column::new(MYSQL_TYPE_DATETIME)
+    .with_name(b"shipped_at")
+    .with_org_table(b"orders")
+    .with_flags(ColumnFlags::NOT_NULL_FLAG)];
+  let conn = local_connection();
+  let mut converter = BatchConverter::new(&conn, &cols);
+  let datetime = |s: &str| vec![vec![Value::Bytes(s.as_bytes().to_vec())]];
+  let first = converter.batch(datetime("2024-01-01 00:00:00"), 0).unwrap();
+  // the zero date only shows up in the second chunk
+  let second = converter.batch(datetime("0000-00-00 00:00:00"), 1).unwrap();
+  assert_eq!(first.schema(), second.schema());
+  assert!(second.column(0).is_null(0));
+}
+
+#[tokio::test]
+#[ignore = "requires a running MySQL server"]
+async fn test_query_stream_matches_query() {
+  let conn = MySqlConnection {
+    geometry_format: GeometryFormat::Wkt,
+    detect_json_text: true,
+    dictionary_encode_strings: Some(0.6),
+    ..local_connection()
+  };
+  let sql = "with recursive seq (n) as (select 1 union all select n + 1 from seq where n < 8) \
+     select n, if(n % 2, 'odd', 'even') as parity, \
+     cast(json_object('n', n) as char) as doc, ST_GeomFromText('POINT(1 2)') as pt \
+     from seq order by n";
+  let expected = conn._query(sql).unwrap();
+  let schema = expected.batch.schema();
+  assert!(matches!(
+    schema.field(1).data_type(),
+    DataType::Dictionary(..)
+  ));
+  assert_eq!(schema.field(3).data_type(), &DataType::Utf8);
+
+  let mut schemas = vec![];
+  let titles = conn
+    .query_stream(sql, 4, |batch| {
+      schemas.push(batch.schema());
+      Ok(())
+    })
+    .await
+    .unwrap();
+  assert_eq!(schemas, [schema.clone(), schema]);
+  let marks = |titles: &[Title]| {
+    titles
+      .iter()
+      .map(|t| (t.name.clone(), t.is_json))
+      .collect::<Vec<_>>()
+  };
+  assert_eq!(marks(&titles), marks(&expected.titles.unwrap()));
+  assert!(titles[2].is_json);
+
+  let conn = MySqlConnection {
+    multi_result: MultiResultPolicy::Error,
+    ..local_connection()
+  };
+  let res = conn
+    .query_stream("select 1; select 2", 10, |_| Ok(()))
+    .await;
+  assert!(res.is_err());
+  let res = conn.query_stream("select 1", 0, |_| Ok(())).await;
+  assert!(res.is_err());
+}
+
+#[test]
+#[ignore = "requires a running MySQL server"]
+fn test_export_progress() {
+  let conn = local_connection();
+  let file = std::env::temp_dir().join("duckling_export_progress.csv");
+  let mut last = 0;
+  let mut progress = |rows: usize| last = rows;
+  let total = conn
+    .export_query(
+      "select * from information_schema.columns",
+      file.to_str().unwrap(),
+      100,
+      Some(&mut progress),
+    )
+    .unwrap();
+  assert!(total > 0);
+  assert_eq!(last, total);
+}
@@ -0,0 +1,563 @@
+use mysql::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::MySqlConnection;
+
+/// performance_schema timers are in picoseconds.
+pub(crate) const PICOS_PER_SECOND: f64 = 1e12;
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActiveTransaction {
+  /// The processlist id, as accepted by `KILL`.
+  pub thread_id: u64,
+  pub user: String,
+  pub host: String,
+  pub age_seconds: f64,
+  pub isolation_level: String,
+  pub autocommit: bool,
+  pub gtid: Option<String>,
+  pub current_statement: Option<String>,
+}
+
+type TransactionRow = (
+  u64,
+  Option<String>,
+  Option<String>,
+  Option<u64>,
+  Option<String>,
+  Option<String>,
+  Option<String>,
+  Option<String>,
+);
+
+impl ActiveTransaction {
+  fn from_row(
+    (thread_id, user, host, timer_wait, isolation_level, autocommit, gtid, statement): TransactionRow,
+  ) -> Self {
+    Self {
+      thread_id,
+      user: user.unwrap_or_default(),
+      host: host.unwrap_or_default(),
+      age_seconds: timer_wait.unwrap_or_default() as f64 / PICOS_PER_SECOND,
+      isolation_level: isolation_level.unwrap_or_default(),
+      autocommit: autocommit.is_some_and(|v| v.eq_ignore_ascii_case("YES")),
+      // AUTOMATIC means no GTID has been assigned yet
+      gtid: gtid.filter(|g| !g.is_empty() && g != "AUTOMATIC"),
+      current_statement: statement,
+    }
+  }
+}
+
+pub(crate) fn older_than(
+  txs: Vec<ActiveTransaction>,
+  min_age_seconds: f64,
+) -> Vec<ActiveTransaction> {
+  txs
+    .into_iter()
+    .filter(|tx| tx.age_seconds >= min_age_seconds)
+    .collect()
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkStats {
+  pub bytes_received_total: u64,
+  pub bytes_sent_total: u64,
+  pub active_connections: u32,
+  pub errors_total: u64,
+  pub avg_latency_ns: f64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserNetworkStats {
+  pub user: String,
+  pub host: String,
+  pub connections: u32,
+  pub bytes_received: u64,
+  pub bytes_sent: u64,
+  pub avg_latency_ns: f64,
+}
+
+/// Socket summary row: event name, COUNT_STAR, SUM_TIMER_WAIT,
+/// SUM_NUMBER_OF_BYTES_READ, SUM_NUMBER_OF_BYTES_WRITE.
+type SocketSummaryRow = (String, u64, u64, u64, u64);
+
+fn avg_latency_ns(count: u64, timer_wait: u64) -> f64 {
+  if count == 0 {
+    0.0
+  } else {
+    // picoseconds to nanoseconds
+    timer_wait as f64 / count as f64 / 1000.0
+  }
+}
+
+impl NetworkStats {
+  /// Sum the socket summary over all socket types (client connections, TCP
+  /// and unix listeners, admin interface, ...).
+  fn from_summary(rows: &[SocketSummaryRow], active_connections: u32, errors_total: u64) -> Self {
+    let count: u64 = rows.iter().map(|r| r.1).sum();
+    let timer_wait: u64 = rows.iter().map(|r| r.2).sum();
+    Self {
+      bytes_received_total: rows.iter().map(|r| r.3).sum(),
+      bytes_sent_total: rows.iter().map(|r| r.4).sum(),
+      active_connections,
+      errors_total,
+      avg_latency_ns: avg_latency_ns(count, timer_wait),
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableAccessFreq {
+  pub schema_name: String,
+  pub table_name: String,
+  pub total_ops: u64,
+  pub read_pct: f64,
+  pub write_pct: f64,
+  /// Last modification time from `information_schema.tables`;
+  /// performance_schema does not record when a table was last read.
+  pub last_accessed: Option<String>,
+}
+
+/// System schemas left out when no schema is given.
+const SYSTEM_SCHEMAS: &str = "'mysql', 'performance_schema', 'sys', 'information_schema'";
+
+type TableIoRow = (String, String, u64, u64, u64, Option<String>);
+
+impl TableAccessFreq {
+  fn from_row(
+    (schema_name, table_name, total_ops, reads, writes, last_accessed): TableIoRow,
+  ) -> Self {
+    let ops = reads + writes;
+    let pct = |n: u64| {
+      if ops == 0 {
+        0.0
+      } else {
+        n as f64 * 100.0 / ops as f64
+      }
+    };
+    Self {
+      schema_name,
+      table_name,
+      total_ops,
+      read_pct: pct(reads),
+      write_pct: pct(writes),
+      last_accessed,
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableLockStats {
+  pub schema_name: String,
+  pub table_name: String,
+  pub count_read_lock_waits: u64,
+  pub count_write_lock_waits: u64,
+  pub sum_read_lock_wait_ns: u64,
+  pub sum_write_lock_wait_ns: u64,
+  pub avg_read_wait_ms: f64,
+  pub avg_write_wait_ms: f64,
+}
+
+type TableLockRow = (String, String, u64, u64, u64, u64);
+
+fn avg_ms(count: u64, sum_ns: u64) -> f64 {
+  if count == 0 {
+    0.0
+  } else {
+    sum_ns as f64 / count as f64 / 1e6
+  }
+}
+
+impl TableLockStats {
+  fn from_row((schema_name, table_name, reads, writes, read_ns, write_ns): TableLockRow) -> Self {
+    Self {
+      schema_name,
+      table_name,
+      count_read_lock_waits: reads,
+      count_write_lock_waits: writes,
+      sum_read_lock_wait_ns: read_ns,
+      sum_write_lock_wait_ns: write_ns,
+      avg_read_wait_ms: avg_ms(reads, read_ns),
+      avg_write_wait_ms: avg_ms(writes, write_ns),
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryDigest {
+  pub digest: String,
+  pub digest_text: String,
+  pub count_star: u64,
+  pub avg_timer_wait_ms: f64,
+  pub max_timer_wait_ms: f64,
+  pub sum_rows_examined: u64,
+  pub sum_rows_sent: u64,
+  pub sum_no_index_used: u64,
+  pub sum_no_good_index_used: u64,
+}
+
+type DigestRow = (
+  Option<String>,
+  Option<String>,
+  u64,
+  u64,
+  u64,
+  u64,
+  u64,
+  u64,
+  u64,
+);
+
+impl QueryDigest {
+  /// The catch-all row for statements over `performance_schema_digests_size`
+  /// has a `NULL` digest.
+  fn from_row(
+    (digest, digest_text, count_star, avg_wait, max_wait, examined, sent, no_index, no_good_index): DigestRow,
+  ) -> Self {
+    let ms = |picos: u64| picos as f64 * 1000.0 / PICOS_PER_SECOND;
+    Self {
+      digest: digest.unwrap_or_default(),
+      digest_text: digest_text.unwrap_or_default(),
+      count_star,
+      avg_timer_wait_ms: ms(avg_wait),
+      max_timer_wait_ms: ms(max_wait),
+      sum_rows_examined: examined,
+      sum_rows_sent: sent,
+      sum_no_index_used: no_index,
+      sum_no_good_index_used: no_good_index,
+    }
+  }
+}
+
+impl MySqlConnection {
+  fn query_digests(&self, filter: &str) -> anyhow::Result<Vec<QueryDigest>> {
+    let mut conn = self.get_conn()?;
+    let sql = format!(
+      r"
+      select DIGEST, DIGEST_TEXT, COUNT_STAR, AVG_TIMER_WAIT, MAX_TIMER_WAIT,
+        SUM_ROWS_EXAMINED, SUM_ROWS_SENT, SUM_NO_INDEX_USED, SUM_NO_GOOD_INDEX_USED
+      from performance_schema.events_statements_summary_by_digest
+      where {filter}
+      order by SUM_TIMER_WAIT desc
+      "
+    );
+    let rows: Vec<DigestRow> = conn.query(sql)?;
+    Ok(rows.into_iter().map(QueryDigest::from_row).collect())
+  }
+
+  /// Per-digest statement statistics, most total time first.
+  ///
+  /// With `since_reset` the summary is truncated after reading so the next
+  /// call only covers statements run in between.
+  pub fn query_digest_trends(&self, since_reset: bool) -> anyhow::Result<Vec<QueryDigest>> {
+    let digests = self.query_digests("true")?;
+    if since_reset {
+      let mut conn = self.get_conn()?;
+      conn.query_drop("TRUNCATE performance_schema.events_statements_summary_by_digest")?;
+    }
+    Ok(digests)
+  }
+
+  /// Digests of statements that ran at least once without using an index.
+  pub fn queries_without_index(&self) -> anyhow::Result<Vec<QueryDigest>> {
+    self.query_digests("SUM_NO_INDEX_USED > 0")
+  }
+
+  /// Table lock waits per table, most write-contended first.
+  pub fn table_lock_stats(&self, schema: Option<&str>) -> anyhow::Result<Vec<TableLockStats>> {
+    let mut conn = self.get_conn()?;
+    let filter = match schema {
+      Some(_) => "OBJECT_SCHEMA = ?".to_string(),
+      None => format!("OBJECT_SCHEMA not in ({SYSTEM_SCHEMAS})"),
+    };
+    // timers are in picoseconds
+    let sql = format!(
+      r"
+      select OBJECT_SCHEMA, OBJECT_NAME, COUNT_READ, COUNT_WRITE,
+        SUM_TIMER_READ div 1000, SUM_TIMER_WRITE div 1000
+      from performance_schema.table_lock_waits_summary_by_table
+      where OBJECT_TYPE = 'TABLE' and {filter}
+      order by SUM_TIMER_WRITE desc, SUM_TIMER_READ desc
+      "
+    );
+    let rows: Vec<TableLockRow> = match schema {
+      Some(schema) => conn.exec(sql, (schema,))?,
+      None => conn.query(sql)?,
+    };
+    Ok(rows.into_iter().map(TableLockStats::from_row).collect())
+  }
+
+  /// The `top_n` busiest tables by row I/O since the server started (or the
+  /// summary was truncated).
+  pub fn table_access_frequency(
+    &self,
+    schema: Option<&str>,
+    top_n: usize,
+  ) -> anyhow::Result<Vec<TableAccessFreq>> {
+    let mut conn = self.get_conn()?;
+    let filter = match schema {
+      Some(_) => "s.OBJECT_SCHEMA = ?".to_string(),
+      None => format!("s.OBJECT_SCHEMA not in ({SYSTEM_SCHEMAS})"),
+    };
+    let sql = format!(
+      r"
+      select s.OBJECT_SCHEMA, s.OBJECT_NAME, s.COUNT_STAR, s.COUNT_READ, s.COUNT_WRITE,
+        cast(t.UPDATE_TIME as char)
+      from performance_schema.table_io_waits_summary_by_table s
+      left join information_schema.tables t
+        on t.TABLE_SCHEMA = s.OBJECT_SCHEMA and t.TABLE_NAME = s.OBJECT_NAME
+      where s.OBJECT_TYPE = 'TABLE' and {filter}
+      order by s.COUNT_STAR desc
+      limit {top_n}
+      "
+    );
+    let rows: Vec<TableIoRow> = match schema {
+      Some(schema) => conn.exec(sql, (schema,))?,
+      None => conn.query(sql)?,
+    };
+    Ok(rows.into_iter().map(TableAccessFreq::from_row).collect())
+  }
+
+  /// Base tables of `schema` that performance_schema has seen no I/O for,
+  /// likely left behind by the application.
+  ///
+  /// Tables never opened since startup are always included; with `zero_ops`
+  /// tables that were opened but have a zero I/O count are included too.
+  pub fn cold_tables(&self, schema: &str, zero_ops: bool) -> anyhow::Result<Vec<String>> {
+    let mut conn = self.get_conn()?;
+    let sql = r"
+    select t.TABLE_NAME
+    from information_schema.tables t
+    left join performance_schema.table_io_waits_summary_by_table s
+      on s.OBJECT_TYPE = 'TABLE' and s.OBJECT_SCHEMA = t.TABLE_SCHEMA
+        and s.OBJECT_NAME = t.TABLE_NAME
+    where t.TABLE_SCHEMA = ? and t.TABLE_TYPE = 'BASE TABLE'
+      and (s.OBJECT_NAME is null or (? and s.COUNT_STAR = 0))
+    order by t.TABLE_NAME
+    ";
+    Ok(conn.exec(sql, (schema, zero_ops))?)
+  }
+
+  /// Server-wide socket traffic from
+  /// `performance_schema.socket_summary_by_event_name`; errors are the sum of
+  /// the `Connection_errors_%` status counters.
+  pub fn network_stats(&self) -> anyhow::Result<NetworkStats> {
+    let mut conn = self.get_conn()?;
+    let rows: Vec<SocketSummaryRow> = conn.query(
+      r"
+      select EVENT_NAME, COUNT_STAR, SUM_TIMER_WAIT,
+        SUM_NUMBER_OF_BYTES_READ, SUM_NUMBER_OF_BYTES_WRITE
+      from performance_schema.socket_summary_by_event_name
+      ",
+    )?;
+    let active: Option<u32> = conn.query_first(
+      r"
+      select count(*) from performance_schema.socket_instances
+      where EVENT_NAME like '%client_connection'
+      ",
+    )?;
+    let errors = self
+      .global_status("Connection_errors_%")?
+      .values()
+      .filter_map(|v| v.trim().parse::<u64>().ok())
+      .sum();
+    Ok(NetworkStats::from_summary(
+      &rows,
+      active.unwrap_or_default(),
+      errors,
+    ))
+  }
+
+  /// Socket traffic of the currently connected sessions grouped by account.
+  ///
+  /// performance_schema has no per-account socket summary, so this joins the
+  /// live client sockets to their threads; disconnected sessions are not
+  /// counted.
+  pub fn per_user_network_stats(&self) -> anyhow::Result<Vec<UserNetworkStats>> {
+    let mut conn = self.get_conn()?;
+    let sql = r"
+    select
+      t.PROCESSLIST_USER,
+      t.PROCESSLIST_HOST,
+      count(*),
+      sum(s.SUM_NUMBER_OF_BYTES_READ),
+      sum(s.SUM_NUMBER_OF_BYTES_WRITE),
+      sum(s.COUNT_STAR),
+      sum(s.SUM_TIMER_WAIT)
+    from performance_schema.socket_instances i
+    join performance_schema.socket_summary_by_instance s
+      on s.OBJECT_INSTANCE_BEGIN = i.OBJECT_INSTANCE_BEGIN
+    join performance_schema.threads t on t.THREAD_ID = i.THREAD_ID
+    where i.EVENT_NAME like '%client_connection' and t.PROCESSLIST_USER is not null
+    group by t.PROCESSLIST_USER, t.PROCESSLIST_HOST
+    order by sum(s.SUM_NUMBER_OF_BYTES_READ) + sum(s.SUM_NUMBER_OF_BYTES_WRITE) desc
+    ";
+    type Row = (String, Option<String>, u32, u64, u64, u64, u64);
+    let rows: Vec<Row> = conn.query(sql)?;
+    Ok(
+      rows
+        .into_iter()
+        .map(
+          |(user, host, connections, received, sent, count, timer_wait)| UserNetworkStats {
+            user,
+            host: host.unwrap_or_default(),
+            connections,
+            bytes_received: received,
+            bytes_sent: sent,
+            avg_latency_ns: avg_latency_ns(count, timer_wait),
+          },
+        )
+        .collect(),
+    )
+  }
+
+  /// Open transactions older than `min_age_seconds`, oldest first.
+  pub fn active_transactions(
+    &self,
+    min_age_seconds: f64,
+  ) -> anyhow::Result<Vec<ActiveTransaction>> {
+    let mut conn = self.get_conn()?;
+    let sql = r"
+    select
+      t.PROCESSLIST_ID,
+      t.PROCESSLIST_USER,
+      t.PROCESSLIST_HOST,
+      tx.TIMER_WAIT,
+      tx.ISOLATION_LEVEL,
+      tx.AUTOCOMMIT,
+      tx.GTID,
+      t.PROCESSLIST_INFO
+    from performance_schema.events_transactions_current tx
+    join performance_schema.threads t on t.THREAD_ID = tx.THREAD_ID
+    where tx.STATE = 'ACTIVE' and t.PROCESSLIST_ID is not null
+    order by tx.TIMER_WAIT desc
+    ";
+    let rows: Vec<TransactionRow> = conn.query(sql)?;
+    let txs = rows.into_iter().map(ActiveTransaction::from_row).collect();
+    Ok(older_than(txs, min_age_seconds))
+  }
+
+  /// Kill connections holding a transaction open longer than
+  /// `max_age_seconds`, returning how many were killed.
+  pub fn kill_long_transactions(&self, max_age_seconds: f64) -> anyhow::Result<u32> {
+    let txs = self.active_transactions(max_age_seconds)?;
+    let mut conn = self.get_conn()?;
+    let own_id = conn.connection_id() as u64;
+    let mut killed = 0;
+    for tx in txs.iter().filter(|tx| tx.thread_id != own_id) {
+      match conn.query_drop(format!("KILL {}", tx.thread_id)) {
+        Ok(_) => killed += 1,
+        Err(err) => log::warn!("failed to kill thread {}: {err}", tx.thread_id),
+      }
+    }
+    Ok(killed)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_active_transactions_filter() {
+    let rows: Vec<TransactionRow> = vec![
+      (
+        12,
+        Some("app".into()),
+        Some("10.0.0.5".into()),
+        Some(90_000_000_000_000),
+        Some("REPEATABLE READ".into()),
+        Some("NO".into()),
+        Some("AUTOMATIC".into()),
+        Some("update orders set state = 1".into()),
+      ),
+      (
+        13,
+        Some("app".into()),
+        Some("10.0.0.6".into()),
+        Some(2_000_000_000_000),
+        Some("READ COMMITTED".into()),
+        Some("YES".into()),
+        None,
+        None,
+      ),
+    ];
+    let txs: Vec<_> = rows.into_iter().map(ActiveTransaction::from_row).collect();
+    assert_eq!(txs[0].age_seconds, 90.0);
+    assert!(!txs[0].autocommit);
+    assert_eq!(txs[0].gtid, None);
+
+    let old = older_than(txs, 60.0);
+    assert_eq!(old.len(), 1);
+    assert_eq!(old[0].thread_id, 12);
+  }
+
+  #[test]
+  fn test_network_stats_sums_socket_types() {
+    let row = |name: &str, count, wait, read, write| -> SocketSummaryRow {
+      (
+        format!("wait/io/socket/sql/{name}"),
+        count,
+        wait,
+        read,
+        write,
+      )
+    };
+    let rows = vec![
+      row("client_connection", 90, 180_000_000, 4_000, 16_000),
+      row("server_tcpip_socket", 8, 0, 0, 0),
+      row("server_unix_socket", 2, 20_000_000, 0, 0),
+      row("admin_socket", 0, 0, 100, 50),
+    ];
+    let stats = NetworkStats::from_summary(&rows, 3, 1);
+    assert_eq!(stats.bytes_received_total, 4_100);
+    assert_eq!(stats.bytes_sent_total, 16_050);
+    assert_eq!(stats.active_connections, 3);
+    assert_eq!(stats.avg_latency_ns, 2_000.0);
+    assert_eq!(NetworkStats::from_summary(&[], 0, 0).avg_latency_ns, 0.0);
+  }
+
+  #[test]
+  fn test_table_access_pct() {
+    let row: TableIoRow = ("shop".into(), "orders".into(), 400, 300, 100, None);
+    let freq = TableAccessFreq::from_row(row);
+    assert_eq!(freq.read_pct, 75.0);
+    assert_eq!(freq.write_pct, 25.0);
+    assert!((freq.read_pct + freq.write_pct - 100.0).abs() < 1e-9);
+
+    let idle = TableAccessFreq::from_row(("shop".into(), "t".into(), 0, 0, 0, None));
+    assert_eq!(idle.read_pct + idle.write_pct, 0.0);
+  }
+
+  #[test]
+  fn test_query_digest_ms() {
+    let row: DigestRow = (
+      Some("3f1a".into()),
+      Some("SELECT * FROM `orders` WHERE `state` = ?".into()),
+      10,
+      2_500_000_000,
+      40_000_000_000,
+      5_000,
+      10,
+      10,
+      0,
+    );
+    let digest = QueryDigest::from_row(row);
+    assert_eq!(digest.avg_timer_wait_ms, 2.5);
+    assert_eq!(digest.max_timer_wait_ms, 40.0);
+    assert_eq!(digest.sum_no_index_used, 10);
+
+    let other = QueryDigest::from_row((None, None, 1, 0, 0, 0, 0, 0, 0));
+    assert_eq!(other.digest, "");
+  }
+
+  #[test]
+  fn test_table_lock_stats_ms() {
+    let row: TableLockRow = ("shop".into(), "orders".into(), 4, 2, 10_000_000, 5_000_000);
+    let stats = TableLockStats::from_row(row);
+    assert_eq!(stats.avg_read_wait_ms, 2.5);
+    assert_eq!(stats.avg_write_wait_ms, 2.5);
+    let none = TableLockStats::from_row(("shop".into(), "t".into(), 0, 0, 0, 0));
+    assert_eq!(none.avg_write_wait_ms, 0.0);
+  }
+}
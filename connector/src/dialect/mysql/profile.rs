@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+
+use mysql::Row;
+use mysql::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{MySqlConnection, quote_ident, quote_table};
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistogramBucket {
+  pub lower_bound: f64,
+  pub upper_bound: f64,
+  pub count: u64,
+}
+
+pub(crate) fn is_numeric_type(data_type: &str) -> bool {
+  matches!(
+    data_type.to_ascii_lowercase().as_str(),
+    "tinyint"
+      | "smallint"
+      | "mediumint"
+      | "int"
+      | "integer"
+      | "bigint"
+      | "decimal"
+      | "numeric"
+      | "float"
+      | "double"
+      | "real"
+      | "year"
+  )
+}
+
+/// Width of each of `buckets` equal ranges covering `min..=max`; a zero width
+/// means every value falls into one bucket.
+fn bucket_width(min: f64, max: f64, buckets: u32) -> f64 {
+  (max - min) / buckets.max(1) as f64
+}
+
+/// Expand the `(bucket index, count)` pairs from the server into `buckets`
+/// contiguous ranges, including empty ones.
+fn build_buckets(min: f64, max: f64, buckets: u32, counts: &[(i64, u64)]) -> Vec<HistogramBucket> {
+  let width = bucket_width(min, max, buckets);
+  if width == 0.0 {
+    let count = counts.iter().map(|(_, c)| c).sum();
+    return vec![HistogramBucket {
+      lower_bound: min,
+      upper_bound: max,
+      count,
+    }];
+  }
+  let mut result: Vec<HistogramBucket> = (0..buckets)
+    .map(|i| HistogramBucket {
+      lower_bound: min + width * i as f64,
+      upper_bound: min + width * (i + 1) as f64,
+      count: 0,
+    })
+    .collect();
+  for (idx, count) in counts {
+    // the maximum value lands on the upper edge of the last bucket
+    let idx = (*idx).clamp(0, buckets as i64 - 1) as usize;
+    result[idx].count += count;
+  }
+  result
+}
+
+/// Most columns counted by one `approx_distinct_all` query.
+const MAX_DISTINCT_COLUMNS: usize = 64;
+
+/// Tables estimated above this many rows are counted over their first rows
+/// only.
+const DISTINCT_SAMPLE_ROWS: u64 = 1_000_000;
+
+/// `(name, DATA_TYPE, COLUMN_TYPE, NUMERIC_PRECISION, NUMERIC_SCALE)`
+type ColumnWidthRow = (String, String, String, Option<usize>, Option<usize>);
+
+/// Characters needed to print any value of a numeric column.
+fn numeric_width(precision: usize, scale: usize, unsigned: bool) -> usize {
+  precision + usize::from(scale > 0) + usize::from(!unsigned)
+}
+
+/// Width of a numeric column known from its type alone, or `None` for
+/// columns whose values must be measured. YEAR has no NUMERIC_PRECISION but
+/// always prints four digits.
+fn fixed_width(
+  data_type: &str,
+  column_type: &str,
+  precision: Option<usize>,
+  scale: Option<usize>,
+) -> Option<usize> {
+  if data_type.eq_ignore_ascii_case("year") {
+    return Some(4);
+  }
+  is_numeric_type(data_type).then(|| {
+    let unsigned = column_type.to_ascii_lowercase().contains("unsigned");
+    numeric_width(
+      precision.unwrap_or_default(),
+      scale.unwrap_or_default(),
+      unsigned,
+    )
+  })
+}
+
+/// Aggregate measuring the longest value of a non-numeric column.
+fn width_expr(column: &str, data_type: &str) -> String {
+  let column = quote_ident(column);
+  match data_type.to_ascii_lowercase().as_str() {
+    "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" | "bit" => {
+      format!("max(length({column}))")
+    }
+    _ => format!("max(char_length({column}))"),
+  }
+}
+
+/// One `COUNT(DISTINCT ...)` per column, over the first `sample` rows if
+/// given.
+fn distinct_count_sql(target: &str, columns: &[String], sample: Option<u64>) -> String {
+  let counts: Vec<String> = columns
+    .iter()
+    .map(|c| format!("count(distinct {})", quote_ident(c)))
+    .collect();
+  let source = match sample {
+    Some(rows) => {
+      let names: Vec<String> = columns.iter().map(|c| quote_ident(c)).collect();
+      format!(
+        "(select {} from {target} limit {rows}) as s",
+        names.join(", ")
+      )
+    }
+    None => target.to_string(),
+  };
+  format!("select {} from {source}", counts.join(", "))
+}
+
+impl MySqlConnection {
+  /// Distinct value counts of every column in one query, keyed by column
+  /// name. Only the first 64 columns are counted, and tables over a million
+  /// rows are sampled, so counts can be low.
+  pub async fn approx_distinct_all(
+    &self,
+    schema: Option<&str>,
+    table: &str,
+  ) -> anyhow::Result<HashMap<String, u64>> {
+    let mut conn = self.get_conn()?;
+    let mut columns: Vec<String> = conn.exec(
+      r"
+      select COLUMN_NAME from information_schema.columns
+      where TABLE_SCHEMA = coalesce(?, database()) and TABLE_NAME = ?
+      order by ORDINAL_POSITION
+      ",
+      (schema, table),
+    )?;
+    if columns.is_empty() {
+      anyhow::bail!("table {table} not found");
+    }
+    if columns.len() > MAX_DISTINCT_COLUMNS {
+      log::warn!(
+        "{table} has {} columns, counting the first {MAX_DISTINCT_COLUMNS}",
+        columns.len()
+      );
+      columns.truncate(MAX_DISTINCT_COLUMNS);
+    }
+    let estimate: Option<Option<u64>> = conn.exec_first(
+      r"
+      select TABLE_ROWS from information_schema.tables
+      where TABLE_SCHEMA = coalesce(?, database()) and TABLE_NAME = ?
+      ",
+      (schema, table),
+    )?;
+    let sample = estimate
+      .flatten()
+      .filter(|rows| *rows > DISTINCT_SAMPLE_ROWS)
+      .map(|_| DISTINCT_SAMPLE_ROWS);
+
+    let sql = distinct_count_sql(&quote_table(schema, table), &columns, sample);
+    let Some(row) = conn.query_first::<Row, _>(sql)? else {
+      return Ok(HashMap::new());
+    };
+    Ok(
+      columns
+        .into_iter()
+        .enumerate()
+        .map(|(i, column)| (column, row.get::<u64, _>(i).unwrap_or_default()))
+        .collect(),
+    )
+  }
+
+  /// Display width per column for sizing grid columns: the longest value in
+  /// characters for most types, bytes for binary ones, and the declared
+  /// precision plus sign and decimal point for numerics.
+  pub async fn max_column_widths(
+    &self,
+    schema: Option<&str>,
+    table: &str,
+  ) -> anyhow::Result<HashMap<String, usize>> {
+    let mut conn = self.get_conn()?;
+    let columns: Vec<ColumnWidthRow> = conn.exec(
+      r"
+      select COLUMN_NAME, DATA_TYPE, COLUMN_TYPE, NUMERIC_PRECISION, NUMERIC_SCALE
+      from information_schema.columns
+      where TABLE_SCHEMA = coalesce(?, database()) and TABLE_NAME = ?
+      order by ORDINAL_POSITION
+      ",
+      (schema, table),
+    )?;
+    if columns.is_empty() {
+      anyhow::bail!("table {table} not found");
+    }
+    let mut widths = HashMap::with_capacity(columns.len());
+    let mut measured = vec![];
+    for (name, data_type, column_type, precision, scale) in columns {
+      match fixed_width(&data_type, &column_type, precision, scale) {
+        Some(width) => {
+          widths.insert(name, width);
+        }
+        None => measured.push((width_expr(&name, &data_type), name)),
+      }
+    }
+    if measured.is_empty() {
+      return Ok(widths);
+    }
+    let exprs: Vec<&str> = measured.iter().map(|(expr, _)| expr.as_str()).collect();
+    let sql = format!(
+      "select {} from {}",
+      exprs.join(", "),
+      quote_table(schema, table)
+    );
+    let row: Option<Row> = conn.query_first(sql)?;
+    for (i, (_, name)) in measured.into_iter().enumerate() {
+      let width = row
+        .as_ref()
+        .and_then(|row| row.get::<Option<usize>, _>(i))
+        .flatten()
+        .unwrap_or_default();
+      widths.insert(name, width);
+    }
+    Ok(widths)
+  }
+
+  /// Equal-width histogram of a column, computed client-side from MIN/MAX and
+  /// a grouped count.
+  ///
+  /// String columns are bucketed by the code point of their first character.
+  pub fn value_distribution(
+    &self,
+    schema: &str,
+    table: &str,
+    column: &str,
+    buckets: u32,
+  ) -> anyhow::Result<Vec<HistogramBucket>> {
+    let mut conn = self.get_conn()?;
+    let data_type: Option<String> = conn.exec_first(
+      r"
+      select DATA_TYPE from information_schema.columns
+      where TABLE_SCHEMA = ? and TABLE_NAME = ? and COLUMN_NAME = ?
+      ",
+      (schema, table, column),
+    )?;
+    let Some(data_type) = data_type else {
+      anyhow::bail!("column {schema}.{table}.{column} not found");
+    };
+
+    let col = quote_ident(column);
+    let expr = if is_numeric_type(&data_type) {
+      format!("cast({col} as double)")
+    } else {
+      format!("cast(ord(left({col}, 1)) as double)")
+    };
+    let target = quote_table(Some(schema), table);
+
+    let sql = format!("select min({expr}), max({expr}) from {target} where {col} is not null");
+    let bounds: Option<(Option<f64>, Option<f64>)> = conn.query_first(sql)?;
+    let Some((Some(min), Some(max))) = bounds else {
+      return Ok(vec![]);
+    };
+
+    let width = bucket_width(min, max, buckets);
+    let bucket = if width == 0.0 {
+      "0".to_string()
+    } else {
+      format!("floor(({expr} - {min}) / {width})")
+    };
+    let sql = format!(
+      "select cast({bucket} as signed) as bucket, count(*) from {target} \
+       where {col} is not null group by bucket"
+    );
+    let counts: Vec<(i64, u64)> = conn.query(sql)?;
+    Ok(build_buckets(min, max, buckets, &counts))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dialect::mysql::duckling_test_table;
+
+  #[test]
+  fn test_uniform_buckets() {
+    // values 0..=99, one row each
+    let counts: Vec<(i64, u64)> = (0..100)
+      .map(|v| ((v as f64 / bucket_width(0.0, 99.0, 10)).floor() as i64, 1))
+      .collect();
+    let buckets = build_buckets(0.0, 99.0, 10, &counts);
+    assert_eq!(buckets.len(), 10);
+    for b in &buckets {
+      assert!((b.upper_bound - b.lower_bound - 9.9).abs() < 1e-9);
+      assert!((9..=11).contains(&b.count));
+    }
+    assert_eq!(buckets.iter().map(|b| b.count).sum::<u64>(), 100);
+    assert_eq!(buckets[0].lower_bound, 0.0);
+    assert!((buckets[9].upper_bound - 99.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_single_value_bucket() {
+    let buckets = build_buckets(5.0, 5.0, 10, &[(0, 7)]);
+    assert_eq!(
+      buckets,
+      [HistogramBucket {
+        lower_bound: 5.0,
+        upper_bound: 5.0,
+        count: 7
+      }]
+    );
+  }
+
+  #[test]
+  fn test_distinct_count_sql() {
+    let columns = ["id".to_string(), "name".to_string()];
+    assert_eq!(
+      distinct_count_sql("`t`", &columns, None),
+      "select count(distinct `id`), count(distinct `name`) from `t`"
+    );
+    assert_eq!(
+      distinct_count_sql("`t`", &columns, Some(10)),
+      "select count(distinct `id`), count(distinct `name`) \
+       from (select `id`, `name` from `t` limit 10) as s"
+    );
+  }
+
+  #[test]
+  fn test_column_widths() {
+    assert_eq!(numeric_width(10, 0, false), 11);
+    assert_eq!(numeric_width(10, 0, true), 10);
+    assert_eq!(numeric_width(8, 2, false), 10);
+    assert_eq!(
+      fixed_width("int", "int unsigned", Some(10), Some(0)),
+      Some(10)
+    );
+    assert_eq!(
+      fixed_width("decimal", "decimal(8,2)", Some(8), Some(2)),
+      Some(10)
+    );
+    assert_eq!(fixed_width("year", "year", None, None), Some(4));
+    assert_eq!(fixed_width("varchar", "varchar(100)", None, None), None);
+    assert_eq!(width_expr("note", "varchar"), "max(char_length(`note`))");
+    assert_eq!(width_expr("raw", "BLOB"), "max(length(`raw`))");
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_max_column_widths() {
+    let conn = crate::dialect::mysql::local_connection();
+    let mut c = duckling_test_table(
+      &conn,
+      "column_widths",
+      "(id int unsigned, price decimal(8, 2), note varchar(100), y year)",
+    );
+    c.query_drop(
+      "insert into duckling_test.column_widths values \
+       (1, 9.5, 'short', 2024), (2, 12.25, 'a longer note', null), (3, null, null, null)",
+    )
+    .unwrap();
+    let widths = conn
+      .max_column_widths(Some("duckling_test"), "column_widths")
+      .await
+      .unwrap();
+    assert_eq!(widths["note"], 13);
+    assert_eq!(widths["price"], 10);
+    assert_eq!(widths["id"], 10);
+    assert_eq!(widths["y"], 4);
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_approx_distinct_all() {
+    let conn = crate::dialect::mysql::local_connection();
+    let mut c = duckling_test_table(
+      &conn,
+      "distinct_vals",
+      "(id int, color varchar(8), flag int)",
+    );
+    c.query_drop(
+      "insert into duckling_test.distinct_vals values \
+       (1, 'red', null), (2, 'red', 1), (3, 'blue', 1), (4, null, 1)",
+    )
+    .unwrap();
+    let counts = conn
+      .approx_distinct_all(Some("duckling_test"), "distinct_vals")
+      .await
+      .unwrap();
+    assert_eq!(counts["id"], 4);
+    assert_eq!(counts["color"], 2);
+    assert_eq!(counts["flag"], 1);
+  }
+
+  #[test]
+  #[ignore = "requires a running MySQL server"]
+  fn test_value_distribution() {
+    let conn = crate::dialect::mysql::local_connection();
+    let buckets = conn
+      .value_distribution("information_schema", "columns", "ORDINAL_POSITION", 10)
+      .unwrap();
+    assert_eq!(buckets.len(), 10);
+    let widths: Vec<f64> = buckets
+      .iter()
+      .map(|b| b.upper_bound - b.lower_bound)
+      .collect();
+    assert!(widths.iter().all(|w| (w - widths[0]).abs() < 1e-9));
+  }
+}
@@ -0,0 +1,396 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use arrow::array::{Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use mysql::prelude::*;
+use mysql::{Error, PooledConn, Row};
+use serde::{Deserialize, Serialize};
+
+use super::MySqlConnection;
+use super::status::{VarMap, var_bool, var_f64, var_u64};
+use crate::utils::RawArrowData;
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SemiSyncStatus {
+  pub master_enabled: bool,
+  pub slave_enabled: bool,
+  pub master_yes_tx: u64,
+  pub master_no_tx: u64,
+  pub slave_received_semisyncs: u64,
+  pub master_net_avg_wait_time_us: f64,
+}
+
+impl SemiSyncStatus {
+  /// Parse the `Rpl_semi_sync%` status variables, accepting both the legacy
+  /// `master`/`slave` and the 8.0.26+ `source`/`replica` names.
+  pub fn from_status(vars: &VarMap) -> anyhow::Result<Self> {
+    if !vars.keys().any(|k| k.starts_with("Rpl_semi_sync")) {
+      anyhow::bail!("semi-sync plugin not loaded");
+    }
+    Ok(Self {
+      master_enabled: var_bool(
+        vars,
+        &["Rpl_semi_sync_master_status", "Rpl_semi_sync_source_status"],
+      ),
+      slave_enabled: var_bool(
+        vars,
+        &["Rpl_semi_sync_slave_status", "Rpl_semi_sync_replica_status"],
+      ),
+      master_yes_tx: var_u64(
+        vars,
+        &["Rpl_semi_sync_master_yes_tx", "Rpl_semi_sync_source_yes_tx"],
+      ),
+      master_no_tx: var_u64(
+        vars,
+        &["Rpl_semi_sync_master_no_tx", "Rpl_semi_sync_source_no_tx"],
+      ),
+      slave_received_semisyncs: var_u64(
+        vars,
+        &[
+          "Rpl_semi_sync_slave_received_semisyncs",
+          "Rpl_semi_sync_replica_received_semisyncs",
+        ],
+      ),
+      master_net_avg_wait_time_us: var_f64(
+        vars,
+        &[
+          "Rpl_semi_sync_master_net_avg_wait_time",
+          "Rpl_semi_sync_source_net_avg_wait_time",
+        ],
+      ),
+    })
+  }
+}
+
+/// GTID ranges `start..=end` per source, keyed by server UUID (or
+/// `uuid:tag` for tagged GTIDs).
+pub type GtidSet = BTreeMap<String, Vec<(u64, u64)>>;
+
+/// A range of transactions of one source missing from an executed GTID set.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GtidGap {
+  pub server_uuid: String,
+  pub expected_start: u64,
+  pub expected_end: u64,
+}
+
+fn parse_interval(interval: &str) -> anyhow::Result<(u64, u64)> {
+  let (start, end) = interval.split_once('-').unwrap_or((interval, interval));
+  let range = (start.trim().parse::<u64>()?, end.trim().parse::<u64>()?);
+  if range.0 > range.1 {
+    anyhow::bail!("invalid GTID interval {interval}");
+  }
+  Ok(range)
+}
+
+/// Parse a GTID set such as `@@global.gtid_executed`:
+/// `uuid:1-3:5-10,uuid2:tag:1-7`.
+///
+/// An element without a UUID continues the previous source, so
+/// `uuid:1-3,5-10` is accepted as well.
+pub fn parse_gtid_set(set: &str) -> anyhow::Result<GtidSet> {
+  let mut gtids = GtidSet::new();
+  let mut source: Option<String> = None;
+  for element in set.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+    if element.contains(':') {
+      let mut parts = element.split(':').map(str::trim).peekable();
+      let mut uuid = parts.next().unwrap_or_default().to_ascii_lowercase();
+      // 8.3+ tagged GTIDs: uuid:tag:1-5
+      while let Some(tag) = parts.next_if(|p| !p.starts_with(|c: char| c.is_ascii_digit())) {
+        uuid = format!("{uuid}:{tag}");
+      }
+      let intervals = gtids.entry(uuid.clone()).or_default();
+      for interval in parts {
+        intervals.push(parse_interval(interval)?);
+      }
+      source = Some(uuid);
+    } else {
+      let Some(uuid) = &source else {
+        anyhow::bail!("GTID interval {element} without a server UUID");
+      };
+      gtids
+        .entry(uuid.clone())
+        .or_default()
+        .push(parse_interval(element)?);
+    }
+  }
+  Ok(gtids)
+}
+
+/// Holes between the intervals of each source.
+fn gtid_gaps(set: &GtidSet) -> Vec<GtidGap> {
+  let mut gaps = vec![];
+  for (uuid, intervals) in set {
+    let mut intervals = intervals.clone();
+    intervals.sort_unstable();
+    let mut executed_to: Option<u64> = None;
+    for (start, end) in intervals {
+      if let Some(prev_end) = executed_to
+        && start > prev_end + 1
+      {
+        gaps.push(GtidGap {
+          server_uuid: uuid.clone(),
+          expected_start: prev_end + 1,
+          expected_end: start - 1,
+        });
+      }
+      executed_to = Some(executed_to.map_or(end, |prev| prev.max(end)));
+    }
+  }
+  gaps
+}
+
+pub(crate) const ER_PARSE_ERROR: u16 = 1064;
+const ER_SPECIFIC_ACCESS_DENIED_ERROR: u16 = 1227;
+
+/// One row per replication channel, empty when the server is not a replica.
+pub(crate) fn replica_status_rows(conn: &mut PooledConn) -> anyhow::Result<Vec<Row>> {
+  // SHOW SLAVE STATUS was renamed in 8.0.22
+  for sql in ["SHOW REPLICA STATUS", "SHOW SLAVE STATUS"] {
+    match conn.query::<Row, _>(sql) {
+      Ok(rows) => return Ok(rows),
+      Err(Error::MySqlError(e)) if e.code == ER_PARSE_ERROR => continue,
+      Err(e) => return Err(e.into()),
+    }
+  }
+  anyhow::bail!("server supports neither SHOW REPLICA STATUS nor SHOW SLAVE STATUS")
+}
+
+/// First present column of a replica status row, trying the 8.0.22+ name
+/// before the legacy one.
+pub(crate) fn replica_column<T: FromValue>(row: &Row, names: &[&str]) -> Option<T> {
+  names
+    .iter()
+    .find_map(|name| row.get_opt::<Option<T>, _>(*name).and_then(Result::ok))
+    .flatten()
+}
+
+/// Key fields of one replication channel.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct ReplicaChannel {
+  channel: String,
+  source_host: Option<String>,
+  seconds_behind_source: Option<i64>,
+  io_running: Option<String>,
+  sql_running: Option<String>,
+  last_io_error: Option<String>,
+  last_sql_error: Option<String>,
+}
+
+impl ReplicaChannel {
+  fn from_row(row: &Row) -> Self {
+    Self {
+      channel: replica_column(row, &["Channel_Name"]).unwrap_or_default(),
+      source_host: replica_column(row, &["Source_Host", "Master_Host"]),
+      seconds_behind_source: replica_column(
+        row,
+        &["Seconds_Behind_Source", "Seconds_Behind_Master"],
+      ),
+      io_running: replica_column(row, &["Replica_IO_Running", "Slave_IO_Running"]),
+      sql_running: replica_column(row, &["Replica_SQL_Running", "Slave_SQL_Running"]),
+      last_io_error: replica_column(row, &["Last_IO_Error"]),
+      last_sql_error: replica_column(row, &["Last_SQL_Error"]),
+    }
+  }
+}
+
+fn replica_channels_batch(channels: Vec<ReplicaChannel>) -> anyhow::Result<RecordBatch> {
+  let schema = Schema::new(vec![
+    Field::new("channel", DataType::Utf8, false),
+    Field::new("source_host", DataType::Utf8, true),
+    Field::new("seconds_behind_source", DataType::Int64, true),
+    Field::new("io_running", DataType::Utf8, true),
+    Field::new("sql_running", DataType::Utf8, true),
+    Field::new("last_io_error", DataType::Utf8, true),
+    Field::new("last_sql_error", DataType::Utf8, true),
+  ]);
+  let mut channel = vec![];
+  let mut source_host = vec![];
+  let mut behind = vec![];
+  let mut io_running = vec![];
+  let mut sql_running = vec![];
+  let mut last_io_error = vec![];
+  let mut last_sql_error = vec![];
+  for c in channels {
+    channel.push(c.channel);
+    source_host.push(c.source_host);
+    behind.push(c.seconds_behind_source);
+    io_running.push(c.io_running);
+    sql_running.push(c.sql_running);
+    last_io_error.push(c.last_io_error.filter(|e| !e.is_empty()));
+    last_sql_error.push(c.last_sql_error.filter(|e| !e.is_empty()));
+  }
+  Ok(RecordBatch::try_new(
+    Arc::new(schema),
+    vec![
+      Arc::new(StringArray::from(channel)),
+      Arc::new(StringArray::from(source_host)),
+      Arc::new(Int64Array::from(behind)),
+      Arc::new(StringArray::from(io_running)),
+      Arc::new(StringArray::from(sql_running)),
+      Arc::new(StringArray::from(last_io_error)),
+      Arc::new(StringArray::from(last_sql_error)),
+    ],
+  )?)
+}
+
+impl MySqlConnection {
+  /// Current `(binlog file, position)` of the source, for starting CDC.
+  pub async fn binlog_position(&self) -> anyhow::Result<(String, u64)> {
+    let mut conn = self.get_conn()?;
+    let mut status = None;
+    // SHOW MASTER STATUS was replaced by SHOW BINARY LOG STATUS in 8.4
+    for sql in ["SHOW MASTER STATUS", "SHOW BINARY LOG STATUS"] {
+      match conn.query_first::<Row, _>(sql) {
+        Ok(row) => {
+          status = Some(row);
+          break;
+        }
+        Err(Error::MySqlError(e)) if e.code == ER_PARSE_ERROR => continue,
+        Err(Error::MySqlError(e)) if e.code == ER_SPECIFIC_ACCESS_DENIED_ERROR => {
+          anyhow::bail!(
+            "reading the binlog position needs the REPLICATION CLIENT privilege: {}",
+            e.message
+          );
+        }
+        Err(e) => return Err(e.into()),
+      }
+    }
+    let Some(row) = status.flatten() else {
+      anyhow::bail!("binary logging is disabled");
+    };
+    let file = row.get_opt::<String, _>(0).and_then(Result::ok);
+    let position = row.get_opt::<u64, _>(1).and_then(Result::ok);
+    match (file, position) {
+      (Some(file), Some(position)) => Ok((file, position)),
+      _ => anyhow::bail!("unexpected binary log status row"),
+    }
+  }
+
+  /// Lag, thread state and last errors per replication channel; no rows
+  /// when the server is not a replica.
+  pub async fn replication_status(&self) -> anyhow::Result<RawArrowData> {
+    let mut conn = self.get_conn()?;
+    let channels = replica_status_rows(&mut conn)?
+      .iter()
+      .map(ReplicaChannel::from_row)
+      .collect();
+    Ok(RawArrowData::from_batch(replica_channels_batch(channels)?))
+  }
+
+  /// Transactions missing from `@@global.gtid_executed`, which a replica
+  /// catching up from this server would fail on.
+  pub fn find_gtid_gaps(&self) -> anyhow::Result<Vec<GtidGap>> {
+    let mut conn = self.get_conn()?;
+    let executed: Option<String> = conn.query_first("select @@global.gtid_executed")?;
+    Ok(gtid_gaps(&parse_gtid_set(&executed.unwrap_or_default())?))
+  }
+
+  pub fn semi_sync_status(&self) -> anyhow::Result<SemiSyncStatus> {
+    SemiSyncStatus::from_status(&self.global_status("Rpl_semi_sync%")?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dialect::mysql::status::var_map as vars;
+
+  #[test]
+  fn test_semi_sync_status() {
+    let status = SemiSyncStatus::from_status(&vars(&[
+      ("Rpl_semi_sync_master_status", "ON"),
+      ("Rpl_semi_sync_slave_status", "OFF"),
+      ("Rpl_semi_sync_master_yes_tx", "120"),
+      ("Rpl_semi_sync_master_no_tx", "3"),
+      ("Rpl_semi_sync_master_net_avg_wait_time", "412"),
+    ]))
+    .unwrap();
+    assert!(status.master_enabled);
+    assert!(!status.slave_enabled);
+    assert_eq!(status.master_yes_tx, 120);
+    assert_eq!(status.master_no_tx, 3);
+    assert_eq!(status.slave_received_semisyncs, 0);
+    assert_eq!(status.master_net_avg_wait_time_us, 412.0);
+
+    let err = SemiSyncStatus::from_status(&vars(&[])).unwrap_err();
+    assert_eq!(err.to_string(), "semi-sync plugin not loaded");
+  }
+
+  #[test]
+  fn test_parse_gtid_set() {
+    let set = parse_gtid_set(
+      "3E11FA47-71CA-11E1-9E33-C80AA9429562:1-5:11,\n\
+       3e11fa47-71ca-11e1-9e33-c80aa9429562:domain:1-2,\n\
+       a3f0c8b2-0000-11e1-9e33-c80aa9429562:7",
+    )
+    .unwrap();
+    assert_eq!(
+      set["3e11fa47-71ca-11e1-9e33-c80aa9429562"],
+      [(1, 5), (11, 11)]
+    );
+    assert_eq!(set["3e11fa47-71ca-11e1-9e33-c80aa9429562:domain"], [(1, 2)]);
+    assert_eq!(set["a3f0c8b2-0000-11e1-9e33-c80aa9429562"], [(7, 7)]);
+    assert!(parse_gtid_set("").unwrap().is_empty());
+    assert!(parse_gtid_set("1-3").is_err());
+    assert!(parse_gtid_set("uuid:5-1").is_err());
+  }
+
+  #[test]
+  fn test_gtid_gaps() {
+    let gaps = gtid_gaps(&parse_gtid_set("uuid:1-3,5-10").unwrap());
+    assert_eq!(
+      gaps,
+      [GtidGap {
+        server_uuid: "uuid".to_string(),
+        expected_start: 4,
+        expected_end: 4,
+      }]
+    );
+    let set = parse_gtid_set("a:1-3:2-6:10-12,b:1-100").unwrap();
+    let gaps = gtid_gaps(&set);
+    assert_eq!(gaps.len(), 1);
+    assert_eq!((gaps[0].expected_start, gaps[0].expected_end), (7, 9));
+  }
+
+  #[test]
+  fn test_replica_channels_batch() {
+    let empty = replica_channels_batch(vec![]).unwrap();
+    assert_eq!(empty.num_rows(), 0);
+    assert_eq!(empty.num_columns(), 7);
+
+    let batch = replica_channels_batch(vec![ReplicaChannel {
+      source_host: Some("db1".to_string()),
+      io_running: Some("Yes".to_string()),
+      sql_running: Some("No".to_string()),
+      last_io_error: Some(String::new()),
+      last_sql_error: Some("Duplicate entry '1'".to_string()),
+      ..Default::default()
+    }])
+    .unwrap();
+    assert_eq!(batch.num_rows(), 1);
+    assert!(batch.column(2).is_null(0));
+    assert!(batch.column(5).is_null(0));
+    assert!(batch.column(6).is_valid(0));
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_replication_status() {
+    let conn = crate::dialect::mysql::local_connection();
+    let res = conn.replication_status().await.unwrap();
+    let schema = res.batch.schema();
+    assert_eq!(schema.field(2).name(), "seconds_behind_source");
+    assert_eq!(schema.fields().len(), 7);
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a MySQL server with binary logging enabled"]
+  async fn test_binlog_position() {
+    let conn = crate::dialect::mysql::local_connection();
+    let (file, position) = conn.binlog_position().await.unwrap();
+    assert!(!file.is_empty());
+    assert!(position > 0);
+  }
+}
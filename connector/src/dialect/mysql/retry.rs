@@ -0,0 +1,152 @@
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use mysql::Error;
+
+use super::MySqlConnection;
+
+/// Client-side "MySQL server has gone away".
+const CR_SERVER_GONE_ERROR: u16 = 2006;
+/// Client-side "Lost connection to MySQL server".
+const CR_SERVER_LOST: u16 = 2013;
+
+/// How often a connection is attempted before giving up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryConfig {
+  /// Attempts in total, including the first; 1 disables retrying.
+  pub max_attempts: u32,
+  /// Delay before the first retry, doubled before each further one.
+  pub base_delay: Duration,
+}
+
+impl Default for RetryConfig {
+  fn default() -> Self {
+    Self {
+      max_attempts: 3,
+      base_delay: Duration::from_millis(100),
+    }
+  }
+}
+
+/// Whether `err` may go away by trying again: a dropped or reset
+/// connection, but not wrong credentials, unknown databases, bad SQL or an
+/// unreachable server.
+pub(crate) fn is_retryable(err: &Error) -> bool {
+  let io_kind = match err {
+    Error::IoError(io) => Some(io.kind()),
+    Error::CodecError(codec) => std::error::Error::source(codec)
+      .and_then(|source| source.downcast_ref::<std::io::Error>())
+      .map(std::io::Error::kind),
+    Error::MySqlError(e) => return matches!(e.code, CR_SERVER_GONE_ERROR | CR_SERVER_LOST),
+    _ => None,
+  };
+  matches!(
+    io_kind,
+    Some(
+      ErrorKind::ConnectionReset
+        | ErrorKind::ConnectionAborted
+        | ErrorKind::BrokenPipe
+        | ErrorKind::UnexpectedEof
+        | ErrorKind::Interrupted
+    )
+  )
+}
+
+impl MySqlConnection {
+  /// Run `op`, retrying with exponential backoff while it fails with a
+  /// retryable `mysql::Error`.
+  pub(crate) fn with_retry<T>(
+    &self,
+    mut op: impl FnMut() -> anyhow::Result<T>,
+  ) -> anyhow::Result<T> {
+    let mut delay = self.retry_config.base_delay;
+    let mut attempt = 1;
+    loop {
+      match op() {
+        Err(e)
+          if attempt < self.retry_config.max_attempts
+            && e.downcast_ref::<Error>().is_some_and(is_retryable) =>
+        {
+          log::warn!("retrying after transient error: {e}");
+          std::thread::sleep(delay);
+          delay = delay.saturating_mul(2);
+          attempt += 1;
+        }
+        result => return result,
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use mysql::MySqlError;
+
+  use super::*;
+
+  fn server_error(code: u16) -> Error {
+    Error::MySqlError(MySqlError {
+      state: "HY000".to_string(),
+      message: String::new(),
+      code,
+    })
+  }
+
+  fn connection(max_attempts: u32) -> MySqlConnection {
+    MySqlConnection {
+      retry_config: RetryConfig {
+        max_attempts,
+        base_delay: Duration::from_millis(1),
+      },
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_is_retryable() {
+    assert!(is_retryable(&server_error(CR_SERVER_LOST)));
+    assert!(is_retryable(&server_error(CR_SERVER_GONE_ERROR)));
+    assert!(is_retryable(&Error::IoError(
+      ErrorKind::ConnectionReset.into()
+    )));
+    assert!(is_retryable(&Error::CodecError(
+      std::io::Error::from(ErrorKind::UnexpectedEof).into()
+    )));
+    // access denied and a syntax error
+    assert!(!is_retryable(&server_error(1045)));
+    assert!(!is_retryable(&server_error(1064)));
+    assert!(!is_retryable(&Error::IoError(
+      ErrorKind::ConnectionRefused.into()
+    )));
+  }
+
+  #[test]
+  fn test_with_retry() {
+    let conn = connection(3);
+    let mut calls = 0;
+    let result = conn.with_retry(|| {
+      calls += 1;
+      match calls {
+        1 => Err(Error::IoError(ErrorKind::ConnectionReset.into()).into()),
+        _ => Ok(calls),
+      }
+    });
+    assert_eq!(result.unwrap(), 2);
+
+    let mut calls = 0;
+    let result: anyhow::Result<()> = conn.with_retry(|| {
+      calls += 1;
+      Err(server_error(1045).into())
+    });
+    assert!(result.is_err());
+    assert_eq!(calls, 1);
+
+    let mut calls = 0;
+    let result: anyhow::Result<()> = connection(2).with_retry(|| {
+      calls += 1;
+      Err(server_error(CR_SERVER_LOST).into())
+    });
+    assert!(result.is_err());
+    assert_eq!(calls, 2);
+  }
+}
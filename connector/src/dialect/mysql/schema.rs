@@ -0,0 +1,1082 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use arrow::array::{RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use chrono::{NaiveDateTime, TimeDelta};
+use mysql::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{MySqlConnection, quote_ident, quote_table};
+use crate::utils::RawArrowData;
+
+/// Foreign key dependencies between the tables of a schema.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FkGraph {
+  pub nodes: Vec<String>,
+  /// `(from_table, to_table)`: `from_table` holds a foreign key referencing
+  /// `to_table`.
+  pub edges: Vec<(String, String)>,
+}
+
+impl FkGraph {
+  fn neighbours(&self) -> HashMap<&str, Vec<&str>> {
+    let mut adj: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (from, to) in &self.edges {
+      let next = adj.entry(from.as_str()).or_default();
+      if !next.contains(&to.as_str()) {
+        next.push(to);
+      }
+    }
+    adj
+  }
+
+  /// Every elementary cycle reachable by DFS, each rotated to start at its
+  /// smallest table name. Self-references are reported as one-table cycles.
+  pub fn detect_cycles(&self) -> Vec<Vec<String>> {
+    fn visit<'a>(
+      node: &'a str,
+      adj: &HashMap<&'a str, Vec<&'a str>>,
+      stack: &mut Vec<&'a str>,
+      done: &mut HashSet<&'a str>,
+      cycles: &mut Vec<Vec<String>>,
+    ) {
+      stack.push(node);
+      for &next in adj.get(node).into_iter().flatten() {
+        if let Some(pos) = stack.iter().position(|n| *n == next) {
+          let mut cycle: Vec<String> = stack[pos..].iter().map(|n| n.to_string()).collect();
+          let min = (0..cycle.len()).min_by_key(|i| &cycle[*i]).unwrap_or(0);
+          cycle.rotate_left(min);
+          if !cycles.contains(&cycle) {
+            cycles.push(cycle);
+          }
+        } else if !done.contains(next) {
+          visit(next, adj, stack, done, cycles);
+        }
+      }
+      stack.pop();
+      done.insert(node);
+    }
+
+    let adj = self.neighbours();
+    let mut done = HashSet::new();
+    let mut cycles = vec![];
+    for node in &self.nodes {
+      if !done.contains(node.as_str()) {
+        visit(node, &adj, &mut vec![], &mut done, &mut cycles);
+      }
+    }
+    cycles
+  }
+
+  /// Tables ordered so that each comes before every table it references,
+  /// i.e. an order in which rows can be removed with `FOREIGN_KEY_CHECKS`
+  /// enabled. Self-references are ignored; other cycles are an error since
+  /// they can only be cleared with the checks disabled.
+  pub fn topological_truncation_order(&self) -> anyhow::Result<Vec<String>> {
+    let adj = self.neighbours();
+    let mut referenced_by: HashMap<&str, usize> =
+      self.nodes.iter().map(|n| (n.as_str(), 0)).collect();
+    for (from, targets) in &adj {
+      for to in targets.iter().filter(|to| *to != from) {
+        *referenced_by.entry(to).or_default() += 1;
+      }
+    }
+
+    let mut queue: VecDeque<&str> = self
+      .nodes
+      .iter()
+      .map(String::as_str)
+      .filter(|n| referenced_by[n] == 0)
+      .collect();
+    let mut order = vec![];
+    while let Some(node) = queue.pop_front() {
+      order.push(node.to_string());
+      for &to in adj.get(node).into_iter().flatten() {
+        if to == node {
+          continue;
+        }
+        let count = referenced_by.get_mut(to).expect("edge target is a node");
+        *count -= 1;
+        if *count == 0 {
+          queue.push_back(to);
+        }
+      }
+    }
+
+    if order.len() < referenced_by.len() {
+      let cycles: Vec<String> = self
+        .detect_cycles()
+        .into_iter()
+        .filter(|c| c.len() > 1)
+        .map(|c| c.join(" -> "))
+        .collect();
+      anyhow::bail!(
+        "circular foreign keys, disable FOREIGN_KEY_CHECKS to truncate: {}",
+        cycles.join("; ")
+      );
+    }
+    Ok(order)
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharsetMismatch {
+  pub table_name: String,
+  /// `None` for table-level problems.
+  pub column_name: Option<String>,
+  pub expected_charset: String,
+  pub actual_charset: String,
+  /// `table_charset`, `column_charset`, `non_utf8mb4` or `binary_text`.
+  pub problem_type: String,
+}
+
+const TEXT_TYPES: [&str; 8] = [
+  "char",
+  "varchar",
+  "tinytext",
+  "text",
+  "mediumtext",
+  "longtext",
+  "enum",
+  "set",
+];
+
+/// `(table, column, data type, charset)` from `information_schema.columns`.
+type ColumnCharset = (String, String, String, Option<String>);
+
+fn charset_mismatches(
+  schema_charset: &str,
+  tables: &[(String, String)],
+  columns: &[ColumnCharset],
+) -> Vec<CharsetMismatch> {
+  let mismatch =
+    |table: &str, column: Option<&str>, expected: &str, actual: &str, problem: &str| {
+      CharsetMismatch {
+        table_name: table.to_string(),
+        column_name: column.map(String::from),
+        expected_charset: expected.to_string(),
+        actual_charset: actual.to_string(),
+        problem_type: problem.to_string(),
+      }
+    };
+
+  let mut problems = vec![];
+  for (table, charset) in tables {
+    if charset != schema_charset {
+      problems.push(mismatch(
+        table,
+        None,
+        schema_charset,
+        charset,
+        "table_charset",
+      ));
+    }
+    for (_, column, data_type, col_charset) in columns.iter().filter(|c| &c.0 == table) {
+      let Some(col_charset) = col_charset else {
+        continue;
+      };
+      let column = Some(column.as_str());
+      let is_text = TEXT_TYPES.contains(&data_type.to_ascii_lowercase().as_str());
+      if col_charset == "binary" {
+        if is_text {
+          problems.push(mismatch(table, column, charset, col_charset, "binary_text"));
+        }
+        continue;
+      }
+      if col_charset != charset {
+        problems.push(mismatch(
+          table,
+          column,
+          charset,
+          col_charset,
+          "column_charset",
+        ));
+      }
+      if is_text && col_charset != "utf8mb4" {
+        problems.push(mismatch(
+          table,
+          column,
+          "utf8mb4",
+          col_charset,
+          "non_utf8mb4",
+        ));
+      }
+    }
+  }
+  problems
+}
+
+/// Column attributes compared by `schema_diff`.
+#[derive(Debug, Clone, PartialEq)]
+struct ColumnDef {
+  name: String,
+  column_type: String,
+  nullable: bool,
+  default: Option<String>,
+}
+
+/// One difference between two column sets: `change` is `added`, `removed`,
+/// `type`, `nullable` or `default`.
+#[derive(Debug, Clone, PartialEq)]
+struct ColumnChange {
+  column: String,
+  change: &'static str,
+  before: Option<String>,
+  after: Option<String>,
+}
+
+fn diff_columns(a: &[ColumnDef], b: &[ColumnDef]) -> Vec<ColumnChange> {
+  let change = |column: &str, change, before: Option<&str>, after: Option<&str>| ColumnChange {
+    column: column.to_string(),
+    change,
+    before: before.map(String::from),
+    after: after.map(String::from),
+  };
+  let nullable = |c: &ColumnDef| if c.nullable { "YES" } else { "NO" };
+
+  let mut changes = vec![];
+  for old in a {
+    let Some(new) = b.iter().find(|c| c.name == old.name) else {
+      changes.push(change(&old.name, "removed", Some(&old.column_type), None));
+      continue;
+    };
+    if old.column_type != new.column_type {
+      changes.push(change(
+        &old.name,
+        "type",
+        Some(&old.column_type),
+        Some(&new.column_type),
+      ));
+    }
+    if old.nullable != new.nullable {
+      changes.push(change(
+        &old.name,
+        "nullable",
+        Some(nullable(old)),
+        Some(nullable(new)),
+      ));
+    }
+    if old.default != new.default {
+      changes.push(change(
+        &old.name,
+        "default",
+        old.default.as_deref(),
+        new.default.as_deref(),
+      ));
+    }
+  }
+  for new in b.iter().filter(|n| a.iter().all(|c| c.name != n.name)) {
+    changes.push(change(&new.name, "added", None, Some(&new.column_type)));
+  }
+  changes
+}
+
+fn changes_to_batch(changes: Vec<ColumnChange>) -> anyhow::Result<RecordBatch> {
+  let schema = Schema::new(vec![
+    Field::new("column", DataType::Utf8, false),
+    Field::new("change", DataType::Utf8, false),
+    Field::new("before", DataType::Utf8, true),
+    Field::new("after", DataType::Utf8, true),
+  ]);
+  let mut column = vec![];
+  let mut change = vec![];
+  let mut before = vec![];
+  let mut after = vec![];
+  for c in changes {
+    column.push(c.column);
+    change.push(c.change);
+    before.push(c.before);
+    after.push(c.after);
+  }
+  Ok(RecordBatch::try_new(
+    Arc::new(schema),
+    vec![
+      Arc::new(StringArray::from(column)),
+      Arc::new(StringArray::from(change)),
+      Arc::new(StringArray::from(before)),
+      Arc::new(StringArray::from(after)),
+    ],
+  )?)
+}
+
+/// A foreign key with no index starting with its columns.
+#[derive(Debug, Clone, PartialEq)]
+struct UnindexedFk {
+  table: String,
+  constraint: String,
+  columns: Vec<String>,
+  referenced_table: String,
+}
+
+/// `(table, constraint, column, referenced table)` in column order.
+type FkColumnRow = (String, String, String, String);
+/// `(table, index, column)` in `SEQ_IN_INDEX` order.
+type IndexColumnRow = (String, String, String);
+
+fn unindexed_fks(fk_columns: &[FkColumnRow], index_columns: &[IndexColumnRow]) -> Vec<UnindexedFk> {
+  let mut fks: Vec<UnindexedFk> = vec![];
+  for (table, constraint, column, referenced_table) in fk_columns {
+    match fks.last_mut() {
+      Some(fk) if &fk.table == table && &fk.constraint == constraint => {
+        fk.columns.push(column.clone())
+      }
+      _ => fks.push(UnindexedFk {
+        table: table.clone(),
+        constraint: constraint.clone(),
+        columns: vec![column.clone()],
+        referenced_table: referenced_table.clone(),
+      }),
+    }
+  }
+  let mut indexes: HashMap<(&str, &str), Vec<&str>> = HashMap::new();
+  for (table, index, column) in index_columns {
+    indexes.entry((table, index)).or_default().push(column);
+  }
+  fks.retain(|fk| {
+    !indexes.iter().any(|((table, _), columns)| {
+      *table == fk.table
+        && columns.len() >= fk.columns.len()
+        && fk
+          .columns
+          .iter()
+          .zip(columns)
+          .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    })
+  });
+  fks
+}
+
+fn unindexed_fks_batch(fks: Vec<UnindexedFk>) -> anyhow::Result<RecordBatch> {
+  let schema = Schema::new(vec![
+    Field::new("table_name", DataType::Utf8, false),
+    Field::new("constraint_name", DataType::Utf8, false),
+    Field::new("columns", DataType::Utf8, false),
+    Field::new("referenced_table", DataType::Utf8, false),
+  ]);
+  let mut table = vec![];
+  let mut constraint = vec![];
+  let mut columns = vec![];
+  let mut referenced = vec![];
+  for fk in fks {
+    table.push(fk.table);
+    constraint.push(fk.constraint);
+    columns.push(fk.columns.join(", "));
+    referenced.push(fk.referenced_table);
+  }
+  Ok(RecordBatch::try_new(
+    Arc::new(schema),
+    vec![
+      Arc::new(StringArray::from(table)),
+      Arc::new(StringArray::from(constraint)),
+      Arc::new(StringArray::from(columns)),
+      Arc::new(StringArray::from(referenced)),
+    ],
+  )?)
+}
+
+/// `information_schema.events` row: name, status, type, EXECUTE_AT,
+/// INTERVAL_VALUE, INTERVAL_FIELD, STARTS, ENDS, LAST_EXECUTED and the
+/// server's `NOW()`, datetimes as text.
+type EventRow = (
+  String,
+  String,
+  String,
+  Option<String>,
+  Option<String>,
+  Option<String>,
+  Option<String>,
+  Option<String>,
+  Option<String>,
+  String,
+);
+
+fn parse_datetime(value: &str) -> Option<NaiveDateTime> {
+  NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok()
+}
+
+/// Length of a fixed-size `EVERY n unit` interval; months, years and
+/// compound units like `DAY_HOUR` vary or need parsing and give `None`.
+fn interval_step(value: &str, field: &str) -> Option<TimeDelta> {
+  let n: i64 = value.trim().parse().ok()?;
+  let seconds = match field {
+    "SECOND" => 1,
+    "MINUTE" => 60,
+    "HOUR" => 3_600,
+    "DAY" => 86_400,
+    "WEEK" => 604_800,
+    _ => return None,
+  };
+  (n > 0).then(|| TimeDelta::seconds(n * seconds))
+}
+
+/// Next run after `now`: the pending `EXECUTE_AT` of a one-time event, or
+/// the next `STARTS + k * interval` of a recurring one before `ENDS`.
+fn next_execution(row: &EventRow) -> Option<String> {
+  let (_, status, event_type, execute_at, value, field, starts, ends, last, now) = row;
+  if status != "ENABLED" {
+    return None;
+  }
+  let now = parse_datetime(now)?;
+  if event_type == "ONE TIME" {
+    return execute_at.clone().filter(|_| last.is_none());
+  }
+  let step = interval_step(value.as_deref()?, field.as_deref()?)?;
+  let starts = parse_datetime(starts.as_deref()?)?;
+  let next = if starts > now {
+    starts
+  } else {
+    let elapsed = (now - starts).num_seconds();
+    let steps = elapsed / step.num_seconds() + 1;
+    starts + step * steps as i32
+  };
+  if ends
+    .as_deref()
+    .and_then(parse_datetime)
+    .is_some_and(|ends| next > ends)
+  {
+    return None;
+  }
+  Some(next.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+fn events_batch(rows: &[EventRow]) -> anyhow::Result<RecordBatch> {
+  let schema = Schema::new(vec![
+    Field::new("event_name", DataType::Utf8, false),
+    Field::new("status", DataType::Utf8, false),
+    Field::new("schedule", DataType::Utf8, true),
+    Field::new("last_executed", DataType::Utf8, true),
+    Field::new("next_execution", DataType::Utf8, true),
+  ]);
+  let mut name = vec![];
+  let mut status = vec![];
+  let mut schedule = vec![];
+  let mut last = vec![];
+  let mut next = vec![];
+  for row in rows {
+    name.push(row.0.clone());
+    status.push(row.1.clone());
+    schedule.push(match (&row.3, &row.4, &row.5) {
+      (Some(at), _, _) => Some(format!("AT '{at}'")),
+      (None, Some(value), Some(field)) => Some(format!("EVERY {value} {field}")),
+      _ => None,
+    });
+    last.push(row.8.clone());
+    next.push(next_execution(row));
+  }
+  Ok(RecordBatch::try_new(
+    Arc::new(schema),
+    vec![
+      Arc::new(StringArray::from(name)),
+      Arc::new(StringArray::from(status)),
+      Arc::new(StringArray::from(schedule)),
+      Arc::new(StringArray::from(last)),
+      Arc::new(StringArray::from(next)),
+    ],
+  )?)
+}
+
+/// Views ordered so each comes after the views its definition selects from.
+/// Views in a reference cycle keep their original relative order at the end.
+fn view_creation_order(schema: &str, views: &[(String, String)]) -> Vec<String> {
+  let refers_to = |definition: &str, view: &str| {
+    definition.contains(&format!("{}.{}", quote_ident(schema), quote_ident(view)))
+  };
+  let mut pending: Vec<&(String, String)> = views.iter().collect();
+  let mut order: Vec<String> = vec![];
+  while !pending.is_empty() {
+    let ready = pending.iter().position(|(name, definition)| {
+      pending
+        .iter()
+        .all(|(other, _)| other == name || !refers_to(definition, other))
+    });
+    let (name, _) = pending.remove(ready.unwrap_or(0));
+    order.push(name.clone());
+  }
+  order
+}
+
+/// A `CREATE DATABASE` + `USE` script of `objects`; routine and trigger
+/// bodies contain `;` and are wrapped in a `DELIMITER ;;` block.
+fn ddl_script(create_database: &str, schema: &str, objects: &[(String, bool)]) -> String {
+  let mut script = format!("{create_database};\nUSE {};\n", quote_ident(schema));
+  for (ddl, compound) in objects {
+    if *compound {
+      script.push_str(&format!("\nDELIMITER ;;\n{ddl} ;;\nDELIMITER ;\n"));
+    } else {
+      script.push_str(&format!("\n{ddl};\n"));
+    }
+  }
+  script
+}
+
+impl MySqlConnection {
+  /// DDL of every table, view, routine and trigger of `schema`, preceded by
+  /// its `CREATE DATABASE`. Tables come in foreign key order (referenced
+  /// tables first) and views after the tables and views they select from.
+  pub async fn dump_schema_ddl(&self, schema: &str) -> anyhow::Result<String> {
+    let graph = self.fk_graph(schema)?;
+    // referenced tables first; with a cycle any order works once the
+    // checks are off
+    let (tables, cyclic) = match graph.topological_truncation_order() {
+      Ok(order) => (order.into_iter().rev().collect::<Vec<_>>(), false),
+      Err(_) => (graph.nodes.clone(), true),
+    };
+
+    let mut conn = self.get_conn()?;
+    let create_db: Option<(String, String)> =
+      conn.query_first(format!("SHOW CREATE DATABASE {}", quote_ident(schema)))?;
+    let Some((_, create_db)) = create_db else {
+      anyhow::bail!("schema {schema} not found");
+    };
+
+    let mut objects: Vec<(String, bool)> = vec![];
+    if cyclic {
+      objects.push(("SET FOREIGN_KEY_CHECKS = 0".to_string(), false));
+    }
+    for table in &tables {
+      let sql = format!("SHOW CREATE TABLE {}", quote_table(Some(schema), table));
+      if let Some((_, ddl)) = conn.query_first::<(String, String), _>(sql)? {
+        objects.push((ddl, false));
+      }
+    }
+    if cyclic {
+      objects.push(("SET FOREIGN_KEY_CHECKS = 1".to_string(), false));
+    }
+
+    let views: Vec<(String, String)> = conn.exec(
+      r"
+      select TABLE_NAME, VIEW_DEFINITION from information_schema.views
+      where TABLE_SCHEMA = ? order by TABLE_NAME
+      ",
+      (schema,),
+    )?;
+    for view in view_creation_order(schema, &views) {
+      let sql = format!("SHOW CREATE VIEW {}", quote_table(Some(schema), &view));
+      if let Some(row) = conn.query_first::<mysql::Row, _>(sql)? {
+        objects.push((row.get::<String, _>(1).unwrap_or_default(), false));
+      }
+    }
+
+    // definitions are NULL without the privileges to read them
+    let routines: Vec<(String, String)> = conn.exec(
+      r"
+      select ROUTINE_TYPE, ROUTINE_NAME from information_schema.routines
+      where ROUTINE_SCHEMA = ? order by ROUTINE_TYPE, ROUTINE_NAME
+      ",
+      (schema,),
+    )?;
+    let triggers: Vec<String> = conn.exec(
+      r"
+      select TRIGGER_NAME from information_schema.triggers
+      where TRIGGER_SCHEMA = ? order by EVENT_OBJECT_TABLE, ACTION_ORDER
+      ",
+      (schema,),
+    )?;
+    let compound = routines
+      .into_iter()
+      .chain(triggers.into_iter().map(|t| ("TRIGGER".to_string(), t)));
+    for (kind, name) in compound {
+      let sql = format!("SHOW CREATE {kind} {}", quote_table(Some(schema), &name));
+      if let Some(row) = conn.query_first::<mysql::Row, _>(sql)? {
+        match row.get::<Option<String>, _>(2).flatten() {
+          Some(ddl) => objects.push((ddl, true)),
+          None => log::warn!("no permission to read the definition of {kind} {name}"),
+        }
+      }
+    }
+
+    Ok(ddl_script(&create_db, schema, &objects))
+  }
+
+  /// Foreign keys of `schema` whose columns are not the leading columns of
+  /// any index, so checks against the child table scan it.
+  ///
+  /// InnoDB creates such an index itself, so this mostly finds foreign keys
+  /// of engines that do not, e.g. NDB.
+  pub async fn unindexed_foreign_keys(&self, schema: &str) -> anyhow::Result<RawArrowData> {
+    let mut conn = self.get_conn()?;
+    let fk_columns: Vec<FkColumnRow> = conn.exec(
+      r"
+      select TABLE_NAME, CONSTRAINT_NAME, COLUMN_NAME, REFERENCED_TABLE_NAME
+      from information_schema.key_column_usage
+      where TABLE_SCHEMA = ? and REFERENCED_TABLE_NAME is not null
+      order by TABLE_NAME, CONSTRAINT_NAME, ORDINAL_POSITION
+      ",
+      (schema,),
+    )?;
+    let index_columns: Vec<IndexColumnRow> = conn.exec(
+      r"
+      select TABLE_NAME, INDEX_NAME, COLUMN_NAME
+      from information_schema.statistics
+      where TABLE_SCHEMA = ?
+      order by TABLE_NAME, INDEX_NAME, SEQ_IN_INDEX
+      ",
+      (schema,),
+    )?;
+    let batch = unindexed_fks_batch(unindexed_fks(&fk_columns, &index_columns))?;
+    Ok(RawArrowData::from_batch(batch))
+  }
+
+  /// Scheduled events of `schema` with their schedule and last/next run,
+  /// in server time.
+  pub async fn list_events(&self, schema: &str) -> anyhow::Result<RawArrowData> {
+    let mut conn = self.get_conn()?;
+    let rows: Vec<EventRow> = conn.exec(
+      r"
+      select EVENT_NAME, STATUS, EVENT_TYPE, cast(EXECUTE_AT as char),
+        INTERVAL_VALUE, INTERVAL_FIELD, cast(STARTS as char), cast(ENDS as char),
+        cast(LAST_EXECUTED as char), cast(now() as char)
+      from information_schema.events
+      where EVENT_SCHEMA = ?
+      order by EVENT_NAME
+      ",
+      (schema,),
+    )?;
+    Ok(RawArrowData::from_batch(events_batch(&rows)?))
+  }
+
+  fn column_defs(&self, schema: &str, table: &str) -> anyhow::Result<Vec<ColumnDef>> {
+    let mut conn = self.get_conn()?;
+    let rows: Vec<(String, String, String, Option<String>)> = conn.exec(
+      r"
+      select COLUMN_NAME, COLUMN_TYPE, IS_NULLABLE, COLUMN_DEFAULT
+      from information_schema.columns
+      where TABLE_SCHEMA = ? and TABLE_NAME = ?
+      order by ORDINAL_POSITION
+      ",
+      (schema, table),
+    )?;
+    if rows.is_empty() {
+      anyhow::bail!("table {schema}.{table} not found");
+    }
+    Ok(
+      rows
+        .into_iter()
+        .map(|(name, column_type, nullable, default)| ColumnDef {
+          name,
+          column_type,
+          nullable: nullable == "YES",
+          default,
+        })
+        .collect(),
+    )
+  }
+
+  /// Tables and columns whose character set differs from the schema or
+  /// table default, or is not utf8mb4.
+  pub fn charset_mismatch_report(&self, schema: &str) -> anyhow::Result<Vec<CharsetMismatch>> {
+    let mut conn = self.get_conn()?;
+    let schema_charset: Option<String> = conn.exec_first(
+      "select DEFAULT_CHARACTER_SET_NAME from information_schema.schemata where SCHEMA_NAME = ?",
+      (schema,),
+    )?;
+    let Some(schema_charset) = schema_charset else {
+      anyhow::bail!("schema {schema} not found");
+    };
+    let tables: Vec<(String, String)> = conn.exec(
+      r"
+      select t.TABLE_NAME, c.CHARACTER_SET_NAME
+      from information_schema.tables t
+      join information_schema.collation_character_set_applicability c
+        on c.COLLATION_NAME = t.TABLE_COLLATION
+      where t.TABLE_SCHEMA = ? and t.TABLE_TYPE = 'BASE TABLE'
+      order by t.TABLE_NAME
+      ",
+      (schema,),
+    )?;
+    let columns: Vec<ColumnCharset> = conn.exec(
+      r"
+      select TABLE_NAME, COLUMN_NAME, DATA_TYPE, CHARACTER_SET_NAME
+      from information_schema.columns
+      where TABLE_SCHEMA = ?
+      order by TABLE_NAME, ORDINAL_POSITION
+      ",
+      (schema,),
+    )?;
+    Ok(charset_mismatches(&schema_charset, &tables, &columns))
+  }
+
+  /// Column differences from table A to table B, one row per added or
+  /// removed column and per changed type, nullability or default.
+  pub async fn schema_diff(
+    &self,
+    schema_a: &str,
+    table_a: &str,
+    schema_b: &str,
+    table_b: &str,
+  ) -> anyhow::Result<RawArrowData> {
+    let a = self.column_defs(schema_a, table_a)?;
+    let b = self.column_defs(schema_b, table_b)?;
+    let batch = changes_to_batch(diff_columns(&a, &b))?;
+    Ok(RawArrowData::from_batch(batch))
+  }
+
+  /// Foreign key graph of the base tables in `schema`; references to other
+  /// schemas are left out.
+  pub fn fk_graph(&self, schema: &str) -> anyhow::Result<FkGraph> {
+    let mut conn = self.get_conn()?;
+    let nodes: Vec<String> = conn.exec(
+      r"
+      select TABLE_NAME from information_schema.tables
+      where TABLE_SCHEMA = ? and TABLE_TYPE = 'BASE TABLE'
+      order by TABLE_NAME
+      ",
+      (schema,),
+    )?;
+    let edges: Vec<(String, String)> = conn.exec(
+      r"
+      select TABLE_NAME, REFERENCED_TABLE_NAME
+      from information_schema.referential_constraints
+      where CONSTRAINT_SCHEMA = ? and UNIQUE_CONSTRAINT_SCHEMA = ?
+      order by TABLE_NAME, CONSTRAINT_NAME
+      ",
+      (schema, schema),
+    )?;
+    let mut nodes = nodes;
+    for (from, to) in &edges {
+      for table in [from, to] {
+        if !nodes.contains(table) {
+          nodes.push(table.clone());
+        }
+      }
+    }
+    Ok(FkGraph { nodes, edges })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dialect::mysql::duckling_test_db;
+
+  fn graph(nodes: &[&str], edges: &[(&str, &str)]) -> FkGraph {
+    FkGraph {
+      nodes: nodes.iter().map(|n| n.to_string()).collect(),
+      edges: edges
+        .iter()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect(),
+    }
+  }
+
+  fn def(name: &str, column_type: &str, nullable: bool, default: Option<&str>) -> ColumnDef {
+    ColumnDef {
+      name: name.to_string(),
+      column_type: column_type.to_string(),
+      nullable,
+      default: default.map(String::from),
+    }
+  }
+
+  #[test]
+  fn test_charset_mismatches() {
+    let tables = vec![
+      ("orders".to_string(), "utf8mb4".to_string()),
+      ("legacy".to_string(), "latin1".to_string()),
+    ];
+    let col = |t: &str, c: &str, ty: &str, cs: Option<&str>| -> ColumnCharset {
+      (t.into(), c.into(), ty.into(), cs.map(String::from))
+    };
+    let columns = vec![
+      col("orders", "id", "int", None),
+      col("orders", "note", "varchar", Some("utf8mb4")),
+      col("orders", "city", "varchar", Some("latin1")),
+      col("orders", "raw", "varchar", Some("binary")),
+      col("legacy", "name", "text", Some("latin1")),
+    ];
+    let problems = charset_mismatches("utf8mb4", &tables, &columns);
+    let kinds: Vec<(&str, Option<&str>, &str)> = problems
+      .iter()
+      .map(|p| {
+        (
+          p.table_name.as_str(),
+          p.column_name.as_deref(),
+          p.problem_type.as_str(),
+        )
+      })
+      .collect();
+    assert_eq!(
+      kinds,
+      [
+        ("orders", Some("city"), "column_charset"),
+        ("orders", Some("city"), "non_utf8mb4"),
+        ("orders", Some("raw"), "binary_text"),
+        ("legacy", None, "table_charset"),
+        ("legacy", Some("name"), "non_utf8mb4"),
+      ]
+    );
+    assert_eq!(problems[0].expected_charset, "utf8mb4");
+    assert_eq!(problems[0].actual_charset, "latin1");
+  }
+
+  #[test]
+  fn test_diff_columns() {
+    let a = vec![
+      def("id", "int", false, None),
+      def("name", "varchar(32)", true, None),
+      def("status", "tinyint", false, Some("0")),
+    ];
+    let mut b = a.clone();
+    b.push(def("email", "varchar(255)", true, None));
+    assert_eq!(
+      diff_columns(&a, &b),
+      [ColumnChange {
+        column: "email".to_string(),
+        change: "added",
+        before: None,
+        after: Some("varchar(255)".to_string()),
+      }]
+    );
+
+    let b = vec![
+      def("id", "bigint", false, None),
+      def("status", "tinyint", true, Some("1")),
+    ];
+    let changes = diff_columns(&a, &b);
+    let kinds: Vec<(&str, &str)> = changes
+      .iter()
+      .map(|c| (c.column.as_str(), c.change))
+      .collect();
+    assert_eq!(
+      kinds,
+      [
+        ("id", "type"),
+        ("name", "removed"),
+        ("status", "nullable"),
+        ("status", "default")
+      ]
+    );
+    let batch = changes_to_batch(changes).unwrap();
+    assert_eq!(batch.num_rows(), 4);
+  }
+
+  #[test]
+  fn test_detect_cycles() {
+    let g = graph(
+      &["A", "B", "C", "D"],
+      &[("A", "B"), ("B", "C"), ("C", "A"), ("D", "A"), ("D", "D")],
+    );
+    assert_eq!(g.detect_cycles(), [vec!["A", "B", "C"], vec!["D"]]);
+    let err = g.topological_truncation_order().unwrap_err();
+    assert!(err.to_string().contains("A -> B -> C"));
+  }
+
+  #[test]
+  fn test_unindexed_fks() {
+    let fk = |table: &str, constraint: &str, column: &str, to: &str| -> FkColumnRow {
+      (table.into(), constraint.into(), column.into(), to.into())
+    };
+    let index = |table: &str, index: &str, column: &str| -> IndexColumnRow {
+      (table.into(), index.into(), column.into())
+    };
+    let fk_columns = [
+      fk("orders", "fk_customer", "customer_id", "customers"),
+      fk("items", "fk_order", "order_id", "orders"),
+      fk("items", "fk_variant", "product_id", "variants"),
+      fk("items", "fk_variant", "variant_id", "variants"),
+    ];
+    let index_columns = [
+      index("orders", "PRIMARY", "id"),
+      index("orders", "idx_customer", "customer_id"),
+      // order_id only appears as the second column
+      index("items", "idx_sku", "sku"),
+      index("items", "idx_sku", "order_id"),
+      index("items", "idx_variant", "product_id"),
+      index("items", "idx_variant", "variant_id"),
+      index("items", "idx_variant", "sku"),
+    ];
+    let fks = unindexed_fks(&fk_columns, &index_columns);
+    assert_eq!(
+      fks,
+      [UnindexedFk {
+        table: "items".into(),
+        constraint: "fk_order".into(),
+        columns: vec!["order_id".into()],
+        referenced_table: "orders".into(),
+      }]
+    );
+    let batch = unindexed_fks_batch(fks).unwrap();
+    assert_eq!(batch.num_rows(), 1);
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_unindexed_foreign_keys() {
+    let conn = crate::dialect::mysql::local_connection();
+    let mut c = duckling_test_db(&conn);
+    c.query_drop("drop table if exists duckling_test.fk_child")
+      .unwrap();
+    c.query_drop("drop table if exists duckling_test.fk_parent")
+      .unwrap();
+    c.query_drop("create table duckling_test.fk_parent (id int primary key)")
+      .unwrap();
+    // InnoDB adds an index on parent_id for the foreign key
+    c.query_drop(
+      "create table duckling_test.fk_child (id int, parent_id int, \
+       foreign key (parent_id) references duckling_test.fk_parent (id))",
+    )
+    .unwrap();
+    let res = conn.unindexed_foreign_keys("duckling_test").await.unwrap();
+    let tables = res
+      .batch
+      .column(0)
+      .as_any()
+      .downcast_ref::<StringArray>()
+      .unwrap();
+    assert!(tables.iter().flatten().all(|t| t != "fk_child"));
+  }
+
+  #[test]
+  fn test_next_execution() {
+    let s = |v: &str| Some(v.to_string());
+    let recurring: EventRow = (
+      "purge".into(),
+      "ENABLED".into(),
+      "RECURRING".into(),
+      None,
+      s("6"),
+      s("HOUR"),
+      s("2024-01-01 00:00:00"),
+      None,
+      s("2024-01-02 12:00:00"),
+      "2024-01-02 13:30:00".into(),
+    );
+    assert_eq!(next_execution(&recurring), s("2024-01-02 18:00:00"));
+
+    let mut monthly = recurring.clone();
+    monthly.4 = s("1");
+    monthly.5 = s("MONTH");
+    assert_eq!(next_execution(&monthly), None);
+
+    let once: EventRow = (
+      "once".into(),
+      "ENABLED".into(),
+      "ONE TIME".into(),
+      s("2024-02-01 00:00:00"),
+      None,
+      None,
+      None,
+      None,
+      None,
+      "2024-01-02 13:30:00".into(),
+    );
+    assert_eq!(next_execution(&once), s("2024-02-01 00:00:00"));
+
+    let batch = events_batch(&[recurring, once]).unwrap();
+    let schedule = batch
+      .column(2)
+      .as_any()
+      .downcast_ref::<StringArray>()
+      .unwrap();
+    assert_eq!(schedule.value(0), "EVERY 6 HOUR");
+    assert_eq!(schedule.value(1), "AT '2024-02-01 00:00:00'");
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_list_events() {
+    let conn = crate::dialect::mysql::local_connection();
+    let mut c = duckling_test_db(&conn);
+    c.query_drop("drop event if exists duckling_test.nightly")
+      .unwrap();
+    c.query_drop("create event duckling_test.nightly on schedule every 1 day do select 1")
+      .unwrap();
+    let res = conn.list_events("duckling_test").await.unwrap();
+    let names = res
+      .batch
+      .column(0)
+      .as_any()
+      .downcast_ref::<StringArray>()
+      .unwrap();
+    let pos = names.iter().position(|n| n == Some("nightly")).unwrap();
+    let schedule = res
+      .batch
+      .column(2)
+      .as_any()
+      .downcast_ref::<StringArray>()
+      .unwrap();
+    assert_eq!(schedule.value(pos), "EVERY 1 DAY");
+  }
+
+  #[test]
+  fn test_view_creation_order() {
+    let view = |name: &str, definition: &str| (name.to_string(), definition.to_string());
+    let views = [
+      view(
+        "a_summary",
+        "select * from `shop`.`b_orders` join `shop`.`orders`",
+      ),
+      view("b_orders", "select * from `shop`.`orders`"),
+      view("c_other", "select 1"),
+    ];
+    assert_eq!(
+      view_creation_order("shop", &views),
+      ["b_orders", "a_summary", "c_other"]
+    );
+  }
+
+  #[test]
+  fn test_ddl_script() {
+    let objects = [
+      ("CREATE TABLE `t` (`id` int)".to_string(), false),
+      (
+        "CREATE PROCEDURE `p`() BEGIN SELECT 1; END".to_string(),
+        true,
+      ),
+    ];
+    assert_eq!(
+      ddl_script("CREATE DATABASE `shop`", "shop", &objects),
+      "CREATE DATABASE `shop`;\nUSE `shop`;\n\
+       \nCREATE TABLE `t` (`id` int);\n\
+       \nDELIMITER ;;\nCREATE PROCEDURE `p`() BEGIN SELECT 1; END ;;\nDELIMITER ;\n"
+    );
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_dump_schema_ddl() {
+    let conn = crate::dialect::mysql::local_connection();
+    let mut c = conn.get_conn().unwrap();
+    c.query_drop("drop database if exists duckling_ddl")
+      .unwrap();
+    c.query_drop("create database duckling_ddl").unwrap();
+    for sql in [
+      "create table duckling_ddl.parent (id int primary key)",
+      "create table duckling_ddl.child (id int, parent_id int, \
+       foreign key (parent_id) references duckling_ddl.parent (id))",
+      "create view duckling_ddl.v_child as select * from duckling_ddl.child",
+      "create procedure duckling_ddl.p() begin select 1; select 2; end",
+    ] {
+      c.query_drop(sql).unwrap();
+    }
+
+    let ddl = conn.dump_schema_ddl("duckling_ddl").await.unwrap();
+    assert!(ddl.starts_with("CREATE DATABASE `duckling_ddl`"));
+    let pos = |needle: &str| ddl.find(needle).unwrap();
+    assert!(pos("CREATE TABLE `parent`") < pos("CREATE TABLE `child`"));
+    assert!(pos("CREATE TABLE `child`") < pos("VIEW `v_child`"));
+    assert!(pos("PROCEDURE `p`") > pos("VIEW `v_child`"));
+  }
+
+  #[test]
+  fn test_truncation_order() {
+    // orders -> customers, order_items -> orders, order_items -> products
+    let g = graph(
+      &["customers", "order_items", "orders", "products", "tree"],
+      &[
+        ("orders", "customers"),
+        ("order_items", "orders"),
+        ("order_items", "products"),
+        ("tree", "tree"),
+      ],
+    );
+    assert!(g.detect_cycles().iter().all(|c| c.len() == 1));
+    let order = g.topological_truncation_order().unwrap();
+    let pos = |t: &str| order.iter().position(|o| o == t).unwrap();
+    assert_eq!(order.len(), 5);
+    assert!(pos("order_items") < pos("orders"));
+    assert!(pos("order_items") < pos("products"));
+    assert!(pos("orders") < pos("customers"));
+  }
+}
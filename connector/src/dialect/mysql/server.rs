@@ -0,0 +1,386 @@
+use mysql::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::MySqlConnection;
+use super::status::{VarMap, var_bool, var_str, var_u64};
+use crate::utils::RawArrowData;
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryCacheStats {
+  pub hits: u64,
+  pub inserts: u64,
+  pub not_cached: u64,
+  pub prunes: u64,
+  pub queries_in_cache: u64,
+  pub total_blocks: u64,
+  pub free_blocks: u64,
+  /// `hits / (hits + inserts + not_cached)`
+  pub hit_ratio: f64,
+}
+
+impl QueryCacheStats {
+  /// `None` when the query cache is off, or gone entirely as in MySQL 8.0
+  /// where `query_cache_type` no longer exists.
+  pub fn from_vars(variables: &VarMap, status: &VarMap) -> Option<Self> {
+    let cache_type = var_str(variables, &["query_cache_type"])?;
+    if matches!(cache_type.trim().to_ascii_uppercase().as_str(), "OFF" | "0") {
+      return None;
+    }
+    let hits = var_u64(status, &["Qcache_hits"]);
+    let inserts = var_u64(status, &["Qcache_inserts"]);
+    let not_cached = var_u64(status, &["Qcache_not_cached"]);
+    let lookups = hits + inserts + not_cached;
+    Some(Self {
+      hits,
+      inserts,
+      not_cached,
+      prunes: var_u64(status, &["Qcache_lowmem_prunes"]),
+      queries_in_cache: var_u64(status, &["Qcache_queries_in_cache"]),
+      total_blocks: var_u64(status, &["Qcache_total_blocks"]),
+      free_blocks: var_u64(status, &["Qcache_free_blocks"]),
+      hit_ratio: if lookups > 0 {
+        hits as f64 / lookups as f64
+      } else {
+        0.0
+      },
+    })
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EngineSupport {
+  Default,
+  Yes,
+  No,
+  Disabled,
+}
+
+impl EngineSupport {
+  fn parse(support: &str) -> Self {
+    match support.trim().to_ascii_uppercase().as_str() {
+      "DEFAULT" => Self::Default,
+      "YES" => Self::Yes,
+      "DISABLED" => Self::Disabled,
+      _ => Self::No,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageEngine {
+  pub engine: String,
+  pub support: EngineSupport,
+  pub transactions: bool,
+  pub xa: bool,
+  pub savepoints: bool,
+}
+
+/// `SHOW ENGINES` row: Engine, Support, Comment, Transactions, XA, Savepoints.
+type EngineRow = (
+  String,
+  String,
+  Option<String>,
+  Option<String>,
+  Option<String>,
+  Option<String>,
+);
+
+impl StorageEngine {
+  /// Capability columns are `NULL` for engines that are not available.
+  fn from_row((engine, support, _, transactions, xa, savepoints): EngineRow) -> Self {
+    let yes = |v: Option<String>| v.is_some_and(|v| v.eq_ignore_ascii_case("YES"));
+    Self {
+      engine,
+      support: EngineSupport::parse(&support),
+      transactions: yes(transactions),
+      xa: yes(xa),
+      savepoints: yes(savepoints),
+    }
+  }
+}
+
+fn default_engine(engines: &[StorageEngine]) -> Option<&StorageEngine> {
+  engines.iter().find(|e| e.support == EngineSupport::Default)
+}
+
+/// Server features the monitoring views rely on.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitoringPrereqs {
+  pub performance_schema_enabled: bool,
+  pub slow_query_log_enabled: bool,
+  pub general_log_enabled: bool,
+  pub binlog_enabled: bool,
+  /// `OFF` when the server has no GTID support at all.
+  pub gtid_mode: String,
+  pub audit_plugin_loaded: bool,
+}
+
+const MONITORING_VARIABLES: [&str; 5] = [
+  "performance_schema",
+  "slow_query_log",
+  "general_log",
+  "log_bin",
+  "gtid_mode",
+];
+
+impl MonitoringPrereqs {
+  pub fn from_vars(vars: &VarMap, audit_plugin_loaded: bool) -> Self {
+    Self {
+      performance_schema_enabled: var_bool(vars, &["performance_schema"]),
+      slow_query_log_enabled: var_bool(vars, &["slow_query_log"]),
+      general_log_enabled: var_bool(vars, &["general_log"]),
+      binlog_enabled: var_bool(vars, &["log_bin"]),
+      gtid_mode: var_str(vars, &["gtid_mode"]).unwrap_or("OFF").to_string(),
+      audit_plugin_loaded,
+    }
+  }
+
+  /// Features that are off but would widen monitoring coverage.
+  pub fn missing_features(&self) -> Vec<String> {
+    let gtid = self.gtid_mode.eq_ignore_ascii_case("ON");
+    [
+      (self.performance_schema_enabled, "performance_schema"),
+      (self.slow_query_log_enabled, "slow_query_log"),
+      (self.general_log_enabled, "general_log"),
+      (self.binlog_enabled, "binary log"),
+      (gtid, "gtid_mode"),
+      (self.audit_plugin_loaded, "audit plugin"),
+    ]
+    .into_iter()
+    .filter(|(enabled, _)| !enabled)
+    .map(|(_, name)| name.to_string())
+    .collect()
+  }
+}
+
+/// Seconds in a `TIME` column, keeping the microseconds.
+fn time_seconds(col: &str) -> String {
+  format!("hour({col}) * 3600 + minute({col}) * 60 + second({col}) + microsecond({col}) / 1e6")
+}
+
+impl MySqlConnection {
+  /// Which of the server features used for monitoring are available.
+  pub fn check_monitoring_prerequisites(&self) -> anyhow::Result<MonitoringPrereqs> {
+    let vars = self.global_variables_in(&MONITORING_VARIABLES)?;
+    let mut conn = self.get_conn()?;
+    let audit_plugins: Option<u64> = conn.query_first(
+      r"
+      select count(*) from information_schema.plugins
+      where PLUGIN_NAME like '%audit%' and PLUGIN_STATUS = 'ACTIVE'
+      ",
+    )?;
+    Ok(MonitoringPrereqs::from_vars(
+      &vars,
+      audit_plugins.unwrap_or_default() > 0,
+    ))
+  }
+
+  /// Query cache counters on MySQL 5.7 and earlier.
+  pub fn query_cache_stats(&self) -> anyhow::Result<Option<QueryCacheStats>> {
+    let variables = self.global_variables("query_cache_type")?;
+    if variables.is_empty() {
+      return Ok(None);
+    }
+    let status = self.global_status("Qcache%")?;
+    Ok(QueryCacheStats::from_vars(&variables, &status))
+  }
+
+  /// Storage engines and their capabilities from `SHOW ENGINES`.
+  pub fn storage_engines(&self) -> anyhow::Result<Vec<StorageEngine>> {
+    let mut conn = self.get_conn()?;
+    let rows: Vec<EngineRow> = conn.query("SHOW ENGINES")?;
+    Ok(rows.into_iter().map(StorageEngine::from_row).collect())
+  }
+
+  /// The engine new tables get when `ENGINE=` is omitted.
+  pub fn default_storage_engine(&self) -> anyhow::Result<String> {
+    let engines = self.storage_engines()?;
+    default_engine(&engines)
+      .map(|e| e.engine.clone())
+      .ok_or_else(|| anyhow::anyhow!("no default storage engine"))
+  }
+
+  /// Spatial reference systems known to the server (MySQL 8.0+).
+  pub async fn list_srs(&self) -> anyhow::Result<RawArrowData> {
+    let mut conn = self.get_conn()?;
+    let exists: Option<u64> = conn.query_first(
+      r"
+      select count(*) from information_schema.tables
+      where TABLE_SCHEMA = 'information_schema'
+        and TABLE_NAME = 'ST_SPATIAL_REFERENCE_SYSTEMS'
+      ",
+    )?;
+    if exists.unwrap_or_default() == 0 {
+      anyhow::bail!("unsupported: spatial reference systems require MySQL 8.0 or later");
+    }
+    self._query(
+      "select SRS_NAME, SRS_ID, DEFINITION \
+       from information_schema.st_spatial_reference_systems order by SRS_ID",
+    )
+  }
+
+  /// The `limit` slowest statements from `mysql.slow_log`, which is only
+  /// written when `slow_query_log` is on and `log_output` includes `TABLE`.
+  pub async fn slow_queries(&self, limit: usize) -> anyhow::Result<RawArrowData> {
+    let vars = self.global_variables_in(&["slow_query_log", "log_output"])?;
+    if !var_bool(&vars, &["slow_query_log"]) {
+      anyhow::bail!(
+        "the slow query log is disabled, enable it with SET GLOBAL slow_query_log = ON"
+      );
+    }
+    let output = var_str(&vars, &["log_output"]).unwrap_or_default();
+    if !output
+      .to_ascii_uppercase()
+      .split(',')
+      .any(|o| o.trim() == "TABLE")
+    {
+      anyhow::bail!(
+        "the slow query log is written to {output}, not a table; \
+         use SET GLOBAL log_output = 'TABLE'"
+      );
+    }
+    let sql = format!(
+      r"
+      select cast(start_time as char) as start_time, user_host,
+        {} as query_time_s, {} as lock_time_s,
+        rows_sent, rows_examined, db, cast(sql_text as char) as sql_text
+      from mysql.slow_log
+      order by query_time desc
+      limit {limit}
+      ",
+      time_seconds("query_time"),
+      time_seconds("lock_time"),
+    );
+    self._query(&sql)
+  }
+
+  /// Server default `(character_set_server, collation_server)`.
+  pub async fn server_charset(&self) -> anyhow::Result<(String, String)> {
+    let mut conn = self.get_conn()?;
+    let charset: Option<(String, String)> =
+      conn.query_first("select @@character_set_server, @@collation_server")?;
+    charset.ok_or_else(|| anyhow::anyhow!("server returned no charset"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dialect::mysql::status::var_map as vars;
+
+  #[test]
+  fn test_query_cache_stats() {
+    let status = vars(&[
+      ("Qcache_hits", "300"),
+      ("Qcache_inserts", "80"),
+      ("Qcache_not_cached", "20"),
+      ("Qcache_lowmem_prunes", "5"),
+      ("Qcache_queries_in_cache", "42"),
+      ("Qcache_total_blocks", "100"),
+      ("Qcache_free_blocks", "10"),
+    ]);
+
+    // MySQL 5.7 with the cache enabled
+    let stats = QueryCacheStats::from_vars(&vars(&[("query_cache_type", "ON")]), &status).unwrap();
+    assert_eq!(stats.hits, 300);
+    assert_eq!(stats.prunes, 5);
+    assert_eq!(stats.hit_ratio, 0.75);
+
+    // MySQL 5.7 with the cache disabled
+    assert!(QueryCacheStats::from_vars(&vars(&[("query_cache_type", "OFF")]), &status).is_none());
+    // MySQL 8.0 has no query cache variables at all
+    assert!(QueryCacheStats::from_vars(&vars(&[]), &vars(&[])).is_none());
+  }
+
+  #[test]
+  fn test_missing_features() {
+    let prereqs = MonitoringPrereqs::from_vars(
+      &vars(&[
+        ("performance_schema", "ON"),
+        ("slow_query_log", "OFF"),
+        ("general_log", "OFF"),
+        ("log_bin", "1"),
+      ]),
+      false,
+    );
+    assert!(prereqs.performance_schema_enabled);
+    assert!(prereqs.binlog_enabled);
+    assert_eq!(prereqs.gtid_mode, "OFF");
+    assert_eq!(
+      prereqs.missing_features(),
+      ["slow_query_log", "general_log", "gtid_mode", "audit plugin"]
+    );
+  }
+
+  #[test]
+  fn test_storage_engines() {
+    let row = |engine: &str, support: &str, caps: Option<[&str; 3]>| -> EngineRow {
+      let cap = |i: usize| caps.map(|c| c[i].to_string());
+      (
+        engine.to_string(),
+        support.to_string(),
+        Some(String::new()),
+        cap(0),
+        cap(1),
+        cap(2),
+      )
+    };
+    let engines: Vec<StorageEngine> = vec![
+      row("MEMORY", "YES", Some(["NO", "NO", "NO"])),
+      row("InnoDB", "DEFAULT", Some(["YES", "YES", "YES"])),
+      row("FEDERATED", "NO", None),
+      row("ARCHIVE", "DISABLED", Some(["NO", "NO", "NO"])),
+    ]
+    .into_iter()
+    .map(StorageEngine::from_row)
+    .collect();
+
+    let default = default_engine(&engines).unwrap();
+    assert_eq!(default.engine, "InnoDB");
+    assert!(default.transactions && default.xa && default.savepoints);
+    assert_eq!(engines[2].support, EngineSupport::No);
+    assert!(!engines[2].transactions);
+    assert_eq!(engines[3].support, EngineSupport::Disabled);
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_server_charset() {
+    let conn = crate::dialect::mysql::local_connection();
+    let (charset, collation) = conn.server_charset().await.unwrap();
+    assert!(!charset.is_empty());
+    assert!(collation.starts_with(&charset));
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a MySQL server logging slow queries to a table"]
+  async fn test_slow_queries() {
+    use arrow::array::AsArray;
+    use arrow::datatypes::Float64Type;
+
+    let conn = crate::dialect::mysql::local_connection();
+    let mut c = conn.get_conn().unwrap();
+    c.query_drop("set session long_query_time = 0").unwrap();
+    c.query_drop("select sleep(0.2)").unwrap();
+
+    let res = conn.slow_queries(10).await.unwrap();
+    assert!(res.batch.num_rows() > 0);
+    let times = res.batch.column(2).as_primitive::<Float64Type>();
+    let times: Vec<f64> = times.iter().flatten().collect();
+    assert!(times.windows(2).all(|w| w[0] >= w[1]));
+    assert!(times[0] >= 0.2);
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_list_srs() {
+    use arrow::array::AsArray;
+    use arrow::datatypes::UInt64Type;
+
+    let conn = crate::dialect::mysql::local_connection();
+    let res = conn.list_srs().await.unwrap();
+    // SRS_ID is INT UNSIGNED
+    let ids = res.batch.column(1).as_primitive::<UInt64Type>();
+    assert!(ids.iter().flatten().any(|id| id == 4326));
+  }
+}
@@ -0,0 +1,119 @@
+use mysql::prelude::*;
+use mysql::{PooledConn, Value};
+
+use super::MySqlConnection;
+use crate::utils::{ExecResult, RawArrowData};
+
+/// A connection held across calls, so session state such as temporary
+/// tables and user variables survives between queries. The connection is
+/// released when the session is dropped.
+pub struct Session<'a> {
+  owner: &'a MySqlConnection,
+  conn: PooledConn,
+}
+
+/// Unquoted user variable names: letters, digits, `.`, `_` and `$`.
+fn check_user_var(name: &str) -> anyhow::Result<&str> {
+  let name = name.strip_prefix('@').unwrap_or(name);
+  let valid = !name.is_empty()
+    && name
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '$'));
+  if !valid {
+    anyhow::bail!("invalid user variable name: {name}");
+  }
+  Ok(name)
+}
+
+impl Session<'_> {
+  pub fn query(&mut self, sql: &str) -> anyhow::Result<RawArrowData> {
+    self.owner.query_on(&mut self.conn, sql)
+  }
+
+  /// Run a statement without a result set, like `Connection::execute`.
+  pub fn execute(&mut self, sql: &str) -> anyhow::Result<ExecResult> {
+    self.conn.query_drop(self.owner.tagged(sql))?;
+    Ok(ExecResult {
+      affected_rows: self.conn.affected_rows(),
+      last_insert_id: self.conn.last_insert_id(),
+    })
+  }
+
+  /// Value of the user variable `@name`; `None` if it was never set or is
+  /// `NULL`.
+  pub async fn get_user_var(&mut self, name: &str) -> anyhow::Result<Option<Value>> {
+    let name = check_user_var(name)?;
+    let value: Option<Value> = self.conn.query_first(format!("SELECT @{name}"))?;
+    Ok(value.filter(|v| *v != Value::NULL))
+  }
+
+  /// Set the user variable `@name` for the rest of the session.
+  pub async fn set_user_var(&mut self, name: &str, value: impl Into<Value>) -> anyhow::Result<()> {
+    let name = check_user_var(name)?;
+    self
+      .conn
+      .exec_drop(format!("SET @{name} = ?"), (value.into(),))?;
+    Ok(())
+  }
+}
+
+impl MySqlConnection {
+  /// Check out one connection for a sequence of queries.
+  pub fn begin_session(&self) -> anyhow::Result<Session<'_>> {
+    Ok(Session {
+      owner: self,
+      conn: self.get_conn()?,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dialect::mysql::local_connection;
+
+  #[test]
+  fn test_check_user_var() {
+    assert_eq!(check_user_var("@batch_id").unwrap(), "batch_id");
+    assert_eq!(check_user_var("v1.$x").unwrap(), "v1.$x");
+    assert!(check_user_var("@").is_err());
+    assert!(check_user_var("x; drop table t").is_err());
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_session_user_var() {
+    let conn = local_connection();
+    let mut session = conn.begin_session().unwrap();
+    assert_eq!(session.get_user_var("duckling_v").await.unwrap(), None);
+    session.set_user_var("@duckling_v", 42).await.unwrap();
+    let value = session.get_user_var("duckling_v").await.unwrap();
+    assert_eq!(value.map(mysql::from_value::<i64>), Some(42));
+    session.set_user_var("duckling_v", "text").await.unwrap();
+    let value = session.get_user_var("@duckling_v").await.unwrap();
+    assert_eq!(
+      value.map(mysql::from_value::<String>).as_deref(),
+      Some("text")
+    );
+  }
+
+  #[test]
+  #[ignore = "requires a running MySQL server"]
+  fn test_session_temporary_table() {
+    let conn = local_connection();
+    let mut session = conn.begin_session().unwrap();
+    session
+      .execute("create temporary table tmp_session (id int auto_increment primary key)")
+      .unwrap();
+    let res = session
+      .execute("insert into tmp_session values (null), (null)")
+      .unwrap();
+    assert_eq!(res.affected_rows, 2);
+    assert_eq!(res.last_insert_id, 1);
+    let res = session.query("select * from tmp_session").unwrap();
+    assert_eq!(res.total, 2);
+    drop(session);
+
+    assert!(conn._query("select * from tmp_session").is_err());
+  }
+}
@@ -0,0 +1,269 @@
+use std::net::UdpSocket;
+use std::time::Duration;
+
+use super::MySqlConnection;
+
+/// One target of a DNS SRV record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrvTarget {
+  pub priority: u16,
+  pub weight: u16,
+  pub port: u16,
+  pub host: String,
+}
+
+/// Looks up the SRV records of a service name such as
+/// `_mysql._tcp.db.example.svc.cluster.local`.
+pub trait SrvResolver {
+  fn resolve_srv(&self, service: &str) -> anyhow::Result<Vec<SrvTarget>>;
+}
+
+/// Queries the `nameserver`s in `/etc/resolv.conf` over UDP, in order, until
+/// one answers. `search` and `ndots` are not applied, so `service` must be
+/// fully qualified.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemResolver;
+
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+const DNS_TIMEOUT: Duration = Duration::from_secs(5);
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+
+impl SrvResolver for SystemResolver {
+  fn resolve_srv(&self, service: &str) -> anyhow::Result<Vec<SrvTarget>> {
+    let conf = std::fs::read_to_string(RESOLV_CONF)?;
+    let query = srv_query(0, service)?;
+    let mut last_err = anyhow::anyhow!("no nameserver in {RESOLV_CONF}");
+    for nameserver in nameservers(&conf) {
+      match query_nameserver(nameserver, &query) {
+        Ok(targets) => return Ok(targets),
+        Err(e) => last_err = e.context(format!("nameserver {nameserver}")),
+      }
+    }
+    Err(last_err)
+  }
+}
+
+/// The `nameserver` addresses of a `resolv.conf`, in order.
+fn nameservers(conf: &str) -> impl Iterator<Item = &str> {
+  conf.lines().filter_map(|line| {
+    let mut parts = line.split_whitespace();
+    (parts.next() == Some("nameserver")).then(|| parts.next())?
+  })
+}
+
+/// Send `query`, with a fresh id, to `nameserver` and parse the answer.
+fn query_nameserver(nameserver: &str, query: &[u8]) -> anyhow::Result<Vec<SrvTarget>> {
+  let ip: std::net::IpAddr = nameserver.parse()?;
+  let bind = if ip.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+  let socket = UdpSocket::bind(bind)?;
+  socket.set_read_timeout(Some(DNS_TIMEOUT))?;
+  socket.connect((ip, 53))?;
+
+  let id = rand::random::<u16>();
+  let mut packet = query.to_vec();
+  packet[..2].copy_from_slice(&id.to_be_bytes());
+  socket.send(&packet)?;
+  let mut buf = [0u8; 4096];
+  let n = socket.recv(&mut buf)?;
+  parse_srv_response(id, &buf[..n])
+}
+
+/// A recursive SRV query for `name`.
+fn srv_query(id: u16, name: &str) -> anyhow::Result<Vec<u8>> {
+  let mut packet = Vec::with_capacity(name.len() + 18);
+  packet.extend_from_slice(&id.to_be_bytes());
+  // recursion desired, one question
+  packet.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+  for label in name.trim_end_matches('.').split('.') {
+    if label.is_empty() || label.len() > 63 {
+      anyhow::bail!("invalid DNS name: {name}");
+    }
+    packet.push(label.len() as u8);
+    packet.extend_from_slice(label.as_bytes());
+  }
+  packet.push(0);
+  packet.extend_from_slice(&TYPE_SRV.to_be_bytes());
+  packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+  Ok(packet)
+}
+
+fn read_u16(buf: &[u8], pos: usize) -> anyhow::Result<u16> {
+  match buf.get(pos..pos + 2) {
+    Some(b) => Ok(u16::from_be_bytes([b[0], b[1]])),
+    None => anyhow::bail!("truncated DNS response"),
+  }
+}
+
+/// Read a possibly compressed name at `pos`; returns it with the position
+/// just past it.
+fn read_name(buf: &[u8], mut pos: usize) -> anyhow::Result<(String, usize)> {
+  let mut labels = vec![];
+  let mut end = None;
+  // each pointer must go backwards, which bounds the loop
+  let mut limit = pos;
+  loop {
+    let Some(&len) = buf.get(pos) else {
+      anyhow::bail!("truncated DNS response");
+    };
+    match len {
+      0 => break,
+      l if l & 0xc0 == 0xc0 => {
+        let target = (read_u16(buf, pos)? & 0x3fff) as usize;
+        if target >= limit {
+          anyhow::bail!("invalid DNS name pointer");
+        }
+        end.get_or_insert(pos + 2);
+        limit = target;
+        pos = target;
+      }
+      l => {
+        let label = buf
+          .get(pos + 1..pos + 1 + l as usize)
+          .ok_or_else(|| anyhow::anyhow!("truncated DNS response"))?;
+        labels.push(String::from_utf8_lossy(label).into_owned());
+        pos += 1 + l as usize;
+      }
+    }
+  }
+  Ok((labels.join("."), end.unwrap_or(pos + 1)))
+}
+
+/// SRV answers of the response to query `id`.
+fn parse_srv_response(id: u16, buf: &[u8]) -> anyhow::Result<Vec<SrvTarget>> {
+  if read_u16(buf, 0)? != id {
+    anyhow::bail!("DNS response does not match the query");
+  }
+  let flags = read_u16(buf, 2)?;
+  // the answers would need a retry over TCP, which is not implemented
+  if flags & 0x0200 != 0 {
+    anyhow::bail!("DNS response truncated");
+  }
+  let rcode = flags & 0x000f;
+  if rcode != 0 {
+    anyhow::bail!("DNS query failed with rcode {rcode}");
+  }
+  let questions = read_u16(buf, 4)?;
+  let answers = read_u16(buf, 6)?;
+  let mut pos = 12;
+  for _ in 0..questions {
+    pos = read_name(buf, pos)?.1 + 4;
+  }
+  let mut targets = vec![];
+  for _ in 0..answers {
+    pos = read_name(buf, pos)?.1;
+    let rtype = read_u16(buf, pos)?;
+    let rdlen = read_u16(buf, pos + 8)? as usize;
+    let rdata = pos + 10;
+    if rtype == TYPE_SRV {
+      targets.push(SrvTarget {
+        priority: read_u16(buf, rdata)?,
+        weight: read_u16(buf, rdata + 2)?,
+        port: read_u16(buf, rdata + 4)?,
+        host: read_name(buf, rdata + 6)?.0,
+      });
+    }
+    pos = rdata + rdlen;
+  }
+  Ok(targets)
+}
+
+/// The target to connect to: lowest priority value first, then the highest
+/// weight. A lone `.` target means the service is not available.
+fn pick_target(targets: Vec<SrvTarget>) -> Option<SrvTarget> {
+  targets
+    .into_iter()
+    .filter(|t| !t.host.is_empty() && t.host != ".")
+    .min_by_key(|t| (t.priority, std::cmp::Reverse(t.weight)))
+}
+
+impl MySqlConnection {
+  /// A connection to the target of the DNS SRV record `service`, e.g.
+  /// `_mysql._tcp.orders.default.svc.cluster.local`. Credentials and the
+  /// database are left empty to be filled in by the caller.
+  pub fn from_srv(service: &str) -> anyhow::Result<Self> {
+    Self::from_srv_with(service, &SystemResolver)
+  }
+
+  /// `from_srv` with the given resolver.
+  pub fn from_srv_with(service: &str, resolver: &dyn SrvResolver) -> anyhow::Result<Self> {
+    let Some(target) = pick_target(resolver.resolve_srv(service)?) else {
+      anyhow::bail!("no SRV record resolves for {service}");
+    };
+    Ok(Self {
+      host: target.host.trim_end_matches('.').to_string(),
+      port: target.port.to_string(),
+      ..Default::default()
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct MockResolver(Vec<SrvTarget>);
+
+  impl SrvResolver for MockResolver {
+    fn resolve_srv(&self, _service: &str) -> anyhow::Result<Vec<SrvTarget>> {
+      Ok(self.0.clone())
+    }
+  }
+
+  fn target(priority: u16, weight: u16, port: u16, host: &str) -> SrvTarget {
+    SrvTarget {
+      priority,
+      weight,
+      port,
+      host: host.to_string(),
+    }
+  }
+
+  #[test]
+  fn test_from_srv_with() {
+    let resolver = MockResolver(vec![
+      target(20, 100, 3306, "replica.db.local."),
+      target(10, 5, 3307, "standby.db.local."),
+      target(10, 50, 3308, "primary.db.local."),
+    ]);
+    let conn = MySqlConnection::from_srv_with("_mysql._tcp.db.local", &resolver).unwrap();
+    assert_eq!(conn.host, "primary.db.local");
+    assert_eq!(conn.port, "3308");
+
+    let empty = MockResolver(vec![target(0, 0, 0, ".")]);
+    let err = MySqlConnection::from_srv_with("_mysql._tcp.db.local", &empty).unwrap_err();
+    assert!(err.to_string().contains("no SRV record"));
+  }
+
+  #[test]
+  fn test_parse_srv_response() {
+    let mut packet = srv_query(0x1234, "_mysql._tcp.db").unwrap();
+    // response, no error, one answer
+    packet[2..8].copy_from_slice(&[0x81, 0x80, 0, 1, 0, 1]);
+    // name: pointer to the question at offset 12
+    packet.extend_from_slice(&[0xc0, 12, 0, 33, 0, 1, 0, 0, 0, 60]);
+    // target `my` followed by a pointer to `db` in the question
+    let host = [2, b'm', b'y', 0xc0, 24];
+    packet.extend_from_slice(&(6 + host.len() as u16).to_be_bytes());
+    packet.extend_from_slice(&[0, 10, 0, 5, 0x0c, 0xea]);
+    packet.extend_from_slice(&host);
+
+    let targets = parse_srv_response(0x1234, &packet).unwrap();
+    assert_eq!(targets, [target(10, 5, 3306, "my.db")]);
+    assert!(parse_srv_response(0x4321, &packet).is_err());
+    assert!(parse_srv_response(0x1234, &packet[..packet.len() - 3]).is_err());
+
+    // the TC bit set
+    packet[2] |= 0x02;
+    let err = parse_srv_response(0x1234, &packet).unwrap_err();
+    assert!(err.to_string().contains("truncated"));
+  }
+
+  #[test]
+  fn test_nameservers() {
+    let conf = "# generated\nsearch svc.cluster.local\nnameserver 10.0.0.10\n\
+                options ndots:5\nnameserver ::1\n";
+    assert_eq!(nameservers(conf).collect::<Vec<_>>(), ["10.0.0.10", "::1"]);
+    assert_eq!(nameservers("").count(), 0);
+  }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use mysql::prelude::*;
+
+use super::{MySqlConnection, escape_literal};
+
+/// Server status or system variables keyed by name.
+pub type VarMap = HashMap<String, String>;
+
+/// Look up the first present name, e.g. both the `master` and `source`
+/// spellings that MySQL 8.0.26 introduced.
+pub(crate) fn var_str<'a>(vars: &'a VarMap, names: &[&str]) -> Option<&'a str> {
+  names
+    .iter()
+    .find_map(|name| vars.get(*name))
+    .map(String::as_str)
+}
+
+pub(crate) fn var_u64(vars: &VarMap, names: &[&str]) -> u64 {
+  var_str(vars, names)
+    .and_then(|v| v.trim().parse().ok())
+    .unwrap_or_default()
+}
+
+pub(crate) fn var_f64(vars: &VarMap, names: &[&str]) -> f64 {
+  var_str(vars, names)
+    .and_then(|v| v.trim().parse().ok())
+    .unwrap_or_default()
+}
+
+/// `ON`/`1`/`YES` style flags.
+pub(crate) fn var_bool(vars: &VarMap, names: &[&str]) -> bool {
+  var_str(vars, names).is_some_and(|v| {
+    matches!(
+      v.trim().to_ascii_uppercase().as_str(),
+      "ON" | "1" | "YES" | "TRUE"
+    )
+  })
+}
+
+#[cfg(test)]
+pub(crate) fn var_map(pairs: &[(&str, &str)]) -> VarMap {
+  pairs
+    .iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect()
+}
+
+impl MySqlConnection {
+  /// `SHOW GLOBAL STATUS LIKE pattern`
+  pub fn global_status(&self, pattern: &str) -> anyhow::Result<VarMap> {
+    let sql = format!("SHOW GLOBAL STATUS LIKE '{}'", escape_literal(pattern));
+    let mut conn = self.get_conn()?;
+    Ok(
+      conn
+        .query::<(String, String), _>(sql)?
+        .into_iter()
+        .collect(),
+    )
+  }
+
+  /// `SHOW GLOBAL VARIABLES LIKE pattern`
+  pub fn global_variables(&self, pattern: &str) -> anyhow::Result<VarMap> {
+    let sql = format!("SHOW GLOBAL VARIABLES LIKE '{}'", escape_literal(pattern));
+    let mut conn = self.get_conn()?;
+    Ok(
+      conn
+        .query::<(String, String), _>(sql)?
+        .into_iter()
+        .collect(),
+    )
+  }
+
+  /// `SHOW GLOBAL VARIABLES` restricted to the given names; missing
+  /// variables are simply absent from the map.
+  pub fn global_variables_in(&self, names: &[&str]) -> anyhow::Result<VarMap> {
+    let names: Vec<String> = names
+      .iter()
+      .map(|n| format!("'{}'", escape_literal(n)))
+      .collect();
+    let sql = format!(
+      "SHOW GLOBAL VARIABLES WHERE Variable_name IN ({})",
+      names.join(", ")
+    );
+    let mut conn = self.get_conn()?;
+    Ok(
+      conn
+        .query::<(String, String), _>(sql)?
+        .into_iter()
+        .collect(),
+    )
+  }
+}
@@ -0,0 +1,1044 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use mysql::prelude::*;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+use super::profile::is_numeric_type;
+use super::status::{var_bool, var_str};
+use super::{MySqlConnection, escape_literal, quote_ident, quote_table};
+use crate::utils::RawArrowData;
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartitionPruningResult {
+  pub partitions_accessed: Vec<String>,
+  pub partitions_skipped: Vec<String>,
+  pub total_partitions: usize,
+}
+
+impl PartitionPruningResult {
+  /// Build the result from the `partitions` column of each EXPLAIN row and the
+  /// full partition list of the table.
+  pub fn from_explain(explain_partitions: &[Option<String>], all_partitions: &[String]) -> Self {
+    let mut accessed: Vec<String> = vec![];
+    for name in explain_partitions
+      .iter()
+      .flatten()
+      .flat_map(|s| s.split(','))
+      .map(str::trim)
+      .filter(|s| !s.is_empty())
+    {
+      if !accessed.iter().any(|a| a == name) {
+        accessed.push(name.to_string());
+      }
+    }
+    let skipped = all_partitions
+      .iter()
+      .filter(|p| !accessed.contains(p))
+      .cloned()
+      .collect();
+    Self {
+      partitions_accessed: accessed,
+      partitions_skipped: skipped,
+      total_partitions: all_partitions.len(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaintenanceOp {
+  Optimize,
+  Analyze,
+  Check,
+  Repair,
+}
+
+impl MaintenanceOp {
+  pub fn sql(&self, schema: Option<&str>, table: &str) -> String {
+    let op = match self {
+      MaintenanceOp::Optimize => "OPTIMIZE",
+      MaintenanceOp::Analyze => "ANALYZE",
+      MaintenanceOp::Check => "CHECK",
+      MaintenanceOp::Repair => "REPAIR",
+    };
+    format!("{op} TABLE {}", quote_table(schema, table))
+  }
+}
+
+/// Copy rate assumed for table rebuilds when estimating ALTER duration.
+const ASSUMED_ALTER_THROUGHPUT_BYTES_PER_SEC: f64 = 50.0 * 1024.0 * 1024.0;
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlterEstimate {
+  pub table_rows: u64,
+  pub table_size_bytes: u64,
+  pub estimated_seconds: f64,
+  pub requires_rebuild: bool,
+  pub instant_compatible: bool,
+}
+
+/// How MySQL 8.0 can execute one ALTER TABLE clause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum AlterKind {
+  /// Metadata only (`ALGORITHM=INSTANT`).
+  Instant,
+  /// In place without copying rows, e.g. building a secondary index.
+  InPlace,
+  /// The table is rebuilt.
+  Rebuild,
+}
+
+/// Split an ALTER clause list on top-level commas.
+fn split_alter_clauses(alter: &str) -> Vec<String> {
+  let mut clauses = vec![];
+  let mut current = String::new();
+  let mut depth = 0;
+  let mut quote = None;
+  for c in alter.chars() {
+    match (quote, c) {
+      (Some(q), c) if c == q => quote = None,
+      (Some(_), _) => {}
+      (None, '\'' | '"' | '`') => quote = Some(c),
+      (None, '(') => depth += 1,
+      (None, ')') => depth -= 1,
+      (None, ',') if depth == 0 => {
+        clauses.push(std::mem::take(&mut current));
+        continue;
+      }
+      _ => {}
+    }
+    current.push(c);
+  }
+  clauses.push(current);
+  clauses
+    .into_iter()
+    .map(|c| c.trim().to_string())
+    .filter(|c| !c.is_empty())
+    .collect()
+}
+
+/// Classify a single clause following the MySQL 8.0 online DDL matrix.
+/// `virtual_columns` are the table's virtual generated columns, which can be
+/// dropped instantly.
+fn classify_alter_clause(clause: &str, virtual_columns: &[String]) -> AlterKind {
+  let upper = clause.to_ascii_uppercase();
+  let words: Vec<&str> = upper.split_whitespace().collect();
+  let starts = |prefix: &[&str]| words.starts_with(prefix);
+
+  if starts(&["ADD", "COLUMN"]) || (starts(&["ADD"]) && !is_index_keyword(words.get(1))) {
+    if upper.contains(" VIRTUAL") {
+      return AlterKind::Instant;
+    }
+    if upper.contains("NOT NULL") || upper.contains(" DEFAULT ") {
+      return AlterKind::Rebuild;
+    }
+    return AlterKind::Instant;
+  }
+  if starts(&["DROP", "COLUMN"]) || (starts(&["DROP"]) && !is_index_keyword(words.get(1))) {
+    let name = clause
+      .split_whitespace()
+      .nth(if starts(&["DROP", "COLUMN"]) { 2 } else { 1 })
+      .unwrap_or_default()
+      .trim_matches('`');
+    return if virtual_columns.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+      AlterKind::Instant
+    } else {
+      AlterKind::Rebuild
+    };
+  }
+  if starts(&["RENAME"])
+    || (starts(&["ALTER"]) && (upper.contains("SET DEFAULT") || upper.contains("DROP DEFAULT")))
+  {
+    return AlterKind::Instant;
+  }
+  if starts(&["ADD", "PRIMARY"]) || starts(&["DROP", "PRIMARY"]) {
+    return AlterKind::Rebuild;
+  }
+  if starts(&["ADD"]) || starts(&["DROP"]) {
+    // secondary, unique, fulltext and spatial indexes
+    return AlterKind::InPlace;
+  }
+  AlterKind::Rebuild
+}
+
+fn is_index_keyword(word: Option<&&str>) -> bool {
+  matches!(
+    word.copied(),
+    Some(
+      "INDEX"
+        | "KEY"
+        | "UNIQUE"
+        | "FULLTEXT"
+        | "SPATIAL"
+        | "PRIMARY"
+        | "CONSTRAINT"
+        | "FOREIGN"
+        | "CHECK"
+        | "PARTITION"
+    )
+  )
+}
+
+impl AlterEstimate {
+  pub(crate) fn new(
+    table_rows: u64,
+    table_size_bytes: u64,
+    alter_clause: &str,
+    virtual_columns: &[String],
+  ) -> Self {
+    let kind = split_alter_clauses(alter_clause)
+      .iter()
+      .map(|c| classify_alter_clause(c, virtual_columns))
+      .max()
+      .unwrap_or(AlterKind::Instant);
+    Self {
+      table_rows,
+      table_size_bytes,
+      estimated_seconds: match kind {
+        AlterKind::Instant => 0.0,
+        _ => table_size_bytes as f64 / ASSUMED_ALTER_THROUGHPUT_BYTES_PER_SEC,
+      },
+      requires_rebuild: kind == AlterKind::Rebuild,
+      instant_compatible: kind == AlterKind::Instant,
+    }
+  }
+}
+
+/// Run `task` for every item with at most `concurrency` running at once,
+/// recording which items succeeded. Failures are logged, not propagated.
+async fn run_bounded<F, Fut>(
+  items: Vec<String>,
+  concurrency: usize,
+  task: F,
+) -> HashMap<String, bool>
+where
+  F: Fn(String) -> Fut,
+  Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+  let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+  let mut handles = vec![];
+  for item in items {
+    let permit = semaphore
+      .clone()
+      .acquire_owned()
+      .await
+      .expect("semaphore is never closed");
+    let fut = task(item.clone());
+    let handle = tokio::spawn(async move {
+      let _permit = permit;
+      fut.await
+    });
+    handles.push((item, handle));
+  }
+
+  let mut results = HashMap::new();
+  for (item, handle) in handles {
+    let ok = match handle.await {
+      Ok(Ok(())) => true,
+      Ok(Err(err)) => {
+        log::warn!("{item}: {err}");
+        false
+      }
+      Err(err) => {
+        log::warn!("{item}: task failed: {err}");
+        false
+      }
+    };
+    results.insert(item, ok);
+  }
+  results
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OscCompatibility {
+  pub has_primary_key: bool,
+  pub has_triggers: bool,
+  pub has_foreign_keys: bool,
+  pub replication_enabled: bool,
+  pub row_based_replication: bool,
+  pub compatible_with_pt_osc: bool,
+  pub compatible_with_gh_ost: bool,
+}
+
+impl OscCompatibility {
+  /// pt-online-schema-change installs its own triggers, so the table must
+  /// have none; gh-ost tails the binlog, so it needs row-based replication
+  /// and cannot handle foreign keys or triggers. Both need a primary key to
+  /// chunk the copy.
+  pub fn new(
+    has_primary_key: bool,
+    has_triggers: bool,
+    has_foreign_keys: bool,
+    replication_enabled: bool,
+    row_based_replication: bool,
+  ) -> Self {
+    Self {
+      has_primary_key,
+      has_triggers,
+      has_foreign_keys,
+      replication_enabled,
+      row_based_replication,
+      compatible_with_pt_osc: has_primary_key && !has_triggers,
+      compatible_with_gh_ost: has_primary_key
+        && !has_triggers
+        && !has_foreign_keys
+        && row_based_replication,
+    }
+  }
+}
+
+/// `DATA_FREE / (DATA_LENGTH + INDEX_LENGTH)`, clamped to `0..=1` since
+/// tables in a shared tablespace report the free space of the whole file.
+pub(crate) fn fragmentation_ratio(data_free: u64, data_length: u64, index_length: u64) -> f64 {
+  let used = data_length + index_length;
+  if used == 0 {
+    return 0.0;
+  }
+  (data_free as f64 / used as f64).min(1.0)
+}
+
+impl MySqlConnection {
+  /// List partition names of a table as EXPLAIN reports them
+  /// (`p0`, or `p0_p0sp0` for subpartitions).
+  pub fn table_partitions(&self, schema: &str, table: &str) -> anyhow::Result<Vec<String>> {
+    let mut conn = self.get_conn()?;
+    let sql = r"
+    select PARTITION_NAME, SUBPARTITION_NAME
+    from information_schema.partitions
+    where TABLE_SCHEMA = ? and TABLE_NAME = ? and PARTITION_NAME is not null
+    order by PARTITION_ORDINAL_POSITION, SUBPARTITION_ORDINAL_POSITION
+    ";
+    let rows: Vec<(String, Option<String>)> = conn.exec(sql, (schema, table))?;
+    Ok(
+      rows
+        .into_iter()
+        .map(|(p, sp)| match sp {
+          Some(sp) => format!("{p}_{sp}"),
+          None => p,
+        })
+        .collect(),
+    )
+  }
+
+  /// Check which partitions the optimizer would read for `where_clause`.
+  ///
+  /// Since MySQL 5.7 plain `EXPLAIN` always reports the `partitions` column
+  /// (`EXPLAIN PARTITIONS` was removed in 8.0), so plain `EXPLAIN` is used.
+  pub fn check_partition_pruning(
+    &self,
+    schema: &str,
+    table: &str,
+    where_clause: &str,
+  ) -> anyhow::Result<PartitionPruningResult> {
+    let all = self.table_partitions(schema, table)?;
+    let mut sql = format!("EXPLAIN SELECT * FROM {}", quote_table(Some(schema), table));
+    if !where_clause.trim().is_empty() {
+      sql = format!("{sql} WHERE {where_clause}");
+    }
+    let mut conn = self.get_conn()?;
+    let partitions: Vec<Option<String>> = conn
+      .query_iter(&sql)?
+      .flatten()
+      .map(|row| row.get::<Option<String>, _>("partitions").flatten())
+      .collect();
+    Ok(PartitionPruningResult::from_explain(&partitions, &all))
+  }
+
+  /// Last modification time from `information_schema.tables.UPDATE_TIME`,
+  /// `None` when the engine does not track it.
+  pub async fn table_freshness(
+    &self,
+    schema: &str,
+    table: &str,
+  ) -> anyhow::Result<Option<DateTime<Utc>>> {
+    Ok(self.table_timestamps(schema, table).await?.1)
+  }
+
+  /// `(CREATE_TIME, UPDATE_TIME)` from `information_schema.tables`, each
+  /// `None` when the engine does not track it.
+  pub async fn table_timestamps(
+    &self,
+    schema: &str,
+    table: &str,
+  ) -> anyhow::Result<(Option<DateTime<Utc>>, Option<DateTime<Utc>>)> {
+    let mut conn = self.get_conn()?;
+    let sql = r"
+    select cast(unix_timestamp(CREATE_TIME) as signed),
+      cast(unix_timestamp(UPDATE_TIME) as signed)
+    from information_schema.tables
+    where TABLE_SCHEMA = ? and TABLE_NAME = ?
+    ";
+    let ts: Option<(Option<i64>, Option<i64>)> = conn.exec_first(sql, (schema, table))?;
+    let (created, updated) = ts.unwrap_or_default();
+    let at = |ts: Option<i64>| ts.and_then(|ts| DateTime::from_timestamp(ts, 0));
+    Ok((at(created), at(updated)))
+  }
+
+  /// Optimizer histogram stored by `ANALYZE TABLE ... UPDATE HISTOGRAM`
+  /// (buckets, histogram type, sampling rate), without scanning the data.
+  pub async fn column_histogram(
+    &self,
+    schema: &str,
+    table: &str,
+    column: &str,
+  ) -> anyhow::Result<Option<serde_json::Value>> {
+    let mut conn = self.get_conn()?;
+    let sql = r"
+    select HISTOGRAM from information_schema.column_statistics
+    where SCHEMA_NAME = ? and TABLE_NAME = ? and COLUMN_NAME = ?
+    ";
+    let histogram: Option<String> = conn.exec_first(sql, (schema, table, column))?;
+    Ok(histogram.map(|h| serde_json::from_str(&h)).transpose()?)
+  }
+
+  /// Rough cost of `ALTER TABLE <table> <alter_clause>` from the table size
+  /// and a lookup of which operations MySQL 8.0 runs instantly, in place or
+  /// by rebuilding the table.
+  pub fn estimate_alter_duration(
+    &self,
+    schema: &str,
+    table: &str,
+    alter_clause: &str,
+  ) -> anyhow::Result<AlterEstimate> {
+    let mut conn = self.get_conn()?;
+    let size: Option<(Option<u64>, Option<u64>)> = conn.exec_first(
+      r"
+      select TABLE_ROWS, DATA_LENGTH + INDEX_LENGTH from information_schema.tables
+      where TABLE_SCHEMA = ? and TABLE_NAME = ?
+      ",
+      (schema, table),
+    )?;
+    let Some((rows, bytes)) = size else {
+      anyhow::bail!("table {schema}.{table} not found");
+    };
+    let virtual_columns: Vec<String> = conn.exec(
+      r"
+      select COLUMN_NAME from information_schema.columns
+      where TABLE_SCHEMA = ? and TABLE_NAME = ? and EXTRA like '%VIRTUAL GENERATED%'
+      ",
+      (schema, table),
+    )?;
+    Ok(AlterEstimate::new(
+      rows.unwrap_or_default(),
+      bytes.unwrap_or_default(),
+      alter_clause,
+      &virtual_columns,
+    ))
+  }
+
+  /// Check whether pt-online-schema-change or gh-ost can alter the table.
+  pub fn osc_compatibility_check(
+    &self,
+    schema: &str,
+    table: &str,
+  ) -> anyhow::Result<OscCompatibility> {
+    let mut conn = self.get_conn()?;
+    let has_primary_key: Option<bool> = conn.exec_first(
+      r"
+      select count(*) > 0 from information_schema.table_constraints
+      where TABLE_SCHEMA = ? and TABLE_NAME = ? and CONSTRAINT_TYPE = 'PRIMARY KEY'
+      ",
+      (schema, table),
+    )?;
+    let has_triggers: Option<bool> = conn.exec_first(
+      r"
+      select count(*) > 0 from information_schema.triggers
+      where EVENT_OBJECT_SCHEMA = ? and EVENT_OBJECT_TABLE = ?
+      ",
+      (schema, table),
+    )?;
+    // foreign keys both from and to the table
+    let has_foreign_keys: Option<bool> = conn.exec_first(
+      r"
+      select count(*) > 0 from information_schema.referential_constraints
+      where (CONSTRAINT_SCHEMA = ? and TABLE_NAME = ?)
+        or (UNIQUE_CONSTRAINT_SCHEMA = ? and REFERENCED_TABLE_NAME = ?)
+      ",
+      (schema, table, schema, table),
+    )?;
+    let mut vars = self.global_variables("log_bin")?;
+    vars.extend(self.global_variables("binlog_format")?);
+    let binlog_format = var_str(&vars, &["binlog_format"]).unwrap_or_default();
+    Ok(OscCompatibility::new(
+      has_primary_key.unwrap_or_default(),
+      has_triggers.unwrap_or_default(),
+      has_foreign_keys.unwrap_or_default(),
+      var_bool(&vars, &["log_bin"]),
+      binlog_format.eq_ignore_ascii_case("ROW"),
+    ))
+  }
+
+  /// Fraction of allocated space that is free; a high value suggests
+  /// running `OPTIMIZE TABLE`.
+  pub async fn table_fragmentation(&self, schema: &str, table: &str) -> anyhow::Result<f64> {
+    let mut conn = self.get_conn()?;
+    let sql = r"
+    select ifnull(DATA_FREE, 0), ifnull(DATA_LENGTH, 0), ifnull(INDEX_LENGTH, 0)
+    from information_schema.tables
+    where TABLE_SCHEMA = ? and TABLE_NAME = ?
+    ";
+    let (free, data, index): (u64, u64, u64) = conn
+      .exec_first(sql, (schema, table))?
+      .ok_or_else(|| anyhow::anyhow!("table {schema}.{table} not found"))?;
+    Ok(fragmentation_ratio(free, data, index))
+  }
+
+  /// Run a maintenance statement, returning MySQL's
+  /// `Table`/`Op`/`Msg_type`/`Msg_text` status rows.
+  pub async fn maintain_table(
+    &self,
+    schema: Option<&str>,
+    table: &str,
+    op: MaintenanceOp,
+  ) -> anyhow::Result<RawArrowData> {
+    self._query(&op.sql(schema, table))
+  }
+
+  /// `(MAX(id_col), COUNT(*))`; a max well above the count points at large
+  /// id gaps. Empty tables give `(0, 0)`.
+  pub async fn id_health(
+    &self,
+    schema: Option<&str>,
+    table: &str,
+    id_col: &str,
+  ) -> anyhow::Result<(u64, u64)> {
+    let mut conn = self.get_conn()?;
+    let data_type: Option<String> = conn.exec_first(
+      r"
+      select DATA_TYPE from information_schema.columns
+      where TABLE_SCHEMA = coalesce(?, database()) and TABLE_NAME = ? and COLUMN_NAME = ?
+      ",
+      (schema, table, id_col),
+    )?;
+    match data_type {
+      None => anyhow::bail!("column {id_col} not found in {table}"),
+      Some(t) if !is_numeric_type(&t) => anyhow::bail!("column {id_col} is {t}, not numeric"),
+      _ => {}
+    }
+    let sql = format!(
+      "select cast(coalesce(max({}), 0) as unsigned), count(*) from {}",
+      quote_ident(id_col),
+      quote_table(schema, table)
+    );
+    let res: Option<(u64, u64)> = conn.query_first(sql)?;
+    Ok(res.unwrap_or_default())
+  }
+
+  /// `ANALYZE TABLE` every table of `schema` over up to `concurrency`
+  /// connections, returning whether each table succeeded. MEMORY tables are
+  /// skipped since ANALYZE does nothing for them.
+  pub async fn refresh_all_statistics(
+    &self,
+    schema: &str,
+    concurrency: usize,
+  ) -> anyhow::Result<HashMap<String, bool>> {
+    let tables: Vec<String> = self.get_conn()?.exec(
+      r"
+      select TABLE_NAME from information_schema.tables
+      where TABLE_SCHEMA = ? and TABLE_TYPE = 'BASE TABLE' and ENGINE <> 'MEMORY'
+      order by TABLE_NAME
+      ",
+      (schema,),
+    )?;
+    let schema = schema.to_string();
+    let results = run_bounded(tables, concurrency, |table| {
+      let conn = self.clone();
+      let sql = MaintenanceOp::Analyze.sql(Some(&schema), &table);
+      async move {
+        tokio::task::spawn_blocking(move || {
+          // errors come back as result rows rather than a failed statement
+          let rows: Vec<(String, String, String, String)> = conn.get_conn()?.query(sql)?;
+          match rows.iter().find(|r| r.2.eq_ignore_ascii_case("error")) {
+            Some(row) => Err(anyhow::anyhow!("{}", row.3)),
+            None => Ok(()),
+          }
+        })
+        .await?
+      }
+    })
+    .await;
+    Ok(results)
+  }
+
+  /// Row counts of several tables of `schema` without a query per table.
+  ///
+  /// Up to `EXACT_ROW_COUNT_TABLES` tables are counted exactly with a
+  /// `UNION ALL` of `COUNT(*)`; beyond that the `TABLE_ROWS` estimates from
+  /// `information_schema.tables` are returned, which are approximate for
+  /// InnoDB. Tables that do not exist are absent from the map.
+  pub async fn row_counts(
+    &self,
+    schema: &str,
+    tables: &[String],
+  ) -> anyhow::Result<HashMap<String, usize>> {
+    if tables.is_empty() {
+      return Ok(HashMap::new());
+    }
+    let mut conn = self.get_conn()?;
+    let names: Vec<String> = tables
+      .iter()
+      .map(|t| format!("'{}'", escape_literal(t)))
+      .collect();
+    let sql = format!(
+      "select TABLE_NAME, TABLE_ROWS from information_schema.tables \
+       where TABLE_SCHEMA = ? and TABLE_NAME in ({})",
+      names.join(", ")
+    );
+    let mut rows: Vec<(String, Option<u64>)> = conn.exec(sql, (schema,))?;
+    if !rows.is_empty() && rows.len() <= EXACT_ROW_COUNT_TABLES {
+      let existing: Vec<String> = rows.into_iter().map(|(table, _)| table).collect();
+      rows = conn.query(union_count_sql(schema, &existing))?;
+    }
+    Ok(
+      rows
+        .into_iter()
+        .map(|(table, count)| (table, count.unwrap_or_default() as usize))
+        .collect(),
+    )
+  }
+
+  /// The first `limit` rows of only the given columns, rather than
+  /// `SELECT *`. Columns must exist in the table.
+  pub async fn query_columns(
+    &self,
+    schema: Option<&str>,
+    table: &str,
+    columns: &[String],
+    limit: usize,
+  ) -> anyhow::Result<RawArrowData> {
+    if columns.is_empty() {
+      anyhow::bail!("no columns to select");
+    }
+    let mut conn = self.get_conn()?;
+    let existing: Vec<String> = conn.exec(
+      r"
+      select COLUMN_NAME from information_schema.columns
+      where TABLE_SCHEMA = coalesce(?, database()) and TABLE_NAME = ?
+      ",
+      (schema, table),
+    )?;
+    let missing: Vec<&str> = columns
+      .iter()
+      .filter(|c| !existing.iter().any(|e| e.eq_ignore_ascii_case(c)))
+      .map(String::as_str)
+      .collect();
+    if !missing.is_empty() {
+      anyhow::bail!("unknown columns in {table}: {}", missing.join(", "));
+    }
+    let projection: Vec<String> = columns.iter().map(|c| quote_ident(c)).collect();
+    let sql = format!(
+      "SELECT {} FROM {} LIMIT {limit}",
+      projection.join(", "),
+      quote_table(schema, table)
+    );
+    self._query(&sql)
+  }
+
+  /// Value counts of `group_col`, most frequent first.
+  pub async fn group_count(
+    &self,
+    schema: Option<&str>,
+    table: &str,
+    group_col: &str,
+  ) -> anyhow::Result<RawArrowData> {
+    let col = quote_ident(group_col);
+    let sql = format!(
+      "SELECT {col}, COUNT(*) AS `count` FROM {} GROUP BY {col} ORDER BY COUNT(*) DESC",
+      quote_table(schema, table)
+    );
+    self._query(&sql)
+  }
+
+  /// Values of `key_cols` that occur in more than one row, with their
+  /// `count`, most repeated first.
+  pub async fn find_duplicates(
+    &self,
+    schema: Option<&str>,
+    table: &str,
+    key_cols: &[String],
+    limit: usize,
+  ) -> anyhow::Result<RawArrowData> {
+    if key_cols.is_empty() {
+      anyhow::bail!("no key columns to group by");
+    }
+    self._query(&duplicates_sql(
+      &quote_table(schema, table),
+      key_cols,
+      limit,
+    ))
+  }
+}
+
+fn duplicates_sql(target: &str, key_cols: &[String], limit: usize) -> String {
+  let keys = key_cols
+    .iter()
+    .map(|c| quote_ident(c))
+    .collect::<Vec<_>>()
+    .join(", ");
+  format!(
+    "SELECT {keys}, COUNT(*) AS `count` FROM {target} GROUP BY {keys} \
+     HAVING COUNT(*) > 1 ORDER BY `count` DESC LIMIT {limit}"
+  )
+}
+
+/// Above this many tables [`MySqlConnection::row_counts`] switches to
+/// estimates.
+const EXACT_ROW_COUNT_TABLES: usize = 10;
+
+/// One `COUNT(*)` per table, tagged with the table name.
+fn union_count_sql(schema: &str, tables: &[String]) -> String {
+  tables
+    .iter()
+    .map(|t| {
+      format!(
+        "select '{}', count(*) from {}",
+        escape_literal(t),
+        quote_table(Some(schema), t)
+      )
+    })
+    .collect::<Vec<_>>()
+    .join(" union all ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::dialect::mysql::{duckling_test_db, duckling_test_table, local_connection};
+
+  #[test]
+  fn test_partition_pruning_from_explain() {
+    let all: Vec<String> = ["p0", "p1", "p2", "p3"].map(String::from).to_vec();
+    let explain = vec![Some("p1,p3".to_string())];
+    let res = PartitionPruningResult::from_explain(&explain, &all);
+    assert_eq!(res.total_partitions, 4);
+    assert_eq!(res.partitions_accessed, vec!["p1", "p3"]);
+    assert_eq!(res.partitions_skipped, vec!["p0", "p2"]);
+  }
+
+  #[test]
+  fn test_fragmentation_ratio() {
+    assert_eq!(fragmentation_ratio(0, 0, 0), 0.0);
+    let ratio = fragmentation_ratio(4 << 20, 12 << 20, 4 << 20);
+    assert!((0.0..=1.0).contains(&ratio));
+    assert_eq!(ratio, 0.25);
+    assert_eq!(fragmentation_ratio(1 << 30, 16 << 10, 0), 1.0);
+  }
+
+  #[test]
+  fn test_union_count_sql() {
+    let tables: Vec<String> = ["a", "it's"].map(String::from).to_vec();
+    assert_eq!(
+      union_count_sql("shop", &tables),
+      "select 'a', count(*) from `shop`.`a` union all \
+       select 'it''s', count(*) from `shop`.`it's`"
+    );
+  }
+
+  #[test]
+  fn test_duplicates_sql() {
+    let keys: Vec<String> = ["email", "tenant id"].map(String::from).to_vec();
+    assert_eq!(
+      duplicates_sql("`shop`.`users`", &keys, 10),
+      "SELECT `email`, `tenant id`, COUNT(*) AS `count` FROM `shop`.`users` \
+       GROUP BY `email`, `tenant id` HAVING COUNT(*) > 1 ORDER BY `count` DESC LIMIT 10"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_run_bounded() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let tables: Vec<String> = (1..=5).map(|i| format!("t{i}")).collect();
+    let results = run_bounded(tables, 2, |table| {
+      let running = running.clone();
+      let peak = peak.clone();
+      async move {
+        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+        peak.fetch_max(now, Ordering::SeqCst);
+        tokio::task::yield_now().await;
+        running.fetch_sub(1, Ordering::SeqCst);
+        if table == "t3" {
+          anyhow::bail!("Table 't3' is marked as crashed");
+        }
+        Ok(())
+      }
+    })
+    .await;
+
+    assert_eq!(results.len(), 5);
+    assert!(peak.load(Ordering::SeqCst) <= 2);
+    assert!(results["t1"]);
+    assert!(!results["t3"]);
+    assert_eq!(results.values().filter(|ok| **ok).count(), 4);
+  }
+
+  #[test]
+  fn test_alter_estimate() {
+    let size = 500 * 1024 * 1024;
+    let est = AlterEstimate::new(1_000_000, size, "ADD COLUMN note varchar(32)", &[]);
+    assert!(est.instant_compatible);
+    assert_eq!(est.estimated_seconds, 0.0);
+
+    let est = AlterEstimate::new(1_000_000, size, "ADD COLUMN flag int NOT NULL", &[]);
+    assert!(est.requires_rebuild);
+    assert!(!est.instant_compatible);
+    assert_eq!(est.estimated_seconds, 10.0);
+
+    let est = AlterEstimate::new(1_000_000, size, "ADD INDEX idx_a (a, b)", &[]);
+    assert!(!est.requires_rebuild && !est.instant_compatible);
+
+    let virtual_columns = vec!["full_name".to_string()];
+    let est = AlterEstimate::new(
+      0,
+      size,
+      "DROP COLUMN `full_name`, RENAME COLUMN a TO b",
+      &virtual_columns,
+    );
+    assert!(est.instant_compatible);
+    let est = AlterEstimate::new(0, size, "DROP COLUMN name, ADD KEY (a)", &virtual_columns);
+    assert!(est.requires_rebuild);
+
+    assert_eq!(
+      split_alter_clauses("ADD c decimal(10,2), ADD d enum('a,b')"),
+      ["ADD c decimal(10,2)", "ADD d enum('a,b')"]
+    );
+  }
+
+  #[test]
+  fn test_osc_compatibility() {
+    let with_triggers = OscCompatibility::new(true, true, false, true, true);
+    assert!(!with_triggers.compatible_with_pt_osc);
+    assert!(!with_triggers.compatible_with_gh_ost);
+
+    let with_fks = OscCompatibility::new(true, false, true, true, true);
+    assert!(with_fks.compatible_with_pt_osc);
+    assert!(!with_fks.compatible_with_gh_ost);
+
+    let statement_based = OscCompatibility::new(true, false, false, true, false);
+    assert!(statement_based.compatible_with_pt_osc);
+    assert!(!statement_based.compatible_with_gh_ost);
+  }
+
+  #[test]
+  fn test_maintenance_sql() {
+    assert_eq!(
+      MaintenanceOp::Analyze.sql(Some("shop"), "order`s"),
+      "ANALYZE TABLE `shop`.`order``s`"
+    );
+    assert_eq!(MaintenanceOp::Optimize.sql(None, "t"), "OPTIMIZE TABLE `t`");
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_maintain_table() {
+    let conn = local_connection();
+    let res = conn
+      .maintain_table(Some("mysql"), "user", MaintenanceOp::Analyze)
+      .await
+      .unwrap();
+    assert!(res.batch.num_rows() > 0);
+    assert_eq!(res.batch.schema().field(1).name(), "Op");
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_table_freshness() {
+    let conn = local_connection();
+    let mut c = duckling_test_db(&conn);
+    c.query_drop("create table if not exists duckling_test.freshness (id int) engine=MyISAM")
+      .unwrap();
+    c.query_drop("insert into duckling_test.freshness values (1)")
+      .unwrap();
+    let updated = conn
+      .table_freshness("duckling_test", "freshness")
+      .await
+      .unwrap()
+      .unwrap();
+    assert!(Utc::now() - updated < chrono::Duration::minutes(5));
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_table_timestamps() {
+    let conn = local_connection();
+    duckling_test_table(&conn, "stamped", "(id int) engine=InnoDB");
+    let (created, _) = conn
+      .table_timestamps("duckling_test", "stamped")
+      .await
+      .unwrap();
+    let created = created.unwrap();
+    assert!(Utc::now() - created < chrono::Duration::minutes(5));
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_column_histogram() {
+    let conn = local_connection();
+    let mut c = duckling_test_db(&conn);
+    c.query_drop("create table if not exists duckling_test.histogram (id int, v int)")
+      .unwrap();
+    c.query_drop("insert into duckling_test.histogram values (1, 1), (2, 2), (3, 2)")
+      .unwrap();
+    c.query_drop("analyze table duckling_test.histogram update histogram on v with 4 buckets")
+      .unwrap();
+    let histogram = conn
+      .column_histogram("duckling_test", "histogram", "v")
+      .await
+      .unwrap()
+      .unwrap();
+    assert!(histogram["buckets"].is_array());
+    assert!(
+      conn
+        .column_histogram("duckling_test", "histogram", "id")
+        .await
+        .unwrap()
+        .is_none()
+    );
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_id_health() {
+    let conn = local_connection();
+    let mut c = duckling_test_table(&conn, "gaps", "(id int primary key, name varchar(8))");
+    c.query_drop("insert into duckling_test.gaps values (1, 'a'), (2, 'b'), (3, 'c'), (10, 'd')")
+      .unwrap();
+    c.query_drop("delete from duckling_test.gaps where id in (2, 3)")
+      .unwrap();
+    let (max, count) = conn
+      .id_health(Some("duckling_test"), "gaps", "id")
+      .await
+      .unwrap();
+    assert_eq!((max, count), (10, 2));
+    assert!(
+      conn
+        .id_health(Some("duckling_test"), "gaps", "name")
+        .await
+        .is_err()
+    );
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_group_count() {
+    use arrow::array::Int64Array;
+
+    let conn = local_connection();
+    let res = conn
+      .group_count(Some("information_schema"), "columns", "DATA_TYPE")
+      .await
+      .unwrap();
+    let counts = res
+      .batch
+      .column(1)
+      .as_any()
+      .downcast_ref::<Int64Array>()
+      .unwrap();
+    let sum: i64 = counts.iter().flatten().sum();
+    let mut c = conn.get_conn().unwrap();
+    let total: i64 = c
+      .query_first("select count(*) from information_schema.columns")
+      .unwrap()
+      .unwrap();
+    assert_eq!(sum, total);
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_find_duplicates() {
+    use arrow::array::{Int64Array, StringArray};
+
+    let conn = local_connection();
+    let mut c = duckling_test_table(&conn, "dups", "(id int, email varchar(32))");
+    c.query_drop(
+      "insert into duckling_test.dups values \
+       (1, 'a@x'), (2, 'b@x'), (3, 'a@x'), (4, 'c@x'), (5, 'a@x'), (6, 'b@x')",
+    )
+    .unwrap();
+
+    let keys = ["email".to_string()];
+    let res = conn
+      .find_duplicates(Some("duckling_test"), "dups", &keys, 10)
+      .await
+      .unwrap();
+    assert_eq!(res.batch.num_rows(), 2);
+    let emails = res
+      .batch
+      .column(0)
+      .as_any()
+      .downcast_ref::<StringArray>()
+      .unwrap();
+    let counts = res
+      .batch
+      .column(1)
+      .as_any()
+      .downcast_ref::<Int64Array>()
+      .unwrap();
+    assert_eq!((emails.value(0), counts.value(0)), ("a@x", 3));
+    assert_eq!((emails.value(1), counts.value(1)), ("b@x", 2));
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_query_columns() {
+    let conn = local_connection();
+    let mut c = duckling_test_table(
+      &conn,
+      "wide",
+      "(a int, b varchar(8), c int, d double, e text)",
+    );
+    c.query_drop(
+      "insert into duckling_test.wide values (1, 'x', 2, 3.0, 'y'), (4, 'z', 5, 6.0, 'w')",
+    )
+    .unwrap();
+
+    let columns: Vec<String> = ["d", "b"].map(String::from).to_vec();
+    let res = conn
+      .query_columns(Some("duckling_test"), "wide", &columns, 1)
+      .await
+      .unwrap();
+    let schema = res.batch.schema();
+    let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(names, ["d", "b"]);
+    assert_eq!(res.batch.num_rows(), 1);
+
+    let unknown: Vec<String> = ["a", "nope"].map(String::from).to_vec();
+    let Err(err) = conn
+      .query_columns(Some("duckling_test"), "wide", &unknown, 1)
+      .await
+    else {
+      panic!("unknown column accepted");
+    };
+    assert!(err.to_string().contains("nope"));
+  }
+
+  #[tokio::test]
+  #[ignore = "requires a running MySQL server"]
+  async fn test_row_counts() {
+    let conn = local_connection();
+    let mut c = duckling_test_db(&conn);
+    for (i, table) in ["counts_a", "counts_b", "counts_c"].iter().enumerate() {
+      c.query_drop(format!("drop table if exists duckling_test.{table}"))
+        .unwrap();
+      c.query_drop(format!("create table duckling_test.{table} (id int)"))
+        .unwrap();
+      for id in 0..i {
+        c.query_drop(format!("insert into duckling_test.{table} values ({id})"))
+          .unwrap();
+      }
+    }
+    let tables: Vec<String> = ["counts_a", "counts_b", "counts_c", "missing"]
+      .map(String::from)
+      .to_vec();
+    let counts = conn.row_counts("duckling_test", &tables).await.unwrap();
+    assert_eq!(counts.len(), 3);
+    assert_eq!(counts["counts_a"], 0);
+    assert_eq!(counts["counts_b"], 1);
+    assert_eq!(counts["counts_c"], 2);
+  }
+}
@@ -132,6 +132,7 @@ impl PostgresConnection {
         table_type: row.get::<_, String>(3),
         r#type: row.get::<_, String>(4),
         size: None,
+        comment: None,
       });
     }
     Ok(tables)
@@ -158,6 +159,8 @@ impl PostgresConnection {
       titles.push(Title {
         name: col.name().to_string(),
         r#type: col.type_().name().to_string(),
+        is_json: false,
+        semantic_type: None,
       });
       let typ = col_to_arrow_type(col);
       let field = Field::new(col.name(), typ, true);
@@ -166,6 +166,7 @@ impl SqliteConnection {
         database: String::new(),
         table: table_name.clone(),
         columns,
+        comments: Default::default(),
       });
     }
     Ok(metadata)
@@ -206,6 +207,8 @@ impl SqliteConnection {
       titles.push(Title {
         name: col.name().to_string(),
         r#type: col.decl_type().unwrap_or_default().to_string(),
+        is_json: false,
+        semantic_type: None,
       });
       let typ = db_to_arrow_type(col.decl_type());
       let field = Field::new(col.name(), typ, true);
@@ -243,6 +246,7 @@ impl SqliteConnection {
         r#type: row.get(2)?,
         schema: None,
         size: None,
+        comment: None,
       });
     }
     Ok(tables)
@@ -1,12 +1,14 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
+use std::io::Write;
 use std::path::Path;
 
-use arrow::csv::WriterBuilder;
+use arrow::csv::{Writer, WriterBuilder};
 use arrow::datatypes::SchemaRef;
 use arrow::ipc::writer::StreamWriter;
 use arrow::json::ReaderBuilder;
 use arrow::record_batch::RecordBatch;
+use arrow::row::{RowConverter, Rows, SortField};
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
@@ -52,6 +54,23 @@ impl TreeNode {
 pub struct Title {
   pub name: String,
   pub r#type: String,
+  /// Text column whose values hold JSON documents, for pretty-printing.
+  #[serde(default)]
+  pub is_json: bool,
+  /// What the text values are, judged from a sample, for rendering links
+  /// and special formatting.
+  #[serde(default)]
+  pub semantic_type: Option<SemanticType>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SemanticType {
+  Email,
+  Url,
+  Uuid,
+  Ip,
+  Phone,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -59,6 +78,17 @@ pub struct Metadata {
   pub database: String,
   pub table: String,
   pub columns: Vec<(String, String)>,
+  /// Column name to its comment, for the columns that have one.
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  pub comments: HashMap<String, String>,
+}
+
+/// Counters reported for a statement that returns no rows.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecResult {
+  pub affected_rows: u64,
+  /// Auto-increment id generated by the statement, or 0 if none was.
+  pub last_insert_id: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,6 +99,8 @@ pub struct Table {
   pub schema: Option<String>,
   pub r#type: String,
   pub size: Option<u64>,
+  #[serde(default)]
+  pub comment: Option<String>,
 }
 
 pub struct RawArrowData {
@@ -90,6 +122,91 @@ impl RawArrowData {
   }
 }
 
+/// Row-level difference between two results of the same query.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchDiff {
+  /// Rows of the new batch whose key is not in the old one.
+  pub added: Vec<usize>,
+  /// Rows of the old batch whose key is not in the new one.
+  pub removed: Vec<usize>,
+  /// `(old, new)` rows with the same key but different values.
+  pub changed: Vec<(usize, usize)>,
+}
+
+/// Encode `columns` of `batch` as comparable rows.
+fn encode_rows(batch: &RecordBatch, columns: &[usize]) -> anyhow::Result<Rows> {
+  let schema = batch.schema();
+  let fields = columns
+    .iter()
+    .map(|&i| SortField::new(schema.field(i).data_type().clone()))
+    .collect();
+  let arrays: Vec<_> = columns.iter().map(|&i| batch.column(i).clone()).collect();
+  Ok(RowConverter::new(fields)?.convert_columns(&arrays)?)
+}
+
+/// Rows added, removed and changed from `old` to `new`, matched on
+/// `key_cols`, which must be unique in each batch.
+pub fn batch_diff(
+  old: &RawArrowData,
+  new: &RawArrowData,
+  key_cols: &[String],
+) -> anyhow::Result<BatchDiff> {
+  let schema = old.batch.schema();
+  let new_schema = new.batch.schema();
+  let same_fields = schema.fields().len() == new_schema.fields().len()
+    && schema
+      .fields()
+      .iter()
+      .zip(new_schema.fields())
+      .all(|(a, b)| a.name() == b.name() && a.data_type() == b.data_type());
+  if !same_fields {
+    anyhow::bail!("batches have different schemas");
+  }
+  if key_cols.is_empty() {
+    anyhow::bail!("at least one key column is required");
+  }
+  let keys = key_cols
+    .iter()
+    .map(|name| {
+      schema
+        .index_of(name)
+        .map_err(|_| anyhow::anyhow!("key column `{name}` not found"))
+    })
+    .collect::<anyhow::Result<Vec<_>>>()?;
+  let all: Vec<usize> = (0..schema.fields().len()).collect();
+
+  let (old_keys, new_keys) = (
+    encode_rows(&old.batch, &keys)?,
+    encode_rows(&new.batch, &keys)?,
+  );
+  let (old_rows, new_rows) = (
+    encode_rows(&old.batch, &all)?,
+    encode_rows(&new.batch, &all)?,
+  );
+
+  let mut old_index = HashMap::with_capacity(old_keys.num_rows());
+  for (i, key) in old_keys.iter().enumerate() {
+    if old_index.insert(key, i).is_some() {
+      anyhow::bail!("duplicate key in old batch at row {i}");
+    }
+  }
+  let mut diff = BatchDiff::default();
+  let mut seen = HashMap::with_capacity(new_keys.num_rows());
+  for (j, key) in new_keys.iter().enumerate() {
+    if seen.insert(key, j).is_some() {
+      anyhow::bail!("duplicate key in new batch at row {j}");
+    }
+    match old_index.remove(&key) {
+      Some(i) if old_rows.row(i) != new_rows.row(j) => diff.changed.push((i, j)),
+      Some(_) => {}
+      None => diff.added.push(j),
+    }
+  }
+  diff.removed = old_index.into_values().collect();
+  diff.removed.sort_unstable();
+  Ok(diff)
+}
+
 pub fn get_file_name<P: AsRef<Path>>(path: P) -> String {
   path
     .as_ref()
@@ -122,7 +239,7 @@ pub fn build_tree(tables: Vec<Table>) -> Vec<TreeNode> {
       schema: t.schema,
       children: None,
       size: t.size,
-      comment: None,
+      comment: t.comment,
     });
   }
   for (key, nodes) in &tree {
@@ -154,10 +271,18 @@ pub fn build_tree(tables: Vec<Table>) -> Vec<TreeNode> {
   databases
 }
 
-pub fn write_csv(file: &str, batch: &RecordBatch) -> anyhow::Result<()> {
-  let file = File::create(file)?;
-  let builder = WriterBuilder::new().with_header(true);
-  let mut writer = builder.build(file);
+/// CSV writer with a header row that renders NULL as `null_string`; empty
+/// strings stay empty.
+pub fn csv_writer<W: Write>(writer: W, null_string: &str) -> Writer<W> {
+  WriterBuilder::new()
+    .with_header(true)
+    .with_null(null_string.to_string())
+    .build(writer)
+}
+
+/// Write `batch` to `file` with [`csv_writer`].
+pub fn write_csv(file: &str, batch: &RecordBatch, null_string: &str) -> anyhow::Result<()> {
+  let mut writer = csv_writer(File::create(file)?, null_string);
   writer.write(batch)?;
   Ok(())
 }
@@ -179,3 +304,65 @@ pub fn serialize_preview(record: &RecordBatch) -> Result<Vec<u8>, arrow::error::
   writer.write(record)?;
   writer.into_inner()
 }
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use arrow::array::{Int64Array, StringArray};
+  use arrow::datatypes::{DataType, Field, Schema};
+
+  use super::*;
+
+  fn data(ids: Vec<i64>, names: Vec<&str>) -> RawArrowData {
+    let schema = Schema::new(vec![
+      Field::new("id", DataType::Int64, false),
+      Field::new("name", DataType::Utf8, true),
+    ]);
+    let batch = RecordBatch::try_new(
+      Arc::new(schema),
+      vec![
+        Arc::new(Int64Array::from(ids)),
+        Arc::new(StringArray::from(names)),
+      ],
+    )
+    .unwrap();
+    RawArrowData::from_batch(batch)
+  }
+
+  #[test]
+  fn test_batch_diff() {
+    let old = data(vec![1, 2, 3], vec!["a", "b", "c"]);
+    let new = data(vec![1, 2, 3, 4], vec!["a", "B", "c", "d"]);
+    let key = ["id".to_string()];
+    let diff = batch_diff(&old, &new, &key).unwrap();
+    assert_eq!(diff.added, [3]);
+    assert_eq!(diff.changed, [(1, 1)]);
+    assert!(diff.removed.is_empty());
+
+    let diff = batch_diff(&new, &old, &key).unwrap();
+    assert_eq!(diff.removed, [3]);
+
+    assert!(batch_diff(&old, &new, &["missing".to_string()]).is_err());
+    let dup = data(vec![1, 1], vec!["a", "b"]);
+    assert!(batch_diff(&dup, &new, &key).is_err());
+  }
+
+  #[test]
+  fn test_build_tree_comment() {
+    let table = Table {
+      table_name: "orders".to_string(),
+      table_type: "BASE TABLE".to_string(),
+      db_name: "shop".to_string(),
+      schema: None,
+      r#type: "table".to_string(),
+      size: None,
+      comment: Some("customer orders".to_string()),
+    };
+    let tree = build_tree(vec![table]);
+    let tables = tree[0].children.as_ref().unwrap();
+    let orders = &tables[0].children.as_ref().unwrap()[0];
+    assert_eq!(orders.name, "orders");
+    assert_eq!(orders.comment.as_deref(), Some("customer orders"));
+  }
+}
@@ -91,6 +91,7 @@ pub async fn get_dialect(
       username: username.unwrap_or_default(),
       password: password.unwrap_or_default(),
       database,
+      ..MySqlConnection::default()
     })),
     "postgres" => Some(Box::new(PostgresConnection {
       host: host.unwrap(),